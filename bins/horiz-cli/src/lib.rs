@@ -0,0 +1,206 @@
+//! Command-line parsing and commands for `horiz-cli`.
+//!
+//! The binary only parses its arguments and calls [`run`], so the commands
+//! can be exercised without spawning a process.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use horizcoin_crypto::{parse_address, PrivateKey};
+use horizcoin_primitives::{Amount, HorizError, TxId, ValidationContext};
+use horizcoin_tx::{Transaction, TransactionBuilder, TxInput, TxOutput};
+
+/// `HorizCoin` command-line interface.
+#[derive(Debug, Parser)]
+#[command(name = "horiz-cli", version, about)]
+pub struct Cli {
+    /// The command to run.
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// Top-level commands.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Wallet management commands.
+    #[command(subcommand)]
+    Wallet(WalletCommand),
+    /// Transaction commands.
+    #[command(subcommand)]
+    Tx(TxCommand),
+}
+
+/// `wallet` subcommands.
+#[derive(Debug, Subcommand)]
+pub enum WalletCommand {
+    /// Generates a new key and prints its address.
+    New {
+        /// Writes the key, `WIF`-encoded, to this file, which must not
+        /// exist yet.
+        #[arg(long, value_name = "KEYFILE")]
+        out: Option<PathBuf>,
+    },
+    /// Prints the address of the key in a `WIF` key file.
+    Address {
+        /// File holding a `WIF`-encoded key.
+        keyfile: PathBuf,
+    },
+}
+
+/// `tx` subcommands.
+#[derive(Debug, Subcommand)]
+pub enum TxCommand {
+    /// Builds an unsigned transaction and prints it hex-encoded.
+    ///
+    /// Inputs carry no key yet; `tx sign` assigns its key to them.
+    Build {
+        /// Recipient address.
+        #[arg(long)]
+        to: String,
+        /// Amount to pay, in base units.
+        #[arg(long, value_parser = parse_amount)]
+        amount: Amount,
+        /// Output to spend, as `txid:index`; may be repeated.
+        #[arg(long = "input", value_name = "TXID:INDEX", required = true, value_parser = parse_outpoint)]
+        inputs: Vec<(TxId, u32)>,
+        /// Optional memo.
+        #[arg(long)]
+        memo: Option<String>,
+    },
+    /// Signs every input of a hex-encoded transaction and prints the
+    /// result hex-encoded.
+    Sign {
+        /// Signing key, `WIF`-encoded.
+        #[arg(long)]
+        key: String,
+        /// Transaction to sign, hex-encoded.
+        #[arg(long)]
+        tx: String,
+    },
+}
+
+/// Runs `cli`, writing its output to `out`.
+pub fn run(cli: Cli, out: &mut impl Write) -> anyhow::Result<()> {
+    match cli.command {
+        Command::Wallet(command) => run_wallet(command, out),
+        Command::Tx(command) => run_tx(command, out),
+    }
+}
+
+fn run_wallet(command: WalletCommand, out: &mut impl Write) -> anyhow::Result<()> {
+    match command {
+        WalletCommand::New { out: keyfile } => {
+            let key = PrivateKey::generate();
+            if let Some(path) = keyfile {
+                write_keyfile(&path, &key)?;
+            }
+            writeln!(out, "{}", key.public_key().to_address())?;
+        }
+        WalletCommand::Address { keyfile } => {
+            let key = read_keyfile(&keyfile)?;
+            writeln!(out, "{}", key.public_key().to_address())?;
+        }
+    }
+    Ok(())
+}
+
+fn run_tx(command: TxCommand, out: &mut impl Write) -> anyhow::Result<()> {
+    let tx = match command {
+        TxCommand::Build {
+            to,
+            amount,
+            inputs,
+            memo,
+        } => build_tx(&to, amount, &inputs, memo)?,
+        TxCommand::Sign { key, tx } => {
+            let key = PrivateKey::from_wif(key.trim())?;
+            sign_tx(decode_tx(&tx)?, &key)?
+        }
+    };
+    writeln!(out, "{}", hex::encode(horizcoin_codec::encode(&tx)?))?;
+    Ok(())
+}
+
+fn build_tx(
+    to: &str,
+    amount: Amount,
+    inputs: &[(TxId, u32)],
+    memo: Option<String>,
+) -> Result<Transaction, HorizError> {
+    parse_address(to)?;
+    let mut builder = TransactionBuilder::new().output(TxOutput::new(amount, to));
+    for &(prev_tx, index) in inputs {
+        builder = builder.input(TxInput::multisig(prev_tx, index));
+    }
+    if let Some(memo) = memo {
+        builder = builder.memo(memo);
+    }
+    let tx = builder.build();
+    tx.validate(&ValidationContext::default())?;
+    Ok(tx)
+}
+
+/// Assigns `key` to every keyless input of `tx`, then signs them all.
+fn sign_tx(mut tx: Transaction, key: &PrivateKey) -> Result<Transaction, HorizError> {
+    if tx.is_coinbase() {
+        return Err(HorizError::InvalidTransaction(
+            "a coinbase has nothing to sign".into(),
+        ));
+    }
+    let public_key = key.public_key();
+    for input in &mut tx.inputs {
+        input.public_key.get_or_insert(public_key);
+    }
+    for index in 0..tx.inputs.len() {
+        tx.sign_input(index, key)?;
+    }
+    Ok(tx)
+}
+
+fn decode_tx(hex_tx: &str) -> Result<Transaction, HorizError> {
+    let bytes = hex::decode(hex_tx.trim())
+        .map_err(|e| HorizError::Serialization(format!("invalid transaction hex: {e}")))?;
+    horizcoin_codec::decode_exact(&bytes)
+}
+
+fn parse_amount(s: &str) -> Result<Amount, String> {
+    s.parse()
+        .map(Amount::new)
+        .map_err(|e| format!("invalid amount {s:?}: {e}"))
+}
+
+fn parse_outpoint(s: &str) -> Result<(TxId, u32), String> {
+    let (txid, index) = s
+        .rsplit_once(':')
+        .ok_or_else(|| format!("expected TXID:INDEX, got {s:?}"))?;
+    let txid = TxId::from_hex(txid).map_err(|e| e.to_string())?;
+    let index = index
+        .parse()
+        .map_err(|e| format!("invalid output index {index:?}: {e}"))?;
+    Ok((txid, index))
+}
+
+/// Loads the `WIF`-encoded key stored in `path`.
+pub fn read_keyfile(path: &Path) -> anyhow::Result<PrivateKey> {
+    let wif = std::fs::read_to_string(path)
+        .with_context(|| format!("reading key file {}", path.display()))?;
+    PrivateKey::from_wif(wif.trim())
+        .with_context(|| format!("decoding key file {}", path.display()))
+}
+
+/// Creates `path` holding `key` in `WIF`, readable only by its owner where
+/// the platform allows.
+fn write_keyfile(path: &Path, key: &PrivateKey) -> anyhow::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("creating key file {}", path.display()))?;
+    writeln!(file, "{}", key.to_wif())
+        .with_context(|| format!("writing key file {}", path.display()))
+}
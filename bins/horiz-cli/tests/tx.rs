@@ -0,0 +1,75 @@
+//! Exercises the `tx` commands through argument parsing.
+
+use clap::Parser;
+use horiz_cli::{run, Cli};
+use horizcoin_crypto::PrivateKey;
+use horizcoin_primitives::{Amount, HorizError};
+use horizcoin_tx::Transaction;
+
+const PREV_TX: &str = "8eb85c39e1f1b0e6b0a6e8d5cf5e6f4dd8a5a4b7f6c2d1e0f9a8b7c6d5e46a17";
+
+fn run_args(args: &[&str]) -> anyhow::Result<String> {
+    let cli = Cli::try_parse_from(std::iter::once("horiz-cli").chain(args.iter().copied()))?;
+    let mut out = Vec::new();
+    run(cli, &mut out)?;
+    Ok(String::from_utf8(out)?.trim().to_owned())
+}
+
+fn decode(hex_tx: &str) -> Transaction {
+    horizcoin_codec::decode_exact(&hex::decode(hex_tx).unwrap()).unwrap()
+}
+
+#[test]
+fn build_then_sign() {
+    let key = PrivateKey::generate();
+    let to = PrivateKey::generate().public_key().to_address();
+    let input = format!("{PREV_TX}:1");
+    let unsigned = run_args(&[
+        "tx", "build", "--to", &to, "--amount", "1500", "--input", &input,
+    ])
+    .unwrap();
+
+    let tx = decode(&unsigned);
+    assert_eq!(tx.inputs.len(), 1);
+    assert_eq!(tx.inputs[0].output_index, 1);
+    assert_eq!(tx.outputs[0].amount, Amount::new(1_500));
+    assert_eq!(tx.outputs[0].address, to);
+    assert!(tx.inputs[0].signature.is_empty());
+
+    let signed = run_args(&["tx", "sign", "--key", &key.to_wif(), "--tx", &unsigned]).unwrap();
+    let tx = decode(&signed);
+    assert_eq!(tx.inputs[0].public_key, Some(key.public_key()));
+    tx.verify_signatures().unwrap();
+
+    // Inputs already claimed by one key cannot be signed by another.
+    let other = PrivateKey::generate().to_wif();
+    assert!(run_args(&["tx", "sign", "--key", &other, "--tx", &signed]).is_err());
+}
+
+#[test]
+fn build_rejects_invalid_input() {
+    let input = format!("{PREV_TX}:0");
+    let err = run_args(&[
+        "tx",
+        "build",
+        "--to",
+        "not-an-address",
+        "--amount",
+        "1500",
+        "--input",
+        &input,
+    ])
+    .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<HorizError>(),
+        Some(HorizError::InvalidAddress(_))
+    ));
+
+    let to = PrivateKey::generate().public_key().to_address();
+    assert!(run_args(&["tx", "build", "--to", &to, "--amount", "1500"]).is_err());
+    assert!(
+        run_args(&["tx", "build", "--to", &to, "--amount", "1500", "--input", "nothex:0",])
+            .is_err()
+    );
+    assert!(run_args(&["tx", "sign", "--key", "bogus", "--tx", "00"]).is_err());
+}
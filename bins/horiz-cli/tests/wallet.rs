@@ -0,0 +1,35 @@
+//! Exercises the `wallet` commands through argument parsing.
+
+use clap::Parser;
+use horiz_cli::{read_keyfile, run, Cli};
+
+fn run_args(args: &[&str]) -> anyhow::Result<String> {
+    let cli = Cli::try_parse_from(std::iter::once("horiz-cli").chain(args.iter().copied()))?;
+    let mut out = Vec::new();
+    run(cli, &mut out)?;
+    Ok(String::from_utf8(out)?)
+}
+
+#[test]
+fn wallet_new_prints_an_address() {
+    let output = run_args(&["wallet", "new"]).unwrap();
+    assert!(output.starts_with("hz"), "unexpected output {output:?}");
+    assert_eq!(output.lines().count(), 1);
+}
+
+#[test]
+fn wallet_address_reads_back_the_key_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let keyfile = dir.path().join("key.wif");
+    let path = keyfile.to_str().unwrap();
+
+    let created = run_args(&["wallet", "new", "--out", path]).unwrap();
+    let key = read_keyfile(&keyfile).unwrap();
+    assert_eq!(created.trim(), key.public_key().to_address());
+    assert_eq!(run_args(&["wallet", "address", path]).unwrap(), created);
+
+    // An existing key file is never overwritten.
+    assert!(run_args(&["wallet", "new", "--out", path]).is_err());
+    assert!(run_args(&["wallet", "address", "/nonexistent/key.wif"]).is_err());
+    assert!(run_args(&["wallet"]).is_err());
+}
@@ -1,8 +1,36 @@
 //! `HorizCoin` node executable.
+//!
+//! Serves JSON-RPC over an in-memory chain that starts at the mainnet
+//! genesis block, with Prometheus metrics at `/metrics`. With `--version`
+//! it prints its version and exits.
 
-fn main() {
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use horizcoin_block::genesis_block;
+use horizcoin_crypto::Network;
+use horizcoin_rpc::ChainState;
+
+/// Port the JSON-RPC server listens on unless `RPC_PORT` says otherwise.
+const DEFAULT_RPC_PORT: u16 = 7332;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🌅 HorizCoin Node v{}", env!("CARGO_PKG_VERSION"));
-    println!("Starting HorizCoin blockchain node...");
-    println!("Node initialized successfully. Exiting for scaffolding phase.");
-    std::process::exit(0);
+    if std::env::args().skip(1).any(|arg| arg == "--version") {
+        return Ok(());
+    }
+
+    let port = match std::env::var("RPC_PORT") {
+        Ok(port) => port.parse()?,
+        Err(_) => DEFAULT_RPC_PORT,
+    };
+    let bind_addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let state = Arc::new(Mutex::new(ChainState::new(genesis_block(Network::Mainnet))));
+
+    println!("JSON-RPC listening on http://{bind_addr}");
+    axum::Server::bind(&bind_addr)
+        .serve(horizcoin_rpc::router(state).into_make_service())
+        .await?;
+    Ok(())
 }
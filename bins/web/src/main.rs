@@ -4,12 +4,18 @@
 //! Designed for deployment on GitHub Copilot Spaces to provide a public demo URL.
 
 use axum::{
+    extract::{Path, State},
     http::StatusCode,
     response::{Html, IntoResponse},
     routing::get,
-    Router,
+    Json, Router,
 };
-use std::net::SocketAddr;
+use horizcoin_block::genesis_block;
+use horizcoin_crypto::{parse_address, Network};
+use horizcoin_state::UtxoSet;
+use horizcoin_storage::MemoryStorage;
+use serde_json::json;
+use std::{net::SocketAddr, sync::Arc};
 use tracing::{info, warn};
 
 /// Main entry point for the HorizCoin web demo server
@@ -28,10 +34,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let bind_addr = SocketAddr::from(([0, 0, 0, 0], port));
 
-    // Build our application with the routes
-    let app = Router::new()
-        .route("/", get(root_handler))
-        .route("/healthz", get(health_handler));
+    // Seed an in-memory UTXO set with the mainnet genesis block
+    let mut utxos = UtxoSet::new(Arc::new(MemoryStorage::new()));
+    utxos.connect_block(&genesis_block(Network::Mainnet))?;
+
+    let app = app(Arc::new(utxos));
 
     info!(
         "HorizCoin Web Demo v{} starting on {}",
@@ -47,6 +54,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Build the application router, answering account queries from `utxos`
+fn app(utxos: Arc<UtxoSet>) -> Router {
+    Router::new()
+        .route("/", get(root_handler))
+        .route("/healthz", get(health_handler))
+        .route("/api/account/:address/balance", get(balance_handler))
+        .with_state(utxos)
+}
+
 /// Handle requests to the root path
 async fn root_handler() -> impl IntoResponse {
     let html = format!(
@@ -136,3 +152,71 @@ async fn root_handler() -> impl IntoResponse {
 async fn health_handler() -> impl IntoResponse {
     (StatusCode::OK, "ok")
 }
+
+/// Handle account balance queries
+async fn balance_handler(
+    State(utxos): State<Arc<UtxoSet>>,
+    Path(address): Path<String>,
+) -> impl IntoResponse {
+    if let Err(e) = parse_address(&address) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": e.to_string() })),
+        );
+    }
+    match utxos.balance_of(&address) {
+        Ok(balance) => (
+            StatusCode::OK,
+            Json(json!({ "address": address, "balance": balance })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": e.to_string() })),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request};
+    use horizcoin_primitives::Amount;
+    use horizcoin_tx::{Transaction, TxOutput};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    async fn get_json(app: Router, uri: &str) -> (StatusCode, Value) {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn balance_reflects_the_utxo_set() {
+        let address = horizcoin_crypto::PrivateKey::generate()
+            .public_key()
+            .to_address();
+        let mut utxos = UtxoSet::new(Arc::new(MemoryStorage::new()));
+        utxos
+            .apply(&Transaction::coinbase(
+                vec![
+                    TxOutput::new(Amount::new(1_200), address.clone()),
+                    TxOutput::new(Amount::new(300), address.clone()),
+                ],
+                None,
+            ))
+            .unwrap();
+        let app = app(Arc::new(utxos));
+
+        let uri = format!("/api/account/{address}/balance");
+        let (status, body) = get_json(app.clone(), &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "address": address, "balance": 1_500 }));
+
+        let (status, body) = get_json(app, "/api/account/not-an-address/balance").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("invalid address"));
+    }
+}
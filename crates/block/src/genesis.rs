@@ -0,0 +1,57 @@
+//! The first block of each network.
+
+use horizcoin_crypto::{encode_address, hash160, Network};
+use horizcoin_primitives::constants::{GENESIS_TIMESTAMP, INITIAL_BLOCK_REWARD};
+use horizcoin_primitives::{BlockId, Hash};
+use horizcoin_tx::{Transaction, TxOutput};
+
+use crate::{Block, BlockHeader};
+
+/// Memo of the genesis coinbase.
+pub const GENESIS_MEMO: &str = "HorizCoin genesis";
+
+/// Returns the genesis block of `network`.
+///
+/// Its coinbase pays [`INITIAL_BLOCK_REWARD`] to an address whose program
+/// is the `HASH160` of [`GENESIS_MEMO`] rather than of a public key, so no
+/// key can spend it. Only the address prefix differs between networks.
+#[must_use]
+pub fn genesis_block(network: Network) -> Block {
+    let address = encode_address(network, &hash160(GENESIS_MEMO.as_bytes()));
+    let coinbase = Transaction::coinbase(
+        vec![TxOutput::new(INITIAL_BLOCK_REWARD, address)],
+        Some(GENESIS_MEMO.into()),
+    );
+    let header = BlockHeader {
+        prev_block_id: BlockId::ZERO,
+        merkle_root: Hash::ZERO,
+        timestamp: GENESIS_TIMESTAMP,
+        height: 0,
+        difficulty: 1,
+        nonce: 0,
+    };
+    Block::new(header, vec![coinbase]).expect("genesis block encodes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn genesis_is_pinned() {
+        let genesis = genesis_block(Network::Mainnet);
+        assert!(genesis.verify_merkle_root().is_ok());
+        assert!(genesis.transactions[0].is_coinbase());
+        assert_eq!(genesis, genesis_block(Network::Mainnet));
+        assert_eq!(
+            genesis.block_id().unwrap().to_hex(),
+            "5c63289a3706057bacace257681926ff4e11491337e9959e30ef0a3fcbfca898"
+        );
+
+        let ids: Vec<_> = Network::ALL
+            .into_iter()
+            .map(|network| genesis_block(network).block_id().unwrap())
+            .collect();
+        assert!(ids[0] != ids[1] && ids[1] != ids[2] && ids[0] != ids[2]);
+    }
+}
@@ -3,10 +3,297 @@
 //! This crate defines block structure and validation logic including
 //! timestamp skew limits for the `HorizCoin` blockchain.
 
+mod genesis;
+mod validation;
+
+pub use genesis::{genesis_block, GENESIS_MEMO};
+pub use validation::{median_time_past, validate_block_basic, validation_context};
+
+use std::collections::HashSet;
+
+use horizcoin_crypto::double_sha256;
+use horizcoin_merkle::MerkleTree;
+use horizcoin_primitives::{BlockId, Hash, HorizError, TxId, ValidationContext};
+use horizcoin_tx::Transaction;
+use serde::{Deserialize, Serialize};
+
+/// The part of a block that is hashed and sealed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    /// Identifier of the parent block, all zeros for genesis.
+    pub prev_block_id: BlockId,
+    /// Merkle root of the block's transaction ids.
+    pub merkle_root: Hash,
+    /// Unix time in seconds at which the block was produced.
+    pub timestamp: u64,
+    /// Number of blocks before this one.
+    pub height: u64,
+    /// Proof-of-work difficulty, a multiple of the easiest target; unused
+    /// by engines without proof of work.
+    pub difficulty: u32,
+    /// Free value a producer may vary to change the block id.
+    pub nonce: u64,
+}
+
+impl BlockHeader {
+    /// Returns the identifier of the block: the double `SHA-256` of the
+    /// header's encoding.
+    pub fn block_id(&self) -> Result<BlockId, HorizError> {
+        horizcoin_codec::encode(self).map(|bytes| double_sha256(&bytes).into())
+    }
+}
+
+/// A header and the transactions it commits to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Block {
+    /// The header; its `merkle_root` commits to `transactions`.
+    pub header: BlockHeader,
+    /// The transactions, coinbase first.
+    pub transactions: Vec<Transaction>,
+}
+
+impl Block {
+    /// Creates a block of `transactions` under `header`, whose
+    /// `merkle_root` is replaced by theirs.
+    pub fn new(
+        mut header: BlockHeader,
+        transactions: Vec<Transaction>,
+    ) -> Result<Self, HorizError> {
+        header.merkle_root = merkle_tree(&transactions)?.root();
+        Ok(Self {
+            header,
+            transactions,
+        })
+    }
+
+    /// Returns the identifier of the block, that of its header.
+    pub fn block_id(&self) -> Result<BlockId, HorizError> {
+        self.header.block_id()
+    }
+
+    /// Returns the ids of the transactions, in block order.
+    pub fn txids(&self) -> Result<Vec<TxId>, HorizError> {
+        self.transactions.iter().map(Transaction::txid).collect()
+    }
+
+    /// Returns the Merkle tree over the transaction ids, for proofs of
+    /// inclusion.
+    pub fn merkle_tree(&self) -> Result<MerkleTree, HorizError> {
+        merkle_tree(&self.transactions)
+    }
+
+    /// Returns the length of this block's canonical encoding, the size
+    /// checked against
+    /// [`MAX_BLOCK_SIZE`](horizcoin_primitives::constants::MAX_BLOCK_SIZE).
+    pub fn serialized_size(&self) -> Result<usize, HorizError> {
+        horizcoin_codec::encoded_len(self)
+    }
+
+    /// Checks the block against the size and transaction-count limits in
+    /// `ctx`, rejects repeated transactions, then checks its merkle root.
+    ///
+    /// The Merkle tree pairs an odd last node with itself, so repeating
+    /// the trailing transactions leaves the root, and the block id,
+    /// unchanged (CVE-2012-2459). Rejecting duplicate txids keeps such a
+    /// mutated copy from passing for the original block.
+    ///
+    /// The transactions themselves are not validated here.
+    pub fn validate(&self, ctx: &ValidationContext) -> Result<(), HorizError> {
+        if self.transactions.len() > ctx.max_block_transactions {
+            return Err(HorizError::InvalidBlock(format!(
+                "{} transactions exceed the maximum of {}",
+                self.transactions.len(),
+                ctx.max_block_transactions
+            )));
+        }
+        let size = self.serialized_size()?;
+        if size > ctx.max_block_size {
+            return Err(HorizError::InvalidBlock(format!(
+                "block is {size} bytes, maximum is {}",
+                ctx.max_block_size
+            )));
+        }
+        let mut seen = HashSet::with_capacity(self.transactions.len());
+        for (index, txid) in self.txids()?.into_iter().enumerate() {
+            if !seen.insert(txid) {
+                return Err(HorizError::InvalidBlock(format!(
+                    "transaction {index} repeats {txid}"
+                )));
+            }
+        }
+        self.verify_merkle_root()
+    }
+
+    /// Checks that the header's `merkle_root` matches the transactions.
+    pub fn verify_merkle_root(&self) -> Result<(), HorizError> {
+        let root = self.merkle_tree()?.root();
+        if root == self.header.merkle_root {
+            Ok(())
+        } else {
+            Err(HorizError::InvalidBlock(format!(
+                "merkle root {} does not match transactions ({root})",
+                self.header.merkle_root
+            )))
+        }
+    }
+}
+
+fn merkle_tree(transactions: &[Transaction]) -> Result<MerkleTree, HorizError> {
+    let leaves = transactions
+        .iter()
+        .map(|tx| tx.txid().map(Hash::from))
+        .collect::<Result<_, _>>()?;
+    Ok(MerkleTree::from_leaves(leaves))
+}
+
 #[cfg(test)]
 mod tests {
+    use horizcoin_crypto::PrivateKey;
+    use horizcoin_primitives::Amount;
+    use horizcoin_tx::{TxInput, TxOutput};
+
+    use super::*;
+
+    #[test]
+    fn block_id_commits_to_every_field() {
+        let header = BlockHeader {
+            prev_block_id: BlockId::new([1; 32]),
+            merkle_root: Hash::new([2; 32]),
+            timestamp: 1_700_000_000,
+            height: 7,
+            difficulty: 1,
+            nonce: 0,
+        };
+        let id = header.block_id().unwrap();
+        assert_eq!(header.block_id(), Ok(id));
+
+        let mut bumped = header;
+        bumped.nonce += 1;
+        assert_ne!(bumped.block_id(), Ok(id));
+    }
+
+    fn sample_block() -> Block {
+        let header = BlockHeader {
+            prev_block_id: BlockId::new([1; 32]),
+            merkle_root: Hash::ZERO,
+            timestamp: 1_700_000_000,
+            height: 1,
+            difficulty: 1,
+            nonce: 0,
+        };
+        let coinbase =
+            Transaction::coinbase(vec![TxOutput::new(Amount::new(50), "hz1miner")], None);
+        let key = PrivateKey::from_bytes(&[7; 32]).unwrap().public_key();
+        let spends = (1..=4u8).map(|byte| {
+            Transaction::new(
+                vec![TxInput::new(TxId::new([byte; 32]), 0, key)],
+                vec![TxOutput::new(Amount::new(10), "hz1payee")],
+                None,
+            )
+        });
+        Block::new(header, std::iter::once(coinbase).chain(spends).collect()).unwrap()
+    }
+
+    #[test]
+    fn block_id_is_deterministic() {
+        let block = sample_block();
+        let encoded = horizcoin_codec::encode(&block.header).unwrap();
+        assert_eq!(
+            block.block_id().unwrap(),
+            BlockId::from(double_sha256(&encoded))
+        );
+        assert_eq!(block.block_id(), sample_block().block_id());
+        let decoded: Block =
+            horizcoin_codec::decode_exact(&horizcoin_codec::encode(&block).unwrap()).unwrap();
+        assert_eq!(decoded.block_id(), block.block_id());
+    }
+
     #[test]
-    fn placeholder_test() {
-        // Placeholder test to ensure the crate compiles
+    fn merkle_root_matches_merkle_crate() {
+        let block = sample_block();
+        let leaves: Vec<Hash> = block.txids().unwrap().into_iter().map(Hash::from).collect();
+        assert_eq!(
+            block.header.merkle_root,
+            horizcoin_merkle::compute_merkle_root(&leaves)
+        );
+        assert_eq!(block.verify_merkle_root(), Ok(()));
+
+        let proof = block.merkle_tree().unwrap().proof(3).unwrap();
+        assert!(proof.verify(leaves[3], block.header.merkle_root));
+
+        let mut dropped = block.clone();
+        dropped.transactions.pop();
+        assert!(matches!(
+            dropped.verify_merkle_root(),
+            Err(HorizError::InvalidBlock(_))
+        ));
+        let mut reordered = block;
+        reordered.transactions.swap(1, 2);
+        assert!(reordered.verify_merkle_root().is_err());
+    }
+
+    #[test]
+    fn limits_are_inclusive() {
+        let block = sample_block();
+        let default = ValidationContext::default();
+        assert_eq!(block.validate(&default), Ok(()));
+
+        let size = block.serialized_size().unwrap();
+        assert_eq!(size, horizcoin_codec::encode(&block).unwrap().len());
+        let at_limit = ValidationContext {
+            max_block_size: size,
+            max_block_transactions: block.transactions.len(),
+            ..default
+        };
+        assert_eq!(block.validate(&at_limit), Ok(()));
+
+        let too_many = ValidationContext {
+            max_block_transactions: block.transactions.len() - 1,
+            ..at_limit
+        };
+        assert_eq!(
+            block.validate(&too_many),
+            Err(HorizError::InvalidBlock(
+                "5 transactions exceed the maximum of 4".into()
+            ))
+        );
+        let too_large = ValidationContext {
+            max_block_size: size - 1,
+            ..at_limit
+        };
+        assert_eq!(
+            block.validate(&too_large),
+            Err(HorizError::InvalidBlock(format!(
+                "block is {size} bytes, maximum is {}",
+                size - 1
+            )))
+        );
+
+        let mut tampered = block;
+        tampered.transactions.pop();
+        assert!(tampered.validate(&at_limit).is_err());
+    }
+
+    #[test]
+    fn repeated_trailing_transaction_is_rejected() {
+        let block = sample_block();
+        assert_eq!(block.transactions.len() % 2, 1);
+
+        // Duplicating the odd last transaction keeps the merkle root.
+        let mut mutated = block.clone();
+        mutated
+            .transactions
+            .push(block.transactions.last().unwrap().clone());
+        assert_eq!(mutated.verify_merkle_root(), Ok(()));
+        assert_eq!(mutated.block_id(), block.block_id());
+
+        let last = block.txids().unwrap()[4];
+        assert_eq!(
+            mutated.validate(&ValidationContext::default()),
+            Err(HorizError::InvalidBlock(format!(
+                "transaction 5 repeats {last}"
+            )))
+        );
+        assert_eq!(block.validate(&ValidationContext::default()), Ok(()));
     }
 }
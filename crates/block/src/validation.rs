@@ -0,0 +1,186 @@
+//! Context-free and timestamp checks run before a block is connected.
+
+use horizcoin_crypto::Network;
+use horizcoin_primitives::constants::MEDIAN_TIME_SPAN;
+use horizcoin_primitives::{HorizError, ValidationContext};
+
+use crate::{Block, BlockHeader};
+
+/// Returns the median timestamp of the last [`MEDIAN_TIME_SPAN`] of
+/// `prev_headers`, which are in chain order, oldest first.
+///
+/// With an even number of headers the later of the two middle timestamps
+/// is used. Returns `0` for no headers.
+#[must_use]
+pub fn median_time_past(prev_headers: &[BlockHeader]) -> u64 {
+    let recent = &prev_headers[prev_headers.len().saturating_sub(MEDIAN_TIME_SPAN)..];
+    let mut timestamps: Vec<u64> = recent.iter().map(|header| header.timestamp).collect();
+    timestamps.sort_unstable();
+    timestamps.get(timestamps.len() / 2).copied().unwrap_or(0)
+}
+
+/// Returns the validation limits of `network`.
+///
+/// Mainnet and testnet use the protocol defaults. Regtest lets coinbase
+/// outputs be spent in the next block, so local tests need not mine a
+/// hundred blocks first.
+#[must_use]
+pub fn validation_context(network: Network) -> ValidationContext {
+    match network {
+        Network::Mainnet | Network::Testnet => ValidationContext::default(),
+        Network::Regtest => ValidationContext {
+            coinbase_maturity: 1,
+            ..ValidationContext::default()
+        },
+    }
+}
+
+/// Checks `block` on its own and against the clock.
+///
+/// The block must pass [`Block::validate`] and its timestamp may be at
+/// most the context's
+/// [`max_future_block_time`](ValidationContext::max_future_block_time)
+/// seconds past `now`. When `prev_headers`
+/// are given, the headers leading up to the block in chain order, the
+/// timestamp must also be strictly greater than their
+/// [`median_time_past`], so producers cannot hold time still or run it
+/// backwards.
+pub fn validate_block_basic(
+    block: &Block,
+    ctx: &ValidationContext,
+    now: u64,
+    prev_headers: Option<&[BlockHeader]>,
+) -> Result<(), HorizError> {
+    block.validate(ctx)?;
+
+    let timestamp = block.header.timestamp;
+    let latest = now.saturating_add(ctx.max_future_block_time);
+    if timestamp > latest {
+        return Err(HorizError::InvalidBlock(format!(
+            "timestamp {timestamp} is more than {}s ahead of {now}",
+            ctx.max_future_block_time
+        )));
+    }
+    if let Some(prev_headers) = prev_headers {
+        let median = median_time_past(prev_headers);
+        if timestamp <= median {
+            return Err(HorizError::InvalidBlock(format!(
+                "timestamp {timestamp} is not after the median time past {median}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_primitives::constants::{COINBASE_MATURITY, MAX_FUTURE_BLOCK_TIME};
+    use horizcoin_primitives::{Amount, BlockId, Hash};
+    use horizcoin_tx::{Transaction, TxOutput};
+
+    use super::*;
+
+    fn header(timestamp: u64) -> BlockHeader {
+        BlockHeader {
+            prev_block_id: BlockId::ZERO,
+            merkle_root: Hash::ZERO,
+            timestamp,
+            height: 0,
+            difficulty: 1,
+            nonce: 0,
+        }
+    }
+
+    fn block_at(timestamp: u64) -> Block {
+        let coinbase =
+            Transaction::coinbase(vec![TxOutput::new(Amount::new(50), "hz1miner")], None);
+        Block::new(header(timestamp), vec![coinbase]).unwrap()
+    }
+
+    #[test]
+    fn median_uses_the_last_eleven_timestamps() {
+        assert_eq!(median_time_past(&[]), 0);
+        assert_eq!(median_time_past(&[header(5)]), 5);
+        assert_eq!(median_time_past(&[header(9), header(1), header(4)]), 4);
+        assert_eq!(median_time_past(&[header(1), header(2)]), 2);
+
+        // Only the last MEDIAN_TIME_SPAN count: the huge first one is ignored.
+        let mut headers = vec![header(1_000_000)];
+        headers.extend((1..=11).rev().map(|t| header(t * 10)));
+        assert_eq!(median_time_past(&headers), 60);
+    }
+
+    #[test]
+    fn timestamp_must_exceed_median_time_past() {
+        let ctx = ValidationContext::default();
+        let now = 2_000_000_000;
+        let prev: Vec<BlockHeader> = (0..11).map(|i| header(now - 1_000 + i * 60)).collect();
+        let median = median_time_past(&prev);
+        assert_eq!(median, now - 1_000 + 5 * 60);
+
+        assert_eq!(
+            validate_block_basic(&block_at(median + 1), &ctx, now, Some(&prev)),
+            Ok(())
+        );
+        assert_eq!(
+            validate_block_basic(&block_at(median), &ctx, now, Some(&prev)),
+            Err(HorizError::InvalidBlock(format!(
+                "timestamp {median} is not after the median time past {median}"
+            )))
+        );
+        assert!(validate_block_basic(&block_at(median - 100), &ctx, now, Some(&prev)).is_err());
+        // Without history only the clock is checked.
+        assert_eq!(
+            validate_block_basic(&block_at(median - 100), &ctx, now, None),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn timestamp_may_not_run_far_ahead() {
+        let ctx = ValidationContext::default();
+        let now = 2_000_000_000;
+        let latest = now + MAX_FUTURE_BLOCK_TIME;
+        assert_eq!(
+            validate_block_basic(&block_at(latest), &ctx, now, None),
+            Ok(())
+        );
+        assert!(validate_block_basic(&block_at(latest + 1), &ctx, now, None).is_err());
+
+        let mut tampered = block_at(now);
+        tampered.transactions.clear();
+        assert!(validate_block_basic(&tampered, &ctx, now, None).is_err());
+
+        // A stricter context rejects what the default accepts.
+        let strict = ValidationContext {
+            max_future_block_time: 60,
+            ..ctx
+        };
+        assert_eq!(
+            validate_block_basic(&block_at(now + 60), &strict, now, None),
+            Ok(())
+        );
+        assert_eq!(
+            validate_block_basic(&block_at(now + 61), &strict, now, None),
+            Err(HorizError::InvalidBlock(format!(
+                "timestamp {} is more than 60s ahead of {now}",
+                now + 61
+            )))
+        );
+    }
+
+    #[test]
+    fn networks_have_their_own_limits() {
+        assert_eq!(
+            validation_context(Network::Mainnet),
+            ValidationContext::default()
+        );
+        assert_eq!(
+            validation_context(Network::Testnet).coinbase_maturity,
+            COINBASE_MATURITY
+        );
+        let regtest = validation_context(Network::Regtest);
+        assert_eq!(regtest.coinbase_maturity, 1);
+        assert_eq!(regtest.max_future_block_time, MAX_FUTURE_BLOCK_TIME);
+    }
+}
@@ -2,11 +2,303 @@
 //!
 //! This crate provides canonical serialization with serde and length-prefixing
 //! for `HorizCoin` data structures.
+//!
+//! Values are encoded with bincode. Length-prefixed frames carry the encoded
+//! length as a 4-byte little-endian integer in front of the payload.
+
+pub mod varint;
+
+use std::io::{Read, Write};
+
+use bincode::Options;
+use horizcoin_crypto::sha256;
+use horizcoin_primitives::{Hash, HorizError};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Size of the length prefix written by [`encode_with_length`].
+pub const LENGTH_PREFIX_LEN: usize = 4;
+
+/// Largest payload [`decode_with_length`] accepts.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Encodes `value` with bincode.
+pub fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, HorizError> {
+    bincode::serialize(value).map_err(|e| HorizError::Serialization(e.to_string()))
+}
+
+/// Returns the length of [`encode`]'s output for `value` without
+/// allocating it.
+pub fn encoded_len<T: Serialize>(value: &T) -> Result<usize, HorizError> {
+    bincode::serialized_size(value)
+        .map_err(|e| HorizError::Serialization(e.to_string()))
+        .and_then(|len| {
+            usize::try_from(len).map_err(|_| HorizError::Serialization("value too large".into()))
+        })
+}
+
+/// Decodes a value produced by [`encode`].
+///
+/// Bytes after the value are ignored; use [`decode_exact`] when the input
+/// must be exactly one canonical encoding.
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, HorizError> {
+    bincode::deserialize(bytes).map_err(|e| HorizError::Serialization(e.to_string()))
+}
+
+/// Decodes a value produced by [`encode`], rejecting trailing bytes.
+///
+/// Only one byte string decodes to any given value, so anything hashed with
+/// [`canonical_hash`] cannot be re-encoded with padding by a peer.
+pub fn decode_exact<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, HorizError> {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+        .deserialize(bytes)
+        .map_err(|e| HorizError::Serialization(e.to_string()))
+}
+
+/// Encodes `value` behind a 4-byte little-endian length prefix.
+pub fn encode_with_length<T: Serialize>(value: &T) -> Result<Vec<u8>, HorizError> {
+    let payload = encode(value)?;
+    let len = u32::try_from(payload.len()).map_err(|_| {
+        HorizError::Serialization(format!("payload of {} bytes is too long", payload.len()))
+    })?;
+    let mut out = Vec::with_capacity(LENGTH_PREFIX_LEN + payload.len());
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(&payload);
+    Ok(out)
+}
+
+/// Decodes one frame produced by [`encode_with_length`], returning the value
+/// and the number of bytes consumed.
+///
+/// Frames longer than [`DEFAULT_MAX_FRAME_LEN`] are rejected.
+pub fn decode_with_length<T: DeserializeOwned>(bytes: &[u8]) -> Result<(T, usize), HorizError> {
+    decode_with_length_capped(bytes, DEFAULT_MAX_FRAME_LEN)
+}
+
+/// Like [`decode_with_length`], but rejects any frame whose declared length
+/// exceeds `max_len` before looking at the payload.
+pub fn decode_with_length_capped<T: DeserializeOwned>(
+    bytes: &[u8],
+    max_len: usize,
+) -> Result<(T, usize), HorizError> {
+    let len = read_length_prefix(bytes)?;
+    if len > max_len {
+        return Err(HorizError::Serialization(format!(
+            "declared length {len} exceeds maximum of {max_len}"
+        )));
+    }
+    let end = LENGTH_PREFIX_LEN + len;
+    let payload = bytes.get(LENGTH_PREFIX_LEN..end).ok_or_else(|| {
+        HorizError::Serialization(format!(
+            "frame declares {len} bytes but only {} follow",
+            bytes.len() - LENGTH_PREFIX_LEN
+        ))
+    })?;
+    Ok((decode(payload)?, end))
+}
+
+/// Writes `value` to `writer` as one length-prefixed frame.
+pub fn encode_to_writer<T: Serialize, W: Write>(
+    value: &T,
+    writer: &mut W,
+) -> Result<(), HorizError> {
+    let frame = encode_with_length(value)?;
+    writer
+        .write_all(&frame)
+        .map_err(|e| HorizError::Serialization(format!("write failed: {e}")))
+}
+
+/// Reads one length-prefixed frame from `reader` and decodes it.
+///
+/// Short reads are retried until the declared length has arrived. Frames
+/// longer than [`DEFAULT_MAX_FRAME_LEN`] are rejected before the payload is
+/// read, and a stream that ends mid-frame is an error.
+pub fn decode_from_reader<T: DeserializeOwned, R: Read>(reader: &mut R) -> Result<T, HorizError> {
+    let mut prefix = [0u8; LENGTH_PREFIX_LEN];
+    read_fully(reader, &mut prefix)?;
+    let len = read_length_prefix(&prefix)?;
+    if len > DEFAULT_MAX_FRAME_LEN {
+        return Err(HorizError::Serialization(format!(
+            "declared length {len} exceeds maximum of {DEFAULT_MAX_FRAME_LEN}"
+        )));
+    }
+    let mut payload = vec![0u8; len];
+    read_fully(reader, &mut payload)?;
+    decode(&payload)
+}
+
+/// Encodes `value` as CBOR.
+///
+/// Only available with the `cbor` feature. Unlike bincode, CBOR is
+/// self-describing, so tools in other languages can read it without our
+/// type definitions. Consensus data is still hashed over bincode.
+#[cfg(feature = "cbor")]
+pub fn encode_cbor<T: Serialize>(value: &T) -> Result<Vec<u8>, HorizError> {
+    let mut out = Vec::new();
+    ciborium::ser::into_writer(value, &mut out)
+        .map_err(|e| HorizError::Serialization(e.to_string()))?;
+    Ok(out)
+}
+
+/// Decodes a value produced by [`encode_cbor`].
+#[cfg(feature = "cbor")]
+pub fn decode_cbor<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, HorizError> {
+    ciborium::de::from_reader(bytes).map_err(|e| HorizError::Serialization(e.to_string()))
+}
+
+/// Returns the `SHA-256` of the canonical encoding of `value`.
+pub fn canonical_hash<T: Serialize>(value: &T) -> Result<Hash, HorizError> {
+    encode(value).map(|bytes| sha256(&bytes))
+}
+
+fn read_fully<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<(), HorizError> {
+    reader.read_exact(buf).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::UnexpectedEof {
+            HorizError::Serialization("stream ended mid-frame".into())
+        } else {
+            HorizError::Serialization(format!("read failed: {e}"))
+        }
+    })
+}
+
+fn read_length_prefix(bytes: &[u8]) -> Result<usize, HorizError> {
+    let prefix: [u8; LENGTH_PREFIX_LEN] = bytes
+        .get(..LENGTH_PREFIX_LEN)
+        .and_then(|prefix| prefix.try_into().ok())
+        .ok_or_else(|| HorizError::Serialization("missing length prefix".into()))?;
+    usize::try_from(u32::from_le_bytes(prefix))
+        .map_err(|_| HorizError::Serialization("length prefix does not fit in usize".into()))
+}
 
 #[cfg(test)]
 mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Sample {
+        id: u64,
+        name: String,
+        tags: Vec<u32>,
+    }
+
+    fn sample() -> Sample {
+        Sample {
+            id: 42,
+            name: "horizon".into(),
+            tags: vec![1, 2, 3],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = encode(&sample()).unwrap();
+        assert_eq!(decode::<Sample>(&bytes).unwrap(), sample());
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_round_trip() {
+        let bytes = encode_cbor(&sample()).unwrap();
+        assert_eq!(decode_cbor::<Sample>(&bytes).unwrap(), sample());
+        assert!(decode_cbor::<Sample>(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn decode_exact_rejects_trailing_bytes() {
+        let mut bytes = encode(&sample()).unwrap();
+        assert_eq!(decode_exact::<Sample>(&bytes).unwrap(), sample());
+
+        bytes.push(0);
+        assert_eq!(decode::<Sample>(&bytes).unwrap(), sample());
+        assert!(decode_exact::<Sample>(&bytes).is_err());
+    }
+
+    #[test]
+    fn length_prefixed_round_trip() {
+        let mut bytes = encode_with_length(&sample()).unwrap();
+        let frame_len = bytes.len();
+        bytes.extend_from_slice(b"next frame");
+
+        let (value, consumed) = decode_with_length::<Sample>(&bytes).unwrap();
+        assert_eq!(value, sample());
+        assert_eq!(consumed, frame_len);
+
+        assert!(decode_with_length::<Sample>(&bytes[..frame_len - 1]).is_err());
+        assert!(decode_with_length::<Sample>(&bytes[..2]).is_err());
+    }
+
+    /// Hands out at most one byte per `read` call.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            if buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn stream_round_trip() {
+        let mut buffer = Vec::new();
+        encode_to_writer(&sample(), &mut buffer).unwrap();
+        encode_to_writer(&7u32, &mut buffer).unwrap();
+
+        let mut cursor = std::io::Cursor::new(buffer.as_slice());
+        assert_eq!(
+            decode_from_reader::<Sample, _>(&mut cursor).unwrap(),
+            sample()
+        );
+        assert_eq!(decode_from_reader::<u32, _>(&mut cursor).unwrap(), 7);
+        assert!(decode_from_reader::<u32, _>(&mut cursor).is_err());
+
+        let mut trickle = Trickle(&buffer);
+        assert_eq!(
+            decode_from_reader::<Sample, _>(&mut trickle).unwrap(),
+            sample()
+        );
+
+        let mut truncated = Trickle(&buffer[..buffer.len() - 6]);
+        decode_from_reader::<Sample, _>(&mut truncated).unwrap();
+        assert_eq!(
+            decode_from_reader::<u32, _>(&mut truncated),
+            Err(HorizError::Serialization("stream ended mid-frame".into()))
+        );
+    }
+
+    #[test]
+    fn oversized_length_prefix_is_rejected() {
+        let mut bytes = u32::MAX.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&[0u8; 8]);
+        let err = decode_with_length_capped::<Sample>(&bytes, 1024).unwrap_err();
+        assert_eq!(
+            err,
+            HorizError::Serialization(format!(
+                "declared length {} exceeds maximum of 1024",
+                u32::MAX
+            ))
+        );
+        assert!(decode_with_length::<Sample>(&bytes).is_err());
+    }
+
     #[test]
-    fn placeholder_test() {
-        // Placeholder test to ensure the crate compiles
+    fn canonical_hash_is_stable() {
+        assert_eq!(
+            canonical_hash(&sample()).unwrap(),
+            sha256(&encode(&sample()).unwrap())
+        );
+        assert_ne!(
+            canonical_hash(&sample()).unwrap(),
+            canonical_hash(&1u8).unwrap()
+        );
     }
 }
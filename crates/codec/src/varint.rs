@@ -0,0 +1,114 @@
+//! Variable-length integer encoding.
+//!
+//! Unsigned values use `LEB128`: seven bits per byte, least significant
+//! group first, with the high bit set on every byte but the last. Only the
+//! shortest encoding of a value is accepted when decoding. Signed values are
+//! zigzag-mapped first so small negative numbers stay short.
+
+use horizcoin_primitives::HorizError;
+
+/// Longest encoding of a `u64`.
+pub const MAX_VARINT_LEN: usize = 10;
+
+/// Encodes `value` as `LEB128`.
+#[must_use]
+pub fn encode_u64(mut value: u64) -> Vec<u8> {
+    let mut out = Vec::with_capacity(MAX_VARINT_LEN);
+    loop {
+        // Truncation keeps exactly the low seven bits.
+        #[allow(clippy::cast_possible_truncation)]
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return out;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Decodes a `LEB128` value from the start of `bytes`, returning it and the
+/// number of bytes read.
+pub fn decode_u64(bytes: &[u8]) -> Result<(u64, usize), HorizError> {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().enumerate().take(MAX_VARINT_LEN) {
+        let group = u64::from(byte & 0x7f);
+        let shift = 7 * i;
+        if shift == 63 && group > 1 {
+            return Err(HorizError::Serialization("varint overflows u64".into()));
+        }
+        value |= group << shift;
+        if byte & 0x80 == 0 {
+            if byte == 0 && i > 0 {
+                return Err(HorizError::Serialization("non-minimal varint".into()));
+            }
+            return Ok((value, i + 1));
+        }
+    }
+    if bytes.len() >= MAX_VARINT_LEN {
+        Err(HorizError::Serialization("varint overflows u64".into()))
+    } else {
+        Err(HorizError::Serialization("truncated varint".into()))
+    }
+}
+
+/// Encodes `value` as a zigzag-mapped `LEB128`.
+#[must_use]
+pub fn encode_i64(value: i64) -> Vec<u8> {
+    encode_u64(zigzag(value))
+}
+
+/// Decodes a value produced by [`encode_i64`], returning it and the number of
+/// bytes read.
+pub fn decode_i64(bytes: &[u8]) -> Result<(i64, usize), HorizError> {
+    decode_u64(bytes).map(|(value, read)| (unzigzag(value), read))
+}
+
+/// Maps `0, -1, 1, -2, ...` to `0, 1, 2, 3, ...`.
+#[allow(clippy::cast_sign_loss)]
+const fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+#[allow(clippy::cast_possible_wrap)]
+const fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsigned_round_trip() {
+        for value in [0, 1, 127, 128, 300, u64::from(u32::MAX), u64::MAX] {
+            let bytes = encode_u64(value);
+            assert_eq!(decode_u64(&bytes).unwrap(), (value, bytes.len()));
+        }
+        assert_eq!(encode_u64(300), [0xac, 0x02]);
+        assert_eq!(encode_u64(u64::MAX).len(), MAX_VARINT_LEN);
+    }
+
+    #[test]
+    fn unsigned_rejects_malformed_input() {
+        assert!(decode_u64(&[]).is_err());
+        assert!(decode_u64(&[0x80]).is_err());
+        assert!(decode_u64(&[0x80, 0x00]).is_err());
+        let mut overflow = vec![0xff; MAX_VARINT_LEN - 1];
+        overflow.push(0x02);
+        assert!(decode_u64(&overflow).is_err());
+        assert!(decode_u64(&[0xff; MAX_VARINT_LEN + 1]).is_err());
+    }
+
+    #[test]
+    fn signed_round_trip() {
+        for value in [0, -1, 1, i64::MIN, i64::MAX] {
+            let bytes = encode_i64(value);
+            assert_eq!(decode_i64(&bytes).unwrap(), (value, bytes.len()));
+        }
+        assert_eq!(encode_i64(0), [0]);
+        assert_eq!(encode_i64(-1), [1]);
+        assert_eq!(encode_i64(1), [2]);
+        assert_eq!(encode_i64(-64).len(), 1);
+    }
+}
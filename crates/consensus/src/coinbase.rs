@@ -0,0 +1,131 @@
+//! Checks on the coinbase transaction that mints a block's subsidy.
+
+use horizcoin_block::Block;
+use horizcoin_primitives::{Amount, HorizError};
+use horizcoin_tx::Transaction;
+
+use crate::{block_reward, invalid};
+
+/// Checks that `tx` is a coinbase paying out exactly the
+/// [`block_reward`] for `height` plus `total_fees`, the fees of the other
+/// transactions in its block.
+pub fn validate_coinbase(
+    tx: &Transaction,
+    height: u64,
+    total_fees: Amount,
+) -> Result<(), HorizError> {
+    if !tx.is_coinbase() {
+        return Err(invalid("expected a coinbase transaction"));
+    }
+    let allowed = block_reward(height).checked_add(total_fees)?;
+    let paid = tx.total_output_amount()?;
+    if paid != allowed {
+        return Err(invalid(format!(
+            "coinbase at height {height} pays {paid}, expected {allowed}"
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that the first transaction of `block`, and only the first, is a
+/// coinbase, and that it passes [`validate_coinbase`] at the block's height.
+pub fn validate_block_coinbase(block: &Block, total_fees: Amount) -> Result<(), HorizError> {
+    let Some((coinbase, rest)) = block.transactions.split_first() else {
+        return Err(invalid("block has no coinbase"));
+    };
+    if let Some(index) = rest.iter().position(Transaction::is_coinbase) {
+        return Err(invalid(format!(
+            "transaction {} is a second coinbase",
+            index + 1
+        )));
+    }
+    validate_coinbase(coinbase, block.header.height, total_fees)
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_block::genesis_block;
+    use horizcoin_crypto::Network;
+    use horizcoin_primitives::constants::INITIAL_BLOCK_REWARD;
+    use horizcoin_primitives::TxId;
+    use horizcoin_tx::{TxInput, TxOutput};
+
+    use super::*;
+    use crate::HALVING_INTERVAL;
+
+    fn coinbase(amount: u64) -> Transaction {
+        Transaction::coinbase(vec![TxOutput::new(Amount::new(amount), "hz1miner")], None)
+    }
+
+    #[test]
+    fn exact_reward_is_accepted() {
+        let reward = INITIAL_BLOCK_REWARD.as_u64();
+        assert_eq!(
+            validate_coinbase(&coinbase(reward), 0, Amount::ZERO),
+            Ok(())
+        );
+        assert_eq!(
+            validate_coinbase(&coinbase(reward / 2), HALVING_INTERVAL, Amount::ZERO),
+            Ok(())
+        );
+        assert_eq!(
+            validate_block_coinbase(&genesis_block(Network::Mainnet), Amount::ZERO),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn wrong_reward_is_rejected() {
+        let reward = INITIAL_BLOCK_REWARD.as_u64();
+        assert_eq!(
+            validate_coinbase(&coinbase(reward + 1), 0, Amount::ZERO),
+            Err(invalid(format!(
+                "coinbase at height 0 pays {}, expected {reward}",
+                reward + 1
+            )))
+        );
+        assert!(validate_coinbase(&coinbase(reward - 1), 0, Amount::ZERO).is_err());
+        // A full first-era reward after the first halving overpays.
+        assert!(validate_coinbase(&coinbase(reward), HALVING_INTERVAL, Amount::ZERO).is_err());
+    }
+
+    #[test]
+    fn fees_raise_the_allowed_total() {
+        let reward = INITIAL_BLOCK_REWARD.as_u64();
+        let fees = Amount::new(1_234);
+        let with_fees = coinbase(reward + 1_234);
+        assert_eq!(validate_coinbase(&with_fees, 0, fees), Ok(()));
+        assert!(validate_coinbase(&with_fees, 0, Amount::ZERO).is_err());
+        assert!(validate_coinbase(&coinbase(reward), 0, fees).is_err());
+    }
+
+    #[test]
+    fn block_needs_exactly_one_leading_coinbase() {
+        let mut block = genesis_block(Network::Mainnet);
+        let spend = Transaction::new(
+            vec![TxInput::multisig(TxId::new([1; 32]), 0)],
+            vec![TxOutput::new(Amount::new(10), "hz1payee")],
+            None,
+        );
+
+        let second = block.transactions[0].clone();
+        block.transactions.push(second);
+        assert_eq!(
+            validate_block_coinbase(&block, Amount::ZERO),
+            Err(invalid("transaction 1 is a second coinbase"))
+        );
+
+        block.transactions = vec![spend.clone(), block.transactions[0].clone()];
+        assert!(validate_block_coinbase(&block, Amount::ZERO).is_err());
+        assert_eq!(
+            validate_coinbase(&spend, 0, Amount::ZERO),
+            Err(invalid("expected a coinbase transaction"))
+        );
+
+        block.transactions.clear();
+        assert_eq!(
+            validate_block_coinbase(&block, Amount::ZERO),
+            Err(invalid("block has no coinbase"))
+        );
+    }
+}
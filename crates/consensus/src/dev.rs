@@ -0,0 +1,152 @@
+//! Proof of authority for development networks.
+
+use horizcoin_block::BlockHeader;
+use horizcoin_crypto::{PrivateKey, PublicKey, SIGNATURE_LEN};
+use horizcoin_primitives::HorizError;
+
+use crate::{check_link, invalid, Consensus, SealedHeader};
+
+/// Proof of authority: a header is valid if its seal is a signature over
+/// its block id by one of a fixed set of authorities.
+#[derive(Debug, Clone)]
+pub struct DevConsensus {
+    authorities: Vec<PublicKey>,
+    signer: Option<PrivateKey>,
+}
+
+impl DevConsensus {
+    /// Creates an engine accepting blocks sealed by any of `authorities`.
+    ///
+    /// It can validate but not seal until given a key with
+    /// [`with_signer`](Self::with_signer).
+    #[must_use]
+    pub const fn new(authorities: Vec<PublicKey>) -> Self {
+        Self {
+            authorities,
+            signer: None,
+        }
+    }
+
+    /// Returns the engine sealing with `key`.
+    #[must_use]
+    pub fn with_signer(mut self, key: PrivateKey) -> Self {
+        self.signer = Some(key);
+        self
+    }
+
+    /// Returns the keys allowed to seal blocks.
+    #[must_use]
+    pub fn authorities(&self) -> &[PublicKey] {
+        &self.authorities
+    }
+}
+
+impl Consensus for DevConsensus {
+    fn validate_block_header(
+        &self,
+        header: &SealedHeader,
+        prev: &BlockHeader,
+    ) -> Result<(), HorizError> {
+        check_link(&header.header, prev)?;
+        let signature: &[u8; SIGNATURE_LEN] = header
+            .seal
+            .as_slice()
+            .try_into()
+            .map_err(|_| invalid(format!("seal is {} bytes", header.seal.len())))?;
+        let block_id = header.header.block_id()?;
+        if self
+            .authorities
+            .iter()
+            .any(|authority| authority.verify(block_id.as_bytes(), signature))
+        {
+            Ok(())
+        } else {
+            Err(invalid(format!("{block_id} is not sealed by an authority")))
+        }
+    }
+
+    fn seal_block(&self, header: BlockHeader) -> Result<SealedHeader, HorizError> {
+        let key = self
+            .signer
+            .as_ref()
+            .ok_or_else(|| invalid("no signing key configured"))?;
+        if !self.authorities.contains(&key.public_key()) {
+            return Err(invalid("signing key is not an authority"));
+        }
+        let seal = key
+            .sign_deterministic(header.block_id()?.as_bytes())
+            .to_vec();
+        Ok(SealedHeader { header, seal })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_primitives::{BlockId, Hash};
+
+    use super::*;
+
+    fn genesis() -> BlockHeader {
+        BlockHeader {
+            prev_block_id: BlockId::ZERO,
+            merkle_root: Hash::ZERO,
+            timestamp: 1_700_000_000,
+            height: 0,
+            difficulty: 1,
+            nonce: 0,
+        }
+    }
+
+    fn child_of(prev: &BlockHeader) -> BlockHeader {
+        BlockHeader {
+            prev_block_id: prev.block_id().unwrap(),
+            merkle_root: Hash::new([7; 32]),
+            timestamp: prev.timestamp + 10,
+            height: prev.height + 1,
+            difficulty: 1,
+            nonce: 0,
+        }
+    }
+
+    #[test]
+    fn accepts_block_sealed_by_authority() {
+        let authority = PrivateKey::generate();
+        let engine = DevConsensus::new(vec![
+            PrivateKey::generate().public_key(),
+            authority.public_key(),
+        ])
+        .with_signer(authority);
+        let prev = genesis();
+        let sealed = engine.seal_block(child_of(&prev)).unwrap();
+        assert_eq!(engine.validate_block_header(&sealed, &prev), Ok(()));
+
+        // The seal and the link both bind.
+        let mut tampered = sealed.clone();
+        tampered.header.nonce += 1;
+        assert!(engine.validate_block_header(&tampered, &prev).is_err());
+        assert!(engine
+            .validate_block_header(&sealed, &sealed.header)
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_block_sealed_by_unknown_key() {
+        let authority = PrivateKey::generate();
+        let outsider = PrivateKey::generate();
+        let engine = DevConsensus::new(vec![authority.public_key()]);
+        let prev = genesis();
+
+        let rogue = DevConsensus::new(vec![outsider.public_key()]).with_signer(outsider.clone());
+        let sealed = rogue.seal_block(child_of(&prev)).unwrap();
+        assert!(matches!(
+            engine.validate_block_header(&sealed, &prev),
+            Err(HorizError::InvalidBlock(_))
+        ));
+        assert!(engine
+            .clone()
+            .with_signer(outsider)
+            .seal_block(child_of(&prev))
+            .is_err());
+        assert!(engine.seal_block(child_of(&prev)).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! Choosing the canonical chain among competing tips.
+
+use horizcoin_primitives::BlockId;
+use serde::{Deserialize, Serialize};
+
+/// Cumulative work of a chain, summed over its blocks.
+///
+/// A proof-of-work block adds its difficulty, which is proportional to the
+/// expected number of hashes needed to find it. A proof-of-authority block
+/// adds one, so the work of such a chain is its length.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct ChainWork(u128);
+
+impl ChainWork {
+    /// The work of an empty chain.
+    pub const ZERO: Self = Self(0);
+
+    /// Returns the work of a proof-of-authority chain of `height` blocks.
+    #[must_use]
+    pub fn from_height(height: u64) -> Self {
+        Self(u128::from(height))
+    }
+
+    /// Returns the work after one more proof-of-work block at `difficulty`.
+    #[must_use]
+    pub fn with_block(self, difficulty: u32) -> Self {
+        Self(self.0.saturating_add(u128::from(difficulty.max(1))))
+    }
+
+    /// Returns the accumulated work.
+    #[must_use]
+    pub const fn get(self) -> u128 {
+        self.0
+    }
+}
+
+/// Returns the tip with the most cumulative work, breaking ties by the
+/// lowest block id so every node picks the same one, or `None` if `tips`
+/// is empty.
+#[must_use]
+pub fn select_best_tip(tips: &[(BlockId, ChainWork)]) -> Option<BlockId> {
+    tips.iter()
+        .max_by(|(a_id, a_work), (b_id, b_work)| a_work.cmp(b_work).then_with(|| b_id.cmp(a_id)))
+        .map(|(id, _)| *id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heaviest_chain_wins() {
+        let short_but_hard = [500, 500]
+            .into_iter()
+            .fold(ChainWork::ZERO, ChainWork::with_block);
+        let long_but_easy = [1; 10]
+            .into_iter()
+            .fold(ChainWork::ZERO, ChainWork::with_block);
+        assert_eq!(short_but_hard.get(), 1_000);
+        assert_eq!(long_but_easy, ChainWork::from_height(10));
+
+        let tips = [
+            (BlockId::new([1; 32]), long_but_easy),
+            (BlockId::new([2; 32]), short_but_hard),
+            (BlockId::new([3; 32]), ChainWork::from_height(9)),
+        ];
+        assert_eq!(select_best_tip(&tips), Some(BlockId::new([2; 32])));
+        assert_eq!(select_best_tip(&[]), None);
+    }
+
+    #[test]
+    fn ties_go_to_the_lowest_block_id() {
+        let work = ChainWork::from_height(42);
+        let tips = [
+            (BlockId::new([9; 32]), work),
+            (BlockId::new([4; 32]), work),
+            (BlockId::new([7; 32]), work),
+            (BlockId::new([0; 32]), ChainWork::from_height(41)),
+        ];
+        assert_eq!(select_best_tip(&tips), Some(BlockId::new([4; 32])));
+
+        let mut reversed = tips;
+        reversed.reverse();
+        assert_eq!(select_best_tip(&reversed), Some(BlockId::new([4; 32])));
+    }
+}
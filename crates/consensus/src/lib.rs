@@ -3,10 +3,63 @@
 //! This crate provides pluggable consensus interface with `DevConsensus` (`PoA`)
 //! for development and `PoB` for production.
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn placeholder_test() {
-        // Placeholder test to ensure the crate compiles
+mod coinbase;
+mod dev;
+mod fork_choice;
+mod pow;
+mod retarget;
+mod reward;
+
+pub use coinbase::{validate_block_coinbase, validate_coinbase};
+pub use dev::DevConsensus;
+pub use fork_choice::{select_best_tip, ChainWork};
+pub use pow::{difficulty_to_target, PowConsensus};
+pub use retarget::{retarget, MAX_ADJUSTMENT_FACTOR, RETARGET_WINDOW};
+pub use reward::{block_reward, HALVING_INTERVAL};
+
+use horizcoin_block::BlockHeader;
+use horizcoin_primitives::HorizError;
+use serde::{Deserialize, Serialize};
+
+/// A header together with the proof that its producer was entitled to
+/// produce it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedHeader {
+    /// The sealed header.
+    pub header: BlockHeader,
+    /// Engine-specific proof, such as an authority's signature; empty for
+    /// engines whose proof lives in the header itself.
+    pub seal: Vec<u8>,
+}
+
+/// Rules deciding which headers may extend the chain.
+pub trait Consensus {
+    /// Checks that `header` directly follows `prev` and carries a valid
+    /// seal.
+    fn validate_block_header(
+        &self,
+        header: &SealedHeader,
+        prev: &BlockHeader,
+    ) -> Result<(), HorizError>;
+
+    /// Produces the seal that makes `header` valid under this engine.
+    fn seal_block(&self, header: BlockHeader) -> Result<SealedHeader, HorizError>;
+}
+
+/// Checks that `header` names `prev` as its parent and sits one above it.
+fn check_link(header: &BlockHeader, prev: &BlockHeader) -> Result<(), HorizError> {
+    if header.prev_block_id != prev.block_id()? {
+        return Err(invalid("header does not extend the previous block"));
+    }
+    if prev.height.checked_add(1) != Some(header.height) {
+        return Err(invalid(format!(
+            "height {} does not follow {}",
+            header.height, prev.height
+        )));
     }
+    Ok(())
+}
+
+fn invalid(reason: impl Into<String>) -> HorizError {
+    HorizError::InvalidBlock(reason.into())
 }
@@ -0,0 +1,161 @@
+//! Proof of work for test networks.
+
+use horizcoin_block::BlockHeader;
+use horizcoin_crypto::sha256;
+use horizcoin_primitives::{HorizError, HASH_LEN};
+
+use crate::{check_link, invalid, Consensus, SealedHeader};
+
+/// Returns the largest proof-of-work hash, read as a big-endian integer,
+/// that meets `difficulty`: `(2^256 - 1) / difficulty`.
+///
+/// A difficulty of `0` is treated as `1`, the easiest target.
+#[must_use]
+pub fn difficulty_to_target(difficulty: u32) -> [u8; HASH_LEN] {
+    let divisor = u64::from(difficulty.max(1));
+    let mut target = [0u8; HASH_LEN];
+    let mut remainder = 0u64;
+    for byte in &mut target {
+        let dividend = (remainder << 8) | 0xff;
+        // The quotient fits in a byte since the remainder is below the divisor.
+        *byte = u8::try_from(dividend / divisor).unwrap_or(u8::MAX);
+        remainder = dividend % divisor;
+    }
+    target
+}
+
+/// Proof of work: a header is valid if the `SHA-256` of its encoding, read
+/// as a big-endian integer, does not exceed the target for its
+/// [`difficulty`](BlockHeader::difficulty).
+#[derive(Debug, Clone, Copy)]
+pub struct PowConsensus {
+    min_difficulty: u32,
+}
+
+impl PowConsensus {
+    /// Creates an engine rejecting headers that claim less than
+    /// `min_difficulty`.
+    #[must_use]
+    pub const fn new(min_difficulty: u32) -> Self {
+        Self { min_difficulty }
+    }
+
+    /// Returns `true` if `header` meets the target for its difficulty.
+    pub fn meets_target(header: &BlockHeader) -> Result<bool, HorizError> {
+        let hash = sha256(&horizcoin_codec::encode(header)?);
+        Ok(*hash.as_bytes() <= difficulty_to_target(header.difficulty))
+    }
+}
+
+impl Consensus for PowConsensus {
+    fn validate_block_header(
+        &self,
+        header: &SealedHeader,
+        prev: &BlockHeader,
+    ) -> Result<(), HorizError> {
+        check_link(&header.header, prev)?;
+        if !header.seal.is_empty() {
+            return Err(invalid("proof-of-work headers carry no seal"));
+        }
+        if header.header.difficulty < self.min_difficulty {
+            return Err(invalid(format!(
+                "difficulty {} is below the minimum of {}",
+                header.header.difficulty, self.min_difficulty
+            )));
+        }
+        if !Self::meets_target(&header.header)? {
+            return Err(invalid(format!(
+                "hash does not meet difficulty {}",
+                header.header.difficulty
+            )));
+        }
+        Ok(())
+    }
+
+    /// Searches nonces upwards from the header's own until the target is
+    /// met, failing if they run out.
+    fn seal_block(&self, mut header: BlockHeader) -> Result<SealedHeader, HorizError> {
+        while !Self::meets_target(&header)? {
+            header.nonce = header
+                .nonce
+                .checked_add(1)
+                .ok_or_else(|| invalid("nonce space exhausted"))?;
+        }
+        Ok(SealedHeader {
+            header,
+            seal: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_primitives::{BlockId, Hash};
+
+    use super::*;
+
+    fn header(prev: &BlockHeader, difficulty: u32) -> BlockHeader {
+        BlockHeader {
+            prev_block_id: prev.block_id().unwrap(),
+            merkle_root: Hash::new([3; 32]),
+            timestamp: prev.timestamp + 60,
+            height: prev.height + 1,
+            difficulty,
+            nonce: 0,
+        }
+    }
+
+    fn genesis() -> BlockHeader {
+        BlockHeader {
+            prev_block_id: BlockId::ZERO,
+            merkle_root: Hash::ZERO,
+            timestamp: 1_700_000_000,
+            height: 0,
+            difficulty: 1,
+            nonce: 0,
+        }
+    }
+
+    #[test]
+    fn targets_shrink_with_difficulty() {
+        assert_eq!(difficulty_to_target(0), [0xff; HASH_LEN]);
+        assert_eq!(difficulty_to_target(1), [0xff; HASH_LEN]);
+        let half = difficulty_to_target(2);
+        assert_eq!(half[0], 0x7f);
+        assert!(half[1..].iter().all(|&byte| byte == 0xff));
+        let hardest = difficulty_to_target(u32::MAX);
+        assert_eq!(hardest[..4], [0, 0, 0, 1]);
+        assert!(difficulty_to_target(1_000) < difficulty_to_target(999));
+    }
+
+    #[test]
+    fn low_difficulty_seals_quickly() {
+        let engine = PowConsensus::new(16);
+        let prev = genesis();
+        let sealed = engine.seal_block(header(&prev, 256)).unwrap();
+        assert!(sealed.header.nonce < 100_000);
+        assert_eq!(engine.validate_block_header(&sealed, &prev), Ok(()));
+
+        let mut with_seal = sealed.clone();
+        with_seal.seal = vec![1];
+        assert!(engine.validate_block_header(&with_seal, &prev).is_err());
+        assert!(PowConsensus::new(512)
+            .validate_block_header(&sealed, &prev)
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_header_missing_target() {
+        let engine = PowConsensus::new(1);
+        let prev = genesis();
+        let unsealed = SealedHeader {
+            header: header(&prev, u32::MAX),
+            seal: Vec::new(),
+        };
+        assert!(!PowConsensus::meets_target(&unsealed.header).unwrap());
+        assert!(matches!(
+            engine.validate_block_header(&unsealed, &prev),
+            Err(HorizError::InvalidBlock(_))
+        ));
+    }
+}
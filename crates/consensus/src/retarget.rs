@@ -0,0 +1,116 @@
+//! Difficulty adjustment for proof of work.
+
+use horizcoin_block::BlockHeader;
+
+/// Number of block intervals the adjustment looks back over.
+pub const RETARGET_WINDOW: usize = 120;
+
+/// Largest factor by which one adjustment may raise or lower difficulty.
+pub const MAX_ADJUSTMENT_FACTOR: u64 = 4;
+
+/// Returns the difficulty for the block after `prev_headers`, which are in
+/// chain order.
+///
+/// The difficulty of the last header is scaled by how much faster than
+/// `target_spacing` seconds per block the last [`RETARGET_WINDOW`]
+/// intervals were produced, by at most [`MAX_ADJUSTMENT_FACTOR`] either
+/// way. With fewer than two headers there is no interval to measure and
+/// the last difficulty, or `1`, is kept. Pass
+/// [`TARGET_BLOCK_TIME`](horizcoin_primitives::constants::TARGET_BLOCK_TIME)
+/// as `target_spacing` on the main chain.
+#[must_use]
+pub fn retarget(prev_headers: &[BlockHeader], target_spacing: u64) -> u32 {
+    let window = &prev_headers[prev_headers.len().saturating_sub(RETARGET_WINDOW + 1)..];
+    let (Some(first), Some(last)) = (window.first(), window.last()) else {
+        return 1;
+    };
+    let current = last.difficulty.max(1);
+    let intervals = u64::try_from(window.len() - 1).unwrap_or(u64::MAX);
+    let expected = intervals.saturating_mul(target_spacing);
+    if expected == 0 {
+        return current;
+    }
+
+    let actual = last.timestamp.saturating_sub(first.timestamp).clamp(
+        expected / MAX_ADJUSTMENT_FACTOR,
+        expected.saturating_mul(MAX_ADJUSTMENT_FACTOR),
+    );
+    let scaled = u128::from(current) * u128::from(expected) / u128::from(actual.max(1));
+    u32::try_from(scaled).unwrap_or(u32::MAX).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_primitives::constants::TARGET_BLOCK_TIME;
+    use horizcoin_primitives::{BlockId, Hash};
+
+    use super::*;
+
+    fn chain(count: u64, spacing: u64, difficulty: u32) -> Vec<BlockHeader> {
+        (0..count)
+            .map(|height| BlockHeader {
+                prev_block_id: BlockId::ZERO,
+                merkle_root: Hash::ZERO,
+                timestamp: 1_700_000_000 + height * spacing,
+                height,
+                difficulty,
+                nonce: 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn on_schedule_keeps_difficulty() {
+        let headers = chain(11, TARGET_BLOCK_TIME, 1_000);
+        assert_eq!(retarget(&headers, TARGET_BLOCK_TIME), 1_000);
+        assert_eq!(retarget(&headers[..1], TARGET_BLOCK_TIME), 1_000);
+        assert_eq!(retarget(&[], TARGET_BLOCK_TIME), 1);
+    }
+
+    #[test]
+    fn fast_blocks_raise_difficulty() {
+        let spacing = TARGET_BLOCK_TIME / 2;
+        assert_eq!(
+            retarget(&chain(11, spacing, 1_000), TARGET_BLOCK_TIME),
+            2_000
+        );
+
+        let quarter = TARGET_BLOCK_TIME / 4;
+        assert_eq!(
+            retarget(&chain(11, quarter, 1_000), TARGET_BLOCK_TIME),
+            4_000
+        );
+        assert_eq!(retarget(&chain(11, 1, 1_000), TARGET_BLOCK_TIME), 4_000);
+        assert_eq!(retarget(&chain(11, 0, 1_000), TARGET_BLOCK_TIME), 4_000);
+        assert_eq!(
+            retarget(&chain(11, 1, u32::MAX), TARGET_BLOCK_TIME),
+            u32::MAX
+        );
+    }
+
+    #[test]
+    fn slow_blocks_lower_difficulty() {
+        let spacing = TARGET_BLOCK_TIME * 2;
+        assert_eq!(retarget(&chain(11, spacing, 1_000), TARGET_BLOCK_TIME), 500);
+
+        let quadruple = TARGET_BLOCK_TIME * 4;
+        assert_eq!(
+            retarget(&chain(11, quadruple, 1_000), TARGET_BLOCK_TIME),
+            250
+        );
+        assert_eq!(retarget(&chain(11, 10_000, 1_000), TARGET_BLOCK_TIME), 250);
+        assert_eq!(retarget(&chain(11, 10_000, 2), TARGET_BLOCK_TIME), 1);
+    }
+
+    #[test]
+    fn only_the_window_counts() {
+        let mut headers = chain(RETARGET_WINDOW as u64 + 1, TARGET_BLOCK_TIME, 1_000);
+        // Ancient slow blocks before the window are ignored.
+        let mut old = chain(5, 10_000, 1_000);
+        for header in &mut headers {
+            header.timestamp += 50_000;
+        }
+        old.append(&mut headers);
+        assert_eq!(retarget(&old, TARGET_BLOCK_TIME), 1_000);
+    }
+}
@@ -0,0 +1,69 @@
+//! Block subsidy schedule.
+
+use horizcoin_primitives::constants::INITIAL_BLOCK_REWARD;
+use horizcoin_primitives::Amount;
+
+/// Number of blocks between halvings: about four years at the target block
+/// time.
+pub const HALVING_INTERVAL: u64 = 2_102_400;
+
+/// Returns the subsidy of the block at `height`:
+/// [`INITIAL_BLOCK_REWARD`] halved once per completed [`HALVING_INTERVAL`],
+/// reaching `0` once every bit has been shifted out.
+#[must_use]
+pub const fn block_reward(height: u64) -> Amount {
+    let halvings = height / HALVING_INTERVAL;
+    if halvings >= u64::BITS as u64 {
+        Amount::ZERO
+    } else {
+        Amount::new(INITIAL_BLOCK_REWARD.as_u64() >> halvings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_primitives::constants::MAX_MONEY;
+
+    use super::*;
+
+    #[test]
+    fn reward_halves_on_schedule() {
+        assert_eq!(block_reward(0), INITIAL_BLOCK_REWARD);
+        assert_eq!(block_reward(HALVING_INTERVAL - 1), INITIAL_BLOCK_REWARD);
+        assert_eq!(
+            block_reward(HALVING_INTERVAL).as_u64(),
+            INITIAL_BLOCK_REWARD.as_u64() / 2
+        );
+        assert_eq!(
+            block_reward(2 * HALVING_INTERVAL).as_u64(),
+            INITIAL_BLOCK_REWARD.as_u64() / 4
+        );
+    }
+
+    #[test]
+    fn reward_reaches_zero() {
+        let last_nonzero = u64::from(INITIAL_BLOCK_REWARD.as_u64().ilog2());
+        assert_eq!(
+            block_reward(last_nonzero * HALVING_INTERVAL),
+            Amount::new(1)
+        );
+        assert_eq!(
+            block_reward((last_nonzero + 1) * HALVING_INTERVAL),
+            Amount::ZERO
+        );
+        assert_eq!(block_reward(64 * HALVING_INTERVAL), Amount::ZERO);
+        assert_eq!(block_reward(u64::MAX), Amount::ZERO);
+    }
+
+    #[test]
+    fn issuance_converges() {
+        let total = (0..64).fold(0u128, |sum, halving| {
+            sum + u128::from(block_reward(halving * HALVING_INTERVAL).as_u64())
+                * u128::from(HALVING_INTERVAL)
+        });
+        assert!(
+            total < 2 * u128::from(INITIAL_BLOCK_REWARD.as_u64()) * u128::from(HALVING_INTERVAL)
+        );
+        assert!(total <= u128::from(MAX_MONEY.as_u64()));
+    }
+}
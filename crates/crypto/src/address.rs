@@ -0,0 +1,232 @@
+//! Bech32m addresses derived from public keys.
+//!
+//! An address encodes `ADDRESS_VERSION || HASH160(compressed_pubkey)` with
+//! the prefix of its [`Network`]. The version byte lets the derivation change
+//! without old and new addresses being confused for one another.
+
+use bech32::{primitives::decode::CheckedHrpstring, Bech32, Bech32m, Hrp};
+use horizcoin_primitives::HorizError;
+
+use crate::{hash160, Network, PrivateKey, PublicKey};
+
+/// Version byte for addresses whose program is `RIPEMD-160(SHA-256(pubkey))`.
+pub const ADDRESS_VERSION: u8 = 1;
+
+/// Length of the hashed public key carried by an address.
+pub const ADDRESS_PROGRAM_LEN: usize = 20;
+
+/// The characters a bech32 data part may contain.
+const BECH32_CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+impl PublicKey {
+    /// Returns the mainnet bech32m address for this key.
+    #[must_use]
+    pub fn to_address(&self) -> String {
+        self.to_address_for(Network::Mainnet)
+    }
+
+    /// Returns the bech32m address for this key on `network`.
+    #[must_use]
+    pub fn to_address_for(&self, network: Network) -> String {
+        encode_address(network, &hash160(&self.to_bytes()))
+    }
+}
+
+/// Encodes `program` as a bech32m address on `network`; the inverse of
+/// [`parse_address`].
+#[must_use]
+pub fn encode_address(network: Network, program: &[u8; ADDRESS_PROGRAM_LEN]) -> String {
+    let mut payload = [0u8; 1 + ADDRESS_PROGRAM_LEN];
+    payload[0] = ADDRESS_VERSION;
+    payload[1..].copy_from_slice(program);
+    bech32::encode::<Bech32m>(Hrp::parse_unchecked(network.hrp()), &payload)
+        .expect("address payload is within bech32 limits")
+}
+
+/// Decodes `address` and returns its network and 20-byte program.
+///
+/// Only bech32m strings with a known network prefix and the current
+/// [`ADDRESS_VERSION`] are accepted. A string carrying a valid plain bech32
+/// checksum instead is refused with [`HorizError::Crypto`], so the two
+/// checksum variants are never confused for one another.
+pub fn parse_address(address: &str) -> Result<(Network, [u8; ADDRESS_PROGRAM_LEN]), HorizError> {
+    let checked = CheckedHrpstring::new::<Bech32m>(address).map_err(|e| {
+        if CheckedHrpstring::new::<Bech32>(address).is_ok() {
+            HorizError::Crypto("address uses a bech32 checksum, expected bech32m".into())
+        } else {
+            HorizError::InvalidAddress(e.to_string())
+        }
+    })?;
+    let hrp = checked.hrp();
+    let network = Network::from_hrp(hrp.as_str())
+        .ok_or_else(|| HorizError::InvalidAddress(format!("unknown prefix {hrp}")))?;
+
+    let payload: Vec<u8> = checked.byte_iter().collect();
+    let (&version, program) = payload
+        .split_first()
+        .ok_or_else(|| HorizError::InvalidAddress("empty payload".into()))?;
+    if version != ADDRESS_VERSION {
+        return Err(HorizError::InvalidAddress(format!(
+            "unsupported address version {version}"
+        )));
+    }
+    let program = program.try_into().map_err(|_| {
+        HorizError::InvalidAddress(format!(
+            "program must be {ADDRESS_PROGRAM_LEN} bytes, got {}",
+            program.len()
+        ))
+    })?;
+    Ok((network, program))
+}
+
+/// Returns `true` if `address` parses with [`parse_address`] and, when
+/// `expected` is given, belongs to that network.
+#[must_use]
+pub fn is_valid_address(address: &str, expected: Option<Network>) -> bool {
+    parse_address(address)
+        .is_ok_and(|(network, _)| expected.map_or(true, |expected| expected == network))
+}
+
+/// Generates keys until one's mainnet address starts with `prefix` right
+/// after `hz1`, giving up after `max_attempts` keys.
+///
+/// Returns the key and its address, or `None` if no match was found. Fails
+/// up front if `prefix` contains a character bech32 cannot encode.
+///
+/// The first characters are not free: the version byte makes every address
+/// continue with `q` followed by one of `y`, `9`, `x` or `8`, so a prefix
+/// starting any other way is never found.
+pub fn find_vanity_address(
+    prefix: &str,
+    max_attempts: u64,
+) -> Result<Option<(PrivateKey, String)>, HorizError> {
+    if let Some(c) = prefix.chars().find(|&c| !BECH32_CHARSET.contains(c)) {
+        return Err(HorizError::InvalidAddress(format!(
+            "{c:?} is not a bech32 character"
+        )));
+    }
+    let data_start = Network::Mainnet.hrp().len() + 1;
+    for _ in 0..max_attempts {
+        let key = PrivateKey::generate();
+        let address = key.public_key().to_address();
+        if address[data_start..].starts_with(prefix) {
+            return Ok(Some((key, address)));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PrivateKey, PRIVATE_KEY_LEN};
+
+    const GENERATOR_HASH160: &str = "751e76e8199196d454941c45d1b3a323f1433bd6";
+
+    fn key_one() -> PublicKey {
+        let mut bytes = [0u8; PRIVATE_KEY_LEN];
+        bytes[PRIVATE_KEY_LEN - 1] = 1;
+        PrivateKey::from_bytes(&bytes).unwrap().public_key()
+    }
+
+    #[test]
+    fn address_pins_hash160_of_generator() {
+        let key = key_one();
+        assert_eq!(hex::encode(hash160(&key.to_bytes())), GENERATOR_HASH160);
+        let address = key.to_address();
+        assert_eq!(address, "hz1q963uahgrxged4z5jswyt5dn5v3lzsem6curp6nl");
+        let (network, program) = parse_address(&address).unwrap();
+        assert_eq!(network, Network::Mainnet);
+        assert_eq!(hex::encode(program), GENERATOR_HASH160);
+    }
+
+    #[test]
+    fn each_network_round_trips_with_its_prefix() {
+        let key = key_one();
+        for network in Network::ALL {
+            let address = key.to_address_for(network);
+            assert!(address.starts_with(&format!("{}1", network.hrp())));
+            let (parsed, program) = parse_address(&address).unwrap();
+            assert_eq!(parsed, network);
+            assert_eq!(hex::encode(program), GENERATOR_HASH160);
+        }
+    }
+
+    #[test]
+    fn cross_network_addresses_are_rejected_when_network_required() {
+        let key = key_one();
+        let testnet = key.to_address_for(Network::Testnet);
+        assert!(is_valid_address(&testnet, None));
+        assert!(is_valid_address(&testnet, Some(Network::Testnet)));
+        assert!(!is_valid_address(&testnet, Some(Network::Mainnet)));
+        assert!(!is_valid_address(&testnet, Some(Network::Regtest)));
+        assert!(!is_valid_address(&key.to_address(), Some(Network::Testnet)));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_addresses() {
+        let address = key_one().to_address();
+        assert!(is_valid_address(&address, None));
+
+        let mut corrupted = address.into_bytes();
+        let last = corrupted.len() - 1;
+        corrupted[last] = if corrupted[last] == b'q' { b'p' } else { b'q' };
+        assert!(!is_valid_address(
+            std::str::from_utf8(&corrupted).unwrap(),
+            None
+        ));
+
+        let program = [0u8; ADDRESS_PROGRAM_LEN];
+        let mut other_version = vec![ADDRESS_VERSION + 1];
+        other_version.extend_from_slice(&program);
+        let hrp = Hrp::parse_unchecked(Network::Mainnet.hrp());
+        let encoded = bech32::encode::<Bech32m>(hrp, &other_version).unwrap();
+        assert!(!is_valid_address(&encoded, None));
+
+        let unknown_hrp =
+            bech32::encode::<Bech32m>(Hrp::parse_unchecked("bc"), &other_version).unwrap();
+        assert!(!is_valid_address(&unknown_hrp, None));
+    }
+
+    #[test]
+    fn plain_bech32_checksum_is_rejected() {
+        let mut payload = vec![ADDRESS_VERSION];
+        payload.extend_from_slice(&hash160(&key_one().to_bytes()));
+        let hrp = Hrp::parse_unchecked(Network::Mainnet.hrp());
+
+        let bech32m = bech32::encode::<Bech32m>(hrp, &payload).unwrap();
+        assert_eq!(bech32m, key_one().to_address());
+        assert!(parse_address(&bech32m).is_ok());
+
+        let bech32 = bech32::encode::<Bech32>(hrp, &payload).unwrap();
+        assert_ne!(bech32, bech32m);
+        assert_eq!(
+            parse_address(&bech32),
+            Err(HorizError::Crypto(
+                "address uses a bech32 checksum, expected bech32m".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn vanity_search_finds_the_prefix() {
+        let (key, address) = find_vanity_address("q", 1).unwrap().unwrap();
+        assert_eq!(address, key.public_key().to_address());
+        assert!(address.starts_with("hz1q"));
+
+        // One in four keys matches; 1000 misses in a row is not a concern.
+        let (key, address) = find_vanity_address("q8", 1_000).unwrap().unwrap();
+        assert_eq!(address, key.public_key().to_address());
+        assert!(address.starts_with("hz1q8"));
+        assert!(is_valid_address(&address, Some(Network::Mainnet)));
+
+        assert!(find_vanity_address("q8", 0).unwrap().is_none());
+    }
+
+    #[test]
+    fn vanity_prefix_must_be_bech32() {
+        for prefix in ["b", "qi", "Q", "q1"] {
+            assert!(find_vanity_address(prefix, 1).is_err(), "{prefix}");
+        }
+    }
+}
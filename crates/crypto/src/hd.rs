@@ -0,0 +1,204 @@
+//! Hierarchical deterministic key derivation (`BIP32`).
+//!
+//! Only private derivation is supported: an [`ExtendedPrivateKey`] is a
+//! [`PrivateKey`] plus a 32-byte chain code, and children are derived with
+//! `HMAC-SHA512` over `secp256k1`.
+
+use std::fmt;
+
+use hmac::{Hmac, Mac};
+use horizcoin_primitives::HorizError;
+use k256::{elliptic_curve::PrimeField, FieldBytes, Scalar};
+use sha2::Sha512;
+
+use crate::PrivateKey;
+
+/// Child indices at or above this value use hardened derivation.
+pub const HARDENED_OFFSET: u32 = 0x8000_0000;
+
+/// `HMAC` key used to derive the master key from a seed.
+const MASTER_HMAC_KEY: &[u8] = b"Bitcoin seed";
+
+/// Accepted seed lengths in bytes.
+const SEED_LEN_RANGE: std::ops::RangeInclusive<usize> = 16..=64;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// A private key together with the chain code needed to derive children.
+#[derive(Clone)]
+pub struct ExtendedPrivateKey {
+    key: PrivateKey,
+    chain_code: [u8; 32],
+    depth: u8,
+    child_number: u32,
+}
+
+impl ExtendedPrivateKey {
+    /// Derives the master key from a 16 to 64 byte seed.
+    pub fn from_seed(seed: &[u8]) -> Result<Self, HorizError> {
+        if !SEED_LEN_RANGE.contains(&seed.len()) {
+            return Err(HorizError::Crypto(format!(
+                "seed must be {} to {} bytes, got {}",
+                SEED_LEN_RANGE.start(),
+                SEED_LEN_RANGE.end(),
+                seed.len()
+            )));
+        }
+        let (il, ir) = hmac_sha512(MASTER_HMAC_KEY, &[seed]);
+        let key = PrivateKey::from_bytes(&il)
+            .map_err(|_| HorizError::Crypto("seed produced an invalid master key".into()))?;
+        Ok(Self {
+            key,
+            chain_code: ir,
+            depth: 0,
+            child_number: 0,
+        })
+    }
+
+    /// Derives the child at `index`; indices from [`HARDENED_OFFSET`] up are
+    /// hardened.
+    pub fn derive_child(&self, index: u32) -> Result<Self, HorizError> {
+        let depth = self
+            .depth
+            .checked_add(1)
+            .ok_or_else(|| HorizError::Crypto("maximum derivation depth reached".into()))?;
+
+        let index_bytes = index.to_be_bytes();
+        let (il, ir) = if index >= HARDENED_OFFSET {
+            hmac_sha512(
+                &self.chain_code,
+                &[&[0], self.key.to_bytes().as_slice(), &index_bytes],
+            )
+        } else {
+            let public = self.key.public_key().to_bytes();
+            hmac_sha512(&self.chain_code, &[&public, &index_bytes])
+        };
+
+        let tweak = parse_scalar(&il)
+            .ok_or_else(|| HorizError::Crypto(format!("child {index} is invalid")))?;
+        let parent = parse_scalar(&self.key.to_bytes())
+            .ok_or_else(|| HorizError::Crypto("invalid parent key".into()))?;
+        let child: [u8; 32] = (tweak + parent).to_repr().into();
+        let key = PrivateKey::from_bytes(&child)
+            .map_err(|_| HorizError::Crypto(format!("child {index} is invalid")))?;
+
+        Ok(Self {
+            key,
+            chain_code: ir,
+            depth,
+            child_number: index,
+        })
+    }
+
+    /// Derives a descendant by applying [`Self::derive_child`] for each index.
+    pub fn derive_path(&self, path: &[u32]) -> Result<Self, HorizError> {
+        path.iter()
+            .try_fold(self.clone(), |key, &index| key.derive_child(index))
+    }
+
+    /// Returns the signing key at this node.
+    #[must_use]
+    pub const fn private_key(&self) -> &PrivateKey {
+        &self.key
+    }
+
+    /// Returns the chain code at this node.
+    #[must_use]
+    pub const fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    /// Returns the number of derivation steps from the master key.
+    #[must_use]
+    pub const fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Returns the index this key was derived with (`0` for the master key).
+    #[must_use]
+    pub const fn child_number(&self) -> u32 {
+        self.child_number
+    }
+}
+
+impl fmt::Debug for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtendedPrivateKey")
+            .field("depth", &self.depth)
+            .field("child_number", &self.child_number)
+            .finish_non_exhaustive()
+    }
+}
+
+fn hmac_sha512(key: &[u8], parts: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
+    let mut mac = HmacSha512::new_from_slice(key).expect("HMAC accepts keys of any length");
+    for part in parts {
+        mac.update(part);
+    }
+    let output = mac.finalize().into_bytes();
+    let mut il = [0u8; 32];
+    let mut ir = [0u8; 32];
+    il.copy_from_slice(&output[..32]);
+    ir.copy_from_slice(&output[32..]);
+    (il, ir)
+}
+
+/// Parses a big-endian scalar, rejecting values not below the curve order.
+fn parse_scalar(bytes: &[u8; 32]) -> Option<Scalar> {
+    Scalar::from_repr(FieldBytes::clone_from_slice(bytes)).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed() -> Vec<u8> {
+        hex::decode("000102030405060708090a0b0c0d0e0f").unwrap()
+    }
+
+    fn assert_node(node: &ExtendedPrivateKey, key: &str, chain_code: &str) {
+        assert_eq!(hex::encode(node.private_key().to_bytes()), key);
+        assert_eq!(hex::encode(node.chain_code()), chain_code);
+    }
+
+    #[test]
+    fn bip32_test_vector_1() {
+        let master = ExtendedPrivateKey::from_seed(&seed()).unwrap();
+        assert_node(
+            &master,
+            "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35",
+            "873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508",
+        );
+
+        let m_0h = master.derive_child(HARDENED_OFFSET).unwrap();
+        assert_node(
+            &m_0h,
+            "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea",
+            "47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141",
+        );
+
+        let m_0h_1 = m_0h.derive_child(1).unwrap();
+        assert_node(
+            &m_0h_1,
+            "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368",
+            "2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19",
+        );
+
+        let m_0h_1_2h = master
+            .derive_path(&[HARDENED_OFFSET, 1, HARDENED_OFFSET + 2])
+            .unwrap();
+        assert_node(
+            &m_0h_1_2h,
+            "cbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca",
+            "04466b9cc8e161e966409ca52986c584f07e9dc81f735db683c3ff6ec7b1503f",
+        );
+        assert_eq!(m_0h_1_2h.depth(), 3);
+        assert_eq!(m_0h_1_2h.child_number(), HARDENED_OFFSET + 2);
+    }
+
+    #[test]
+    fn rejects_bad_seed_length() {
+        assert!(ExtendedPrivateKey::from_seed(&[0u8; 15]).is_err());
+        assert!(ExtendedPrivateKey::from_seed(&[0u8; 65]).is_err());
+    }
+}
@@ -0,0 +1,436 @@
+//! `secp256k1` key pairs and `ECDSA` signatures.
+//!
+//! Messages are hashed with `SHA-256` before signing. Signatures are the
+//! fixed 64-byte `r || s` encoding, always produced in low-S form so a
+//! signature cannot be altered into a second valid one. Public keys
+//! serialize as hex in human-readable formats and as raw compressed bytes
+//! otherwise.
+
+use std::{fmt, num::NonZeroUsize, thread};
+
+use horizcoin_primitives::HorizError;
+use k256::ecdsa::{
+    signature::{RandomizedSigner, Signer, Verifier},
+    RecoveryId, Signature, SigningKey, VerifyingKey,
+};
+use rand_core::OsRng;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::{ZeroizeOnDrop, Zeroizing};
+
+/// Length of a serialized private key.
+pub const PRIVATE_KEY_LEN: usize = 32;
+
+/// Length of a compressed `SEC1` public key.
+pub const PUBLIC_KEY_LEN: usize = 33;
+
+/// Length of an uncompressed `SEC1` public key.
+pub const UNCOMPRESSED_PUBLIC_KEY_LEN: usize = 65;
+
+/// Length of a compact `r || s` signature.
+pub const SIGNATURE_LEN: usize = 64;
+
+/// Length of a recoverable `r || s || recovery_id` signature.
+pub const RECOVERABLE_SIGNATURE_LEN: usize = 65;
+
+/// Batches smaller than this are verified on the calling thread.
+const PARALLEL_VERIFY_THRESHOLD: usize = 16;
+
+/// A `secp256k1` signing key.
+///
+/// The secret scalar is overwritten with zeros when the key is dropped, and
+/// [`PrivateKey::to_bytes`] hands out a [`Zeroizing`] buffer so exported
+/// copies are wiped as well. `Debug` output never includes the secret.
+#[derive(Clone)]
+pub struct PrivateKey(SigningKey);
+
+impl PrivateKey {
+    /// Generates a new random key from the operating system RNG.
+    #[must_use]
+    pub fn generate() -> Self {
+        Self(SigningKey::random(&mut OsRng))
+    }
+
+    /// Parses a 32-byte scalar.
+    pub fn from_bytes(bytes: &[u8; PRIVATE_KEY_LEN]) -> Result<Self, HorizError> {
+        SigningKey::from_bytes(bytes.into())
+            .map(Self)
+            .map_err(|e| HorizError::Crypto(format!("invalid private key: {e}")))
+    }
+
+    /// Returns the 32-byte scalar in a buffer that is zeroized on drop.
+    #[must_use]
+    pub fn to_bytes(&self) -> Zeroizing<[u8; PRIVATE_KEY_LEN]> {
+        Zeroizing::new(self.0.to_bytes().into())
+    }
+
+    /// Returns the matching public key.
+    #[must_use]
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(*self.0.verifying_key())
+    }
+
+    /// Signs `message` with a randomized nonce.
+    ///
+    /// The signature is normalized to low-S, so it passes
+    /// [`PublicKey::verify_strict`].
+    #[must_use]
+    pub fn sign(&self, message: &[u8]) -> [u8; SIGNATURE_LEN] {
+        let signature: Signature = self.0.sign_with_rng(&mut OsRng, message);
+        to_low_s(&signature)
+    }
+
+    /// Signs `message` with an `RFC 6979` deterministic nonce.
+    ///
+    /// Signing the same message with the same key always yields the same
+    /// signature, which keeps test vectors and fixtures reproducible.
+    #[must_use]
+    pub fn sign_deterministic(&self, message: &[u8]) -> [u8; SIGNATURE_LEN] {
+        let signature: Signature = self.0.sign(message);
+        to_low_s(&signature)
+    }
+
+    /// Signs `message` so the public key can be recovered from the signature.
+    ///
+    /// The result is the 64-byte signature followed by the recovery id. The
+    /// nonce is deterministic (`RFC 6979`).
+    #[must_use]
+    pub fn sign_recoverable(&self, message: &[u8]) -> [u8; RECOVERABLE_SIGNATURE_LEN] {
+        let (signature, recovery_id) = self
+            .0
+            .sign_recoverable(message)
+            .expect("signing with a valid key cannot fail");
+        let mut out = [0u8; RECOVERABLE_SIGNATURE_LEN];
+        out[..SIGNATURE_LEN].copy_from_slice(&signature.to_bytes());
+        out[SIGNATURE_LEN] = recovery_id.to_byte();
+        out
+    }
+}
+
+/// Encodes `signature` with `s` replaced by `n - s` if it is in the upper
+/// half of the curve order.
+fn to_low_s(signature: &Signature) -> [u8; SIGNATURE_LEN] {
+    signature
+        .normalize_s()
+        .unwrap_or(*signature)
+        .to_bytes()
+        .into()
+}
+
+// `SigningKey` zeroizes its scalar in its own `Drop`, which runs when ours does.
+impl ZeroizeOnDrop for PrivateKey {}
+
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PrivateKey(..)")
+    }
+}
+
+/// A `secp256k1` verifying key.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PublicKey(VerifyingKey);
+
+impl PublicKey {
+    /// Parses a 33-byte compressed `SEC1` encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, HorizError> {
+        if bytes.len() != PUBLIC_KEY_LEN {
+            return Err(HorizError::Crypto(format!(
+                "public key must be {PUBLIC_KEY_LEN} bytes, got {}",
+                bytes.len()
+            )));
+        }
+        Self::from_sec1(bytes)
+    }
+
+    /// Parses either a 33-byte compressed or a 65-byte uncompressed `SEC1`
+    /// encoding.
+    ///
+    /// Both encodings of a key yield the same [`PublicKey`], and so the same
+    /// address, since addresses always hash the compressed form.
+    pub fn from_sec1(bytes: &[u8]) -> Result<Self, HorizError> {
+        if bytes.len() != PUBLIC_KEY_LEN && bytes.len() != UNCOMPRESSED_PUBLIC_KEY_LEN {
+            return Err(HorizError::Crypto(format!(
+                "public key must be {PUBLIC_KEY_LEN} or {UNCOMPRESSED_PUBLIC_KEY_LEN} bytes, got {}",
+                bytes.len()
+            )));
+        }
+        VerifyingKey::from_sec1_bytes(bytes)
+            .map(Self)
+            .map_err(|e| HorizError::Crypto(format!("invalid public key: {e}")))
+    }
+
+    /// Returns the 65-byte uncompressed `SEC1` encoding.
+    #[must_use]
+    pub fn to_uncompressed_bytes(&self) -> [u8; UNCOMPRESSED_PUBLIC_KEY_LEN] {
+        let point = self.0.to_encoded_point(false);
+        let mut out = [0u8; UNCOMPRESSED_PUBLIC_KEY_LEN];
+        out.copy_from_slice(point.as_bytes());
+        out
+    }
+
+    /// Returns the 33-byte compressed `SEC1` encoding.
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_LEN] {
+        let point = self.0.to_encoded_point(true);
+        let mut out = [0u8; PUBLIC_KEY_LEN];
+        out.copy_from_slice(point.as_bytes());
+        out
+    }
+
+    /// Recovers the key that produced a
+    /// [`PrivateKey::sign_recoverable`] signature.
+    pub fn recover(
+        message: &[u8],
+        recoverable_sig: &[u8; RECOVERABLE_SIGNATURE_LEN],
+    ) -> Result<Self, HorizError> {
+        let signature = Signature::from_slice(&recoverable_sig[..SIGNATURE_LEN])
+            .map_err(|e| HorizError::Crypto(format!("invalid signature: {e}")))?;
+        let recovery_id = RecoveryId::from_byte(recoverable_sig[SIGNATURE_LEN])
+            .ok_or_else(|| HorizError::Crypto("invalid recovery id".into()))?;
+        VerifyingKey::recover_from_msg(message, &signature, recovery_id)
+            .map(Self)
+            .map_err(|e| HorizError::Crypto(format!("public key recovery failed: {e}")))
+    }
+
+    /// Returns `true` if `signature` is valid for `message` under this key,
+    /// in either its low-S or its high-S form.
+    ///
+    /// Anything whose identity covers the signature, such as a transaction,
+    /// must use [`verify_strict`](Self::verify_strict) instead.
+    #[must_use]
+    pub fn verify(&self, message: &[u8], signature: &[u8; SIGNATURE_LEN]) -> bool {
+        Signature::from_slice(signature).is_ok_and(|sig| {
+            let sig = sig.normalize_s().unwrap_or(sig);
+            self.0.verify(message, &sig).is_ok()
+        })
+    }
+
+    /// Like [`verify`](Self::verify), but rejects signatures whose `s` is in
+    /// the upper half of the curve order, leaving exactly one valid
+    /// encoding per signature.
+    #[must_use]
+    pub fn verify_strict(&self, message: &[u8], signature: &[u8; SIGNATURE_LEN]) -> bool {
+        Signature::from_slice(signature)
+            .is_ok_and(|sig| sig.normalize_s().is_none() && self.0.verify(message, &sig).is_ok())
+    }
+}
+
+/// Verifies many `(key, message, signature)` triples with
+/// [`PublicKey::verify_strict`], returning `true` only if every signature is
+/// valid.
+///
+/// `ECDSA` has no batch verification equation, so large batches are split
+/// across threads instead.
+#[must_use]
+pub fn verify_batch(items: &[(PublicKey, Vec<u8>, [u8; SIGNATURE_LEN])]) -> bool {
+    fn verify_all(items: &[(PublicKey, Vec<u8>, [u8; SIGNATURE_LEN])]) -> bool {
+        items
+            .iter()
+            .all(|(key, message, signature)| key.verify_strict(message, signature))
+    }
+
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    if threads == 1 || items.len() < PARALLEL_VERIFY_THRESHOLD {
+        return verify_all(items);
+    }
+
+    let chunk_size = (items.len() + threads - 1) / threads;
+    thread::scope(|scope| {
+        let workers: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(|| verify_all(chunk)))
+            .collect();
+        let mut all_valid = true;
+        for worker in workers {
+            all_valid &= worker.join().unwrap_or(false);
+        }
+        all_valid
+    })
+}
+
+impl fmt::Debug for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PublicKey({})", hex::encode(self.to_bytes()))
+    }
+}
+
+impl Serialize for PublicKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&hex::encode(self.to_bytes()))
+        } else {
+            serializer.serialize_bytes(&self.to_bytes())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> de::Visitor<'de> for BytesVisitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a compressed SEC1 public key")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(v.to_vec())
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut bytes = Vec::with_capacity(PUBLIC_KEY_LEN);
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(bytes)
+            }
+        }
+
+        let bytes = if deserializer.is_human_readable() {
+            let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+            hex::decode(s.as_ref()).map_err(de::Error::custom)?
+        } else {
+            deserializer.deserialize_bytes(BytesVisitor)?
+        };
+        Self::from_bytes(&bytes).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_bytes_round_trip() {
+        let key = PrivateKey::generate();
+        let restored = PrivateKey::from_bytes(&key.to_bytes()).unwrap();
+        assert_eq!(restored.public_key(), key.public_key());
+
+        let public = key.public_key();
+        assert_eq!(PublicKey::from_bytes(&public.to_bytes()).unwrap(), public);
+        assert!(PublicKey::from_bytes(&[2u8; 32]).is_err());
+        assert!(PrivateKey::from_bytes(&[0u8; PRIVATE_KEY_LEN]).is_err());
+    }
+
+    #[test]
+    fn compressed_and_uncompressed_encodings_agree() {
+        let public = PrivateKey::generate().public_key();
+        let compressed = public.to_bytes();
+        let uncompressed = public.to_uncompressed_bytes();
+        assert_eq!(uncompressed[0], 0x04);
+        assert_eq!(uncompressed[1..33], compressed[1..]);
+
+        let from_compressed = PublicKey::from_sec1(&compressed).unwrap();
+        let from_uncompressed = PublicKey::from_sec1(&uncompressed).unwrap();
+        assert_eq!(from_compressed, from_uncompressed);
+        assert_eq!(from_uncompressed.to_address(), public.to_address());
+        assert_eq!(from_uncompressed.to_bytes(), compressed);
+
+        // The compressed-only parser keeps refusing the long form.
+        assert!(PublicKey::from_bytes(&uncompressed).is_err());
+        assert!(PublicKey::from_sec1(&uncompressed[..64]).is_err());
+        let mut off_curve = uncompressed;
+        off_curve[64] ^= 0x01;
+        assert!(PublicKey::from_sec1(&off_curve).is_err());
+    }
+
+    #[test]
+    fn private_key_is_wiped_on_drop() {
+        fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+        assert_zeroize_on_drop::<PrivateKey>();
+
+        let key = PrivateKey::generate();
+        let bytes = key.to_bytes();
+        assert_ne!(*bytes, [0u8; PRIVATE_KEY_LEN]);
+        drop(bytes);
+        drop(key);
+    }
+
+    #[test]
+    fn public_key_serde_round_trip() {
+        let public = PrivateKey::generate().public_key();
+
+        let json = serde_json::to_string(&public).unwrap();
+        assert_eq!(json, format!("\"{}\"", hex::encode(public.to_bytes())));
+        assert_eq!(serde_json::from_str::<PublicKey>(&json).unwrap(), public);
+
+        let bytes = bincode::serialize(&public).unwrap();
+        assert_eq!(bincode::deserialize::<PublicKey>(&bytes).unwrap(), public);
+    }
+
+    #[test]
+    fn randomized_signature_verifies() {
+        let key = PrivateKey::generate();
+        let signature = key.sign(b"message");
+        assert!(key.public_key().verify(b"message", &signature));
+        assert!(!key.public_key().verify(b"other", &signature));
+    }
+
+    #[test]
+    fn only_low_s_signatures_are_strictly_valid() {
+        let key = PrivateKey::generate();
+        let public = key.public_key();
+        for signature in [key.sign(b"message"), key.sign_deterministic(b"message")] {
+            let parsed = Signature::from_slice(&signature).unwrap();
+            assert!(parsed.normalize_s().is_none(), "signature is high-S");
+            assert!(public.verify_strict(b"message", &signature));
+
+            // Negating s gives the malleated twin of the same signature.
+            let (r, s) = parsed.split_scalars();
+            let high_s: [u8; SIGNATURE_LEN] =
+                Signature::from_scalars(r, -s).unwrap().to_bytes().into();
+            assert_ne!(high_s, signature);
+            assert!(public.verify(b"message", &high_s));
+            assert!(!public.verify_strict(b"message", &high_s));
+            assert!(!verify_batch(&[(public, b"message".to_vec(), high_s)]));
+        }
+    }
+
+    #[test]
+    fn recover_public_key_from_signature() {
+        let key = PrivateKey::generate();
+        let signature = key.sign_recoverable(b"message");
+        assert_eq!(
+            PublicKey::recover(b"message", &signature).unwrap(),
+            key.public_key()
+        );
+
+        // A different message recovers some other key (or fails), never ours.
+        let other = PublicKey::recover(b"other", &signature);
+        assert!(other.map_or(true, |k| k != key.public_key()));
+
+        let mut bad_id = signature;
+        bad_id[SIGNATURE_LEN] = 9;
+        assert!(PublicKey::recover(b"message", &bad_id).is_err());
+    }
+
+    #[test]
+    fn batch_verification_rejects_any_bad_signature() {
+        let mut items: Vec<_> = (0..40u8)
+            .map(|i| {
+                let key = PrivateKey::generate();
+                let message = vec![i; 8];
+                let signature = key.sign(&message);
+                (key.public_key(), message, signature)
+            })
+            .collect();
+        assert!(verify_batch(&[]));
+        assert!(verify_batch(&items[..3]));
+        assert!(verify_batch(&items));
+
+        items[27].2[10] ^= 0x01;
+        assert!(!verify_batch(&items));
+        assert!(!verify_batch(&items[25..28]));
+    }
+
+    #[test]
+    fn deterministic_signature_is_stable() {
+        let key = PrivateKey::from_bytes(&[7u8; PRIVATE_KEY_LEN]).unwrap();
+        let first = key.sign_deterministic(b"message");
+        let second = key.sign_deterministic(b"message");
+        assert_eq!(first, second);
+        assert!(key.public_key().verify(b"message", &first));
+        assert_ne!(key.sign_deterministic(b"other"), first);
+    }
+}
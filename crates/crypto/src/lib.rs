@@ -3,10 +3,184 @@
 //! This crate provides cryptographic functionality including hashing, signatures,
 //! and address encoding for the `HorizCoin` blockchain.
 
+mod address;
+pub mod hd;
+mod keys;
+mod network;
+#[cfg(feature = "schnorr")]
+mod schnorr;
+mod wif;
+
+pub use address::{
+    encode_address, find_vanity_address, is_valid_address, parse_address, ADDRESS_PROGRAM_LEN,
+    ADDRESS_VERSION,
+};
+pub use keys::{
+    verify_batch, PrivateKey, PublicKey, PRIVATE_KEY_LEN, PUBLIC_KEY_LEN,
+    RECOVERABLE_SIGNATURE_LEN, SIGNATURE_LEN, UNCOMPRESSED_PUBLIC_KEY_LEN,
+};
+pub use network::Network;
+pub use wif::WIF_VERSION;
+
+use hmac::{Hmac, Mac};
+use horizcoin_primitives::Hash;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+/// Computes the `SHA-256` digest of `data`.
+#[must_use]
+pub fn sha256(data: &[u8]) -> Hash {
+    Hash::new(Sha256::digest(data).into())
+}
+
+/// Incremental `SHA-256`, for input too large or too scattered to hash as a
+/// single slice.
+///
+/// Feeding the same bytes in any chunking yields the same digest as
+/// [`sha256`].
+#[derive(Debug, Clone, Default)]
+pub struct Sha256Hasher(Sha256);
+
+impl Sha256Hasher {
+    /// Creates a hasher with no input.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `data` to the input.
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    /// Consumes the hasher and returns the digest of everything fed so far.
+    #[must_use]
+    pub fn finalize(self) -> Hash {
+        Hash::new(self.0.finalize().into())
+    }
+}
+
+/// Computes `SHA-256(SHA-256(data))`, as used for checksums.
+#[must_use]
+pub fn double_sha256(data: &[u8]) -> Hash {
+    Hash::new(Sha256::digest(Sha256::digest(data)).into())
+}
+
+/// Computes `RIPEMD-160(SHA-256(data))`, the 20-byte public key hash used
+/// in addresses.
+#[must_use]
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(Sha256::digest(data)).into()
+}
+
+/// Computes the `HMAC-SHA-256` tag of `data` under `key`.
+#[must_use]
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// Returns `true` if `tag` is the [`hmac_sha256`] tag of `data` under `key`.
+///
+/// The comparison takes the same time wherever the first mismatch is, so
+/// it does not leak how much of a forged tag was right.
+#[must_use]
+pub fn hmac_sha256_verify(key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.verify_slice(tag).is_ok()
+}
+
+/// Computes the `BLAKE3` digest of `data`.
+///
+/// Only available with the `blake3` feature; `SHA-256` remains the default
+/// hash for consensus data.
+#[cfg(feature = "blake3")]
+#[must_use]
+pub fn blake3(data: &[u8]) -> Hash {
+    Hash::new(*::blake3::hash(data).as_bytes())
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_known_vector() {
+        assert_eq!(
+            sha256(b"hello world").to_hex(),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+    }
+
+    #[test]
+    fn double_sha256_known_vector() {
+        assert_eq!(
+            double_sha256(b"hello world").to_hex(),
+            "bc62d4b80d9e36da29c16c5d4d9f11731f36052c72401a76c23c0fb5a9b74423"
+        );
+    }
+
+    #[test]
+    fn incremental_hash_matches_one_shot() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(10_000).collect();
+
+        let mut whole = Sha256Hasher::new();
+        whole.update(&data);
+
+        let mut chunked = Sha256Hasher::new();
+        for chunk in data.chunks(7) {
+            chunked.update(chunk);
+        }
+
+        let expected = sha256(&data);
+        assert_eq!(whole.finalize(), expected);
+        assert_eq!(chunked.finalize(), expected);
+        assert_eq!(Sha256Hasher::new().finalize(), sha256(&[]));
+    }
+
+    #[test]
+    fn hmac_sha256_rfc4231_vectors() {
+        assert_eq!(
+            hex::encode(hmac_sha256(&[0x0b; 20], b"Hi There")),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        let tag = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            hex::encode(tag),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert!(hmac_sha256_verify(
+            b"Jefe",
+            b"what do ya want for nothing?",
+            &tag
+        ));
+    }
+
+    #[test]
+    fn hmac_sha256_rejects_tampering() {
+        let key = b"shared secret";
+        let mut data = b"block announcement".to_vec();
+        let tag = hmac_sha256(key, &data);
+        assert!(hmac_sha256_verify(key, &data, &tag));
+
+        assert!(!hmac_sha256_verify(b"other secret", &data, &tag));
+        assert!(!hmac_sha256_verify(key, &data, &tag[..31]));
+        let mut bad_tag = tag;
+        bad_tag[31] ^= 0x80;
+        assert!(!hmac_sha256_verify(key, &data, &bad_tag));
+
+        data[0] ^= 0x01;
+        assert!(!hmac_sha256_verify(key, &data, &tag));
+    }
+
+    #[cfg(feature = "blake3")]
     #[test]
-    fn placeholder_test() {
-        // Placeholder test to ensure the crate compiles
+    fn blake3_known_vector() {
+        assert_eq!(
+            blake3(b"hello world").to_hex(),
+            "d74981efa70a0c880b8d8c1985d075dbcbf679b99a5f9914e5aaf96b831a9e24"
+        );
     }
 }
@@ -0,0 +1,51 @@
+//! Networks with separate address namespaces.
+
+use std::fmt;
+
+/// A `HorizCoin` network.
+///
+/// Each network uses its own address prefix so funds cannot be sent to an
+/// address meant for another network by mistake.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Network {
+    /// The production network.
+    #[default]
+    Mainnet,
+    /// The public test network.
+    Testnet,
+    /// A local regression-test network.
+    Regtest,
+}
+
+impl Network {
+    /// All networks, in declaration order.
+    pub const ALL: [Self; 3] = [Self::Mainnet, Self::Testnet, Self::Regtest];
+
+    /// Returns the bech32m human-readable prefix for addresses on this network.
+    #[must_use]
+    pub const fn hrp(self) -> &'static str {
+        match self {
+            Self::Mainnet => "hz",
+            Self::Testnet => "thz",
+            Self::Regtest => "rhz",
+        }
+    }
+
+    /// Returns the network whose prefix is `hrp`, compared case-insensitively.
+    #[must_use]
+    pub fn from_hrp(hrp: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|network| network.hrp().eq_ignore_ascii_case(hrp))
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Mainnet => "mainnet",
+            Self::Testnet => "testnet",
+            Self::Regtest => "regtest",
+        })
+    }
+}
@@ -0,0 +1,64 @@
+//! `BIP340` Schnorr signatures over `secp256k1`.
+//!
+//! Keys are the same as for `ECDSA`; the public key is used in its x-only
+//! form. As in the `ECDSA` path, messages are hashed with `SHA-256` before
+//! signing.
+
+use k256::schnorr::{
+    signature::{RandomizedSigner, Verifier},
+    Signature, SigningKey, VerifyingKey,
+};
+use rand_core::OsRng;
+
+use crate::{PrivateKey, PublicKey, SIGNATURE_LEN};
+
+impl PrivateKey {
+    /// Signs `message` with a `BIP340` Schnorr signature using fresh
+    /// auxiliary randomness.
+    #[must_use]
+    pub fn sign_schnorr(&self, message: &[u8]) -> [u8; SIGNATURE_LEN] {
+        let key = SigningKey::from_bytes(self.to_bytes().as_slice())
+            .expect("a valid ECDSA key is a valid Schnorr key");
+        let signature: Signature = key.sign_with_rng(&mut OsRng, message);
+        signature.to_bytes()
+    }
+}
+
+impl PublicKey {
+    /// Returns `true` if `signature` is a valid `BIP340` Schnorr signature
+    /// for `message` under the x-only form of this key.
+    #[must_use]
+    pub fn verify_schnorr(&self, message: &[u8], signature: &[u8; SIGNATURE_LEN]) -> bool {
+        let Ok(key) = VerifyingKey::from_bytes(&self.to_bytes()[1..]) else {
+            return false;
+        };
+        Signature::try_from(signature.as_slice()).is_ok_and(|sig| key.verify(message, &sig).is_ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schnorr_round_trip() {
+        for _ in 0..8 {
+            // Covers keys whose public point has either y parity.
+            let key = PrivateKey::generate();
+            let signature = key.sign_schnorr(b"message");
+            assert!(key.public_key().verify_schnorr(b"message", &signature));
+            assert!(!key.public_key().verify_schnorr(b"other", &signature));
+        }
+    }
+
+    #[test]
+    fn schnorr_rejects_flipped_byte() {
+        let key = PrivateKey::generate();
+        let mut signature = key.sign_schnorr(b"message");
+        signature[40] ^= 0x01;
+        assert!(!key.public_key().verify_schnorr(b"message", &signature));
+        assert!(!PrivateKey::generate()
+            .public_key()
+            .verify_schnorr(b"message", &key.sign_schnorr(b"message")));
+    }
+}
@@ -0,0 +1,115 @@
+//! Wallet Import Format (`WIF`) encoding of private keys.
+//!
+//! The payload is `version || key || 0x01` followed by the first four bytes
+//! of its double `SHA-256`, encoded in base58. The trailing `0x01` marks the
+//! key as using compressed public keys, which is the only form this crate
+//! produces.
+
+use horizcoin_primitives::HorizError;
+use zeroize::Zeroizing;
+
+use crate::{double_sha256, PrivateKey, PRIVATE_KEY_LEN};
+
+/// Version byte prefixed to `WIF` payloads.
+pub const WIF_VERSION: u8 = 0x80;
+
+/// Suffix marking a key whose public key is serialized compressed.
+const COMPRESSED_FLAG: u8 = 0x01;
+
+const CHECKSUM_LEN: usize = 4;
+const PAYLOAD_LEN: usize = 1 + PRIVATE_KEY_LEN + 1;
+
+impl PrivateKey {
+    /// Encodes the key as a base58check `WIF` string.
+    #[must_use]
+    pub fn to_wif(&self) -> String {
+        let mut data = Zeroizing::new(Vec::with_capacity(PAYLOAD_LEN + CHECKSUM_LEN));
+        data.push(WIF_VERSION);
+        data.extend_from_slice(self.to_bytes().as_slice());
+        data.push(COMPRESSED_FLAG);
+        let checksum = double_sha256(&data);
+        data.extend_from_slice(&checksum.as_bytes()[..CHECKSUM_LEN]);
+        bs58::encode(data.as_slice()).into_string()
+    }
+
+    /// Decodes a `WIF` string produced by [`PrivateKey::to_wif`].
+    pub fn from_wif(s: &str) -> Result<Self, HorizError> {
+        let data = Zeroizing::new(
+            bs58::decode(s)
+                .into_vec()
+                .map_err(|e| HorizError::Crypto(format!("invalid WIF encoding: {e}")))?,
+        );
+        if data.len() != PAYLOAD_LEN + CHECKSUM_LEN {
+            return Err(HorizError::Crypto(format!(
+                "WIF payload must be {} bytes, got {}",
+                PAYLOAD_LEN + CHECKSUM_LEN,
+                data.len()
+            )));
+        }
+
+        let (payload, checksum) = data.split_at(PAYLOAD_LEN);
+        if double_sha256(payload).as_bytes()[..CHECKSUM_LEN] != *checksum {
+            return Err(HorizError::Crypto("WIF checksum mismatch".into()));
+        }
+        if payload[0] != WIF_VERSION {
+            return Err(HorizError::Crypto(format!(
+                "unexpected WIF version byte {:#04x}",
+                payload[0]
+            )));
+        }
+        if payload[PAYLOAD_LEN - 1] != COMPRESSED_FLAG {
+            return Err(HorizError::Crypto(
+                "only compressed WIF keys are supported".into(),
+            ));
+        }
+
+        let mut key = Zeroizing::new([0u8; PRIVATE_KEY_LEN]);
+        key.copy_from_slice(&payload[1..=PRIVATE_KEY_LEN]);
+        Self::from_bytes(&key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let key = PrivateKey::generate();
+        let restored = PrivateKey::from_wif(&key.to_wif()).unwrap();
+        assert_eq!(restored.to_bytes(), key.to_bytes());
+    }
+
+    #[test]
+    fn known_vector() {
+        let mut bytes = [0u8; PRIVATE_KEY_LEN];
+        hex::decode_to_slice(
+            "0c28fca386c7a227600b2fe50b7cae11ec86d3bf1fbe471be89827e19d72aa1d",
+            &mut bytes,
+        )
+        .unwrap();
+        let key = PrivateKey::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            key.to_wif(),
+            "KwdMAjGmerYanjeui5SHS7JkmpZvVipYvB2LJGU1ZxJwYvP98617"
+        );
+    }
+
+    #[test]
+    fn corrupted_checksum_is_rejected() {
+        let wif = PrivateKey::generate().to_wif();
+        let mut data = bs58::decode(&wif).into_vec().unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        let corrupted = bs58::encode(data).into_string();
+
+        assert!(matches!(
+            PrivateKey::from_wif(&corrupted),
+            Err(HorizError::Crypto(_))
+        ));
+        assert!(matches!(
+            PrivateKey::from_wif("not base58 0OIl"),
+            Err(HorizError::Crypto(_))
+        ));
+    }
+}
@@ -0,0 +1,148 @@
+//! Fee-rate suggestions drawn from recently confirmed transactions.
+
+use std::collections::VecDeque;
+
+use horizcoin_primitives::Amount;
+
+/// Default number of confirmed fee rates a [`FeeEstimator`] remembers.
+pub const DEFAULT_MAX_FEE_SAMPLES: usize = 10_000;
+
+/// Suggests fee rates from the rates of recently mined transactions.
+///
+/// Samples are kept in a ring buffer: once it is full, recording a block
+/// drops the oldest rates. Estimates depend only on the samples held, so
+/// the same history always gives the same answer.
+#[derive(Debug, Clone)]
+pub struct FeeEstimator {
+    samples: VecDeque<Amount>,
+    max_samples: usize,
+}
+
+impl Default for FeeEstimator {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_MAX_FEE_SAMPLES)
+    }
+}
+
+impl FeeEstimator {
+    /// Creates an estimator remembering [`DEFAULT_MAX_FEE_SAMPLES`] rates.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an estimator remembering at most `max_samples` rates.
+    #[must_use]
+    pub fn with_capacity(max_samples: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(max_samples),
+            max_samples,
+        }
+    }
+
+    /// Returns the number of rates currently remembered.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns `true` if no rates have been recorded.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Records the fee rates, per byte, of the transactions in a newly
+    /// confirmed block.
+    pub fn record_block(&mut self, fee_rates: &[Amount]) {
+        for &rate in fee_rates {
+            if self.samples.len() == self.max_samples {
+                self.samples.pop_front();
+            }
+            if self.max_samples > 0 {
+                self.samples.push_back(rate);
+            }
+        }
+    }
+
+    /// Suggests a fee rate, per byte, for confirmation within
+    /// `target_blocks` blocks.
+    ///
+    /// The answer is the `50 + 45 / target_blocks`th percentile of the
+    /// remembered rates: the 95th for one block, the 72nd for two, and
+    /// approaching the median for distant targets, so a tighter target
+    /// never gets a lower rate. A target of `0` is treated as `1`. Returns
+    /// [`Amount::ZERO`] with no history.
+    #[must_use]
+    pub fn estimate(&self, target_blocks: u32) -> Amount {
+        if self.samples.is_empty() {
+            return Amount::ZERO;
+        }
+        let mut sorted: Vec<Amount> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        // Nearest-rank percentile: the smallest rate at or above the given
+        // share of samples.
+        let rank = (target_percentile(target_blocks) * sorted.len() + 99) / 100;
+        sorted[rank.max(1) - 1]
+    }
+}
+
+fn target_percentile(target_blocks: u32) -> usize {
+    50 + 45 / target_blocks.max(1) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rates(values: impl IntoIterator<Item = u64>) -> Vec<Amount> {
+        values.into_iter().map(Amount::new).collect()
+    }
+
+    #[test]
+    fn tighter_targets_pay_more() {
+        let mut estimator = FeeEstimator::new();
+        assert_eq!(estimator.estimate(1), Amount::ZERO);
+
+        // Ten blocks each holding rates 1..=100.
+        for _ in 0..10 {
+            estimator.record_block(&rates(1..=100));
+        }
+        assert_eq!(estimator.estimate(1), Amount::new(95));
+        assert_eq!(estimator.estimate(0), estimator.estimate(1));
+        assert_eq!(estimator.estimate(2), Amount::new(72));
+        assert_eq!(estimator.estimate(1_000), Amount::new(50));
+
+        let estimates: Vec<Amount> = (1..=25).map(|t| estimator.estimate(t)).collect();
+        assert!(estimates.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert!(estimates[0] > estimates[24]);
+    }
+
+    #[test]
+    fn skewed_history_is_reflected() {
+        let mut estimator = FeeEstimator::new();
+        // Mostly cheap transactions with a few expensive ones.
+        estimator.record_block(&rates([1; 90].into_iter().chain([500; 10])));
+        assert_eq!(estimator.estimate(1), Amount::new(500));
+        assert_eq!(estimator.estimate(6), Amount::new(1));
+    }
+
+    #[test]
+    fn oldest_samples_are_dropped() {
+        let mut estimator = FeeEstimator::with_capacity(100);
+        estimator.record_block(&rates([1_000; 100]));
+        assert_eq!(estimator.estimate(10), Amount::new(1_000));
+
+        estimator.record_block(&rates([10; 60]));
+        assert_eq!(estimator.len(), 100);
+        assert_eq!(estimator.estimate(10), Amount::new(10));
+        assert_eq!(estimator.estimate(1), Amount::new(1_000));
+
+        estimator.record_block(&rates([10; 40]));
+        assert_eq!(estimator.estimate(1), Amount::new(10));
+
+        let mut no_capacity = FeeEstimator::with_capacity(0);
+        no_capacity.record_block(&rates([5]));
+        assert!(no_capacity.is_empty());
+    }
+}
@@ -3,10 +3,824 @@
 //! This crate provides transaction pool with admission rules and propagation
 //! for the `HorizCoin` blockchain.
 
+mod estimator;
+mod snapshot;
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use horizcoin_primitives::{Amount, HorizError, TxId};
+use horizcoin_tx::Transaction;
+
+pub use estimator::{FeeEstimator, DEFAULT_MAX_FEE_SAMPLES};
+pub use snapshot::MempoolSnapshot;
+
+/// Default cap on the summed encoded size of pooled transactions.
+pub const DEFAULT_MAX_SIZE_BYTES: usize = 300 * 1024 * 1024;
+
+/// Default cap on how many pooled transactions a package may hold: a
+/// transaction together with its unconfirmed ancestors.
+pub const DEFAULT_MAX_ANCESTORS: usize = 25;
+
+/// Default cap on the summed encoded size of a package.
+pub const DEFAULT_MAX_ANCESTOR_BYTES: usize = 101_000;
+
+/// A pooled transaction with the fee it pays and its encoded size.
+#[derive(Debug, Clone)]
+struct Entry {
+    tx: Transaction,
+    fee: Amount,
+    size: usize,
+}
+
+impl Entry {
+    const fn cost(&self) -> PackageCost {
+        PackageCost {
+            fee: self.fee,
+            size: self.size,
+        }
+    }
+}
+
+/// Summed fee and size of one or more transactions, typically one together
+/// with the unconfirmed ancestors that must be mined with it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct PackageCost {
+    fee: Amount,
+    size: usize,
+}
+
+impl PackageCost {
+    /// Orders by fee per byte, comparing exactly rather than on the
+    /// rounded-down rate.
+    fn cmp_fee_rate(self, other: Self) -> Ordering {
+        let lhs = u128::from(self.fee.as_u64()) * other.size as u128;
+        let rhs = u128::from(other.fee.as_u64()) * self.size as u128;
+        lhs.cmp(&rhs)
+    }
+
+    /// Returns the cost with `part`, a member of the package, taken out.
+    fn without(self, part: Self) -> Self {
+        Self {
+            fee: self.fee.checked_sub(part.fee).unwrap_or(Amount::ZERO),
+            size: self.size.saturating_sub(part.size),
+        }
+    }
+
+    /// Returns the fee per byte, rounded down, or `0` for an empty package.
+    fn fee_rate(self) -> Amount {
+        match u64::try_from(self.size) {
+            Ok(size) if size > 0 => Amount::new(self.fee.as_u64() / size),
+            _ => Amount::ZERO,
+        }
+    }
+}
+
+/// A transaction queued for block selection with the cost of its
+/// not-yet-selected package; the best rate is greatest, ties going to the
+/// smaller txid.
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    cost: PackageCost,
+    txid: TxId,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cost
+            .cmp_fee_rate(other.cost)
+            .then_with(|| other.txid.cmp(&self.txid))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Candidate {}
+
+/// Unconfirmed transactions waiting to be mined.
+///
+/// Each outpoint may be spent by at most one pooled transaction, so the
+/// pool never holds a double spend. Its summed size stays within a cap;
+/// see [`add`](Self::add) for how room is made. Every transaction's
+/// package, itself plus its pooled ancestors, stays within a count and a
+/// size limit, which bounds the work of evaluating packages.
+#[derive(Debug, Clone)]
+pub struct Mempool {
+    entries: HashMap<TxId, Entry>,
+    spenders: HashMap<(TxId, u32), TxId>,
+    /// Pooled transactions spending each txid's outputs, whether or not
+    /// that txid is itself pooled.
+    children: HashMap<TxId, HashSet<TxId>>,
+    total_bytes: usize,
+    max_size_bytes: usize,
+    max_ancestors: usize,
+    max_ancestor_bytes: usize,
+}
+
+impl Default for Mempool {
+    fn default() -> Self {
+        Self::with_max_size(DEFAULT_MAX_SIZE_BYTES)
+    }
+}
+
+impl Mempool {
+    /// Creates an empty pool capped at [`DEFAULT_MAX_SIZE_BYTES`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty pool whose transactions may total at most
+    /// `max_size_bytes` encoded bytes.
+    #[must_use]
+    pub fn with_max_size(max_size_bytes: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            spenders: HashMap::new(),
+            children: HashMap::new(),
+            total_bytes: 0,
+            max_size_bytes,
+            max_ancestors: DEFAULT_MAX_ANCESTORS,
+            max_ancestor_bytes: DEFAULT_MAX_ANCESTOR_BYTES,
+        }
+    }
+
+    /// Limits every package to `max_ancestors` transactions and
+    /// `max_ancestor_bytes` encoded bytes, the transaction itself included.
+    #[must_use]
+    pub const fn with_ancestor_limits(
+        mut self,
+        max_ancestors: usize,
+        max_ancestor_bytes: usize,
+    ) -> Self {
+        self.max_ancestors = max_ancestors;
+        self.max_ancestor_bytes = max_ancestor_bytes;
+        self
+    }
+
+    /// Returns the cap on the number of transactions in a package.
+    #[must_use]
+    pub const fn max_ancestors(&self) -> usize {
+        self.max_ancestors
+    }
+
+    /// Returns the cap on the encoded size of a package.
+    #[must_use]
+    pub const fn max_ancestor_bytes(&self) -> usize {
+        self.max_ancestor_bytes
+    }
+
+    /// Returns the cap on [`total_bytes`](Self::total_bytes).
+    #[must_use]
+    pub const fn max_size_bytes(&self) -> usize {
+        self.max_size_bytes
+    }
+
+    /// Returns the number of pooled transactions.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the pool holds no transactions.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the summed encoded size of all pooled transactions.
+    #[must_use]
+    pub const fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    /// Returns `true` if `txid` is pooled.
+    #[must_use]
+    pub fn contains(&self, txid: &TxId) -> bool {
+        self.entries.contains_key(txid)
+    }
+
+    /// Returns the pooled transaction `txid`, if any.
+    #[must_use]
+    pub fn get(&self, txid: &TxId) -> Option<&Transaction> {
+        self.entries.get(txid).map(|entry| &entry.tx)
+    }
+
+    /// Returns the eviction floor: the fee per byte, rounded down, of the
+    /// cheapest package eviction would take, a pooled transaction with all
+    /// its descendants, or `0` if the pool is empty.
+    ///
+    /// Once the pool is full, a newcomer must pay more than this to get in.
+    #[must_use]
+    pub fn min_fee_rate(&self) -> Amount {
+        self.entries
+            .keys()
+            .map(|txid| self.cost(&self.descendants(txid)))
+            .min_by(|a, b| a.cmp_fee_rate(*b))
+            .map_or(Amount::ZERO, PackageCost::fee_rate)
+    }
+
+    /// Adds `tx`, which pays `fee`.
+    ///
+    /// Rejects a coinbase, a transaction already pooled, one spending an
+    /// output that a pooled transaction already spends, and one that would
+    /// push its own package, or that of a pooled descendant, past the
+    /// ancestor limits. If `tx` does not fit
+    /// under the size cap, transactions are evicted together with their
+    /// descendants, cheapest such package by fee per byte first, to make
+    /// room; `tx` is rejected instead if that would evict a package paying
+    /// at least its rate, so a cheap parent is protected by rich children.
+    pub fn add(&mut self, tx: Transaction, fee: Amount) -> Result<(), HorizError> {
+        if tx.is_coinbase() {
+            return Err(invalid("coinbase transactions cannot be pooled"));
+        }
+        let txid = tx.txid()?;
+        if self.entries.contains_key(&txid) {
+            return Err(invalid(format!("{txid} is already pooled")));
+        }
+        for input in &tx.inputs {
+            if let Some(spender) = self.spenders.get(&(input.prev_tx, input.output_index)) {
+                return Err(invalid(format!(
+                    "{}:{} is already spent by pooled {spender}",
+                    input.prev_tx, input.output_index
+                )));
+            }
+        }
+
+        let size = tx.serialized_size()?;
+        self.check_ancestor_limits(txid, &tx, size)?;
+        let evicted = self.plan_eviction(&tx, PackageCost { fee, size })?;
+        for txid in &evicted {
+            self.remove(txid);
+        }
+        for input in &tx.inputs {
+            self.spenders
+                .insert((input.prev_tx, input.output_index), txid);
+            self.children.entry(input.prev_tx).or_default().insert(txid);
+        }
+        self.total_bytes += size;
+        self.entries.insert(txid, Entry { tx, fee, size });
+        Ok(())
+    }
+
+    /// Removes `txid`, freeing the outputs it spent. Does nothing if it is
+    /// not pooled.
+    pub fn remove(&mut self, txid: &TxId) {
+        let Some(entry) = self.entries.remove(txid) else {
+            return;
+        };
+        for input in &entry.tx.inputs {
+            self.spenders.remove(&(input.prev_tx, input.output_index));
+            if let Some(children) = self.children.get_mut(&input.prev_tx) {
+                children.remove(txid);
+                if children.is_empty() {
+                    self.children.remove(&input.prev_tx);
+                }
+            }
+        }
+        self.total_bytes -= entry.size;
+    }
+
+    /// Returns the pooled transactions `txid` spends from, directly or
+    /// through other pooled transactions, parents before children.
+    #[must_use]
+    pub fn ancestors(&self, txid: &TxId) -> Vec<TxId> {
+        let mut package = self.package(txid, &HashSet::new());
+        package.pop();
+        package
+    }
+
+    /// Returns the fee per byte of `txid` together with its pooled
+    /// ancestors, rounded down, or `0` if `txid` is not pooled.
+    ///
+    /// A parent cannot be mined without its child, so this is the rate a
+    /// miner earns for including the child.
+    #[must_use]
+    pub fn package_fee_rate(&self, txid: &TxId) -> Amount {
+        self.cost(&self.package(txid, &HashSet::new())).fee_rate()
+    }
+
+    /// Returns transactions whose sizes sum to at most `max_bytes`, in an
+    /// order a block can include them.
+    ///
+    /// Selection is greedy by [`package_fee_rate`](Self::package_fee_rate)
+    /// over what is not yet selected, so a high-fee child pulls its
+    /// low-fee parents in ahead of it. A package too large for the space
+    /// left is skipped, and so is every later package that includes a
+    /// skipped transaction; smaller, cheaper ones may still fill the space.
+    #[must_use]
+    pub fn select_for_block(&self, max_bytes: usize) -> Vec<Transaction> {
+        self.select(max_bytes)
+            .iter()
+            .map(|txid| self.entries[txid].tx.clone())
+            .collect()
+    }
+
+    /// Returns the txids [`select_for_block`](Self::select_for_block)
+    /// picks, in order.
+    fn select(&self, max_bytes: usize) -> Vec<TxId> {
+        let none = HashSet::new();
+        let mut costs = HashMap::with_capacity(self.entries.len());
+        let mut queue = BinaryHeap::with_capacity(self.entries.len());
+        for txid in self.entries.keys() {
+            let cost = self.cost(&self.package(txid, &none));
+            costs.insert(*txid, cost);
+            queue.push(Candidate { cost, txid: *txid });
+        }
+
+        let mut selected = HashSet::new();
+        let mut skipped = HashSet::new();
+        let mut remaining = max_bytes;
+        let mut block = Vec::new();
+        while let Some(Candidate { cost, txid }) = queue.pop() {
+            // Queue entries go stale as ancestors get selected; the current
+            // cost was pushed separately.
+            if selected.contains(&txid) || skipped.contains(&txid) || costs[&txid] != cost {
+                continue;
+            }
+            let package = self.package(&txid, &selected);
+            // A skipped ancestor is never selected later, so nothing that
+            // depends on it can be either.
+            if cost.size > remaining || package.iter().any(|member| skipped.contains(member)) {
+                skipped.insert(txid);
+                continue;
+            }
+            remaining -= cost.size;
+            for member in &package {
+                selected.insert(*member);
+                block.push(*member);
+            }
+            // Every pooled descendant of a selected transaction now has a
+            // smaller package left to pay for.
+            for member in &package {
+                let member_cost = self.entries[member].cost();
+                for descendant in self.descendants(member).into_iter().skip(1) {
+                    if selected.contains(&descendant) {
+                        continue;
+                    }
+                    let cost = costs.get_mut(&descendant).expect("descendants are pooled");
+                    *cost = cost.without(member_cost);
+                    queue.push(Candidate {
+                        cost: *cost,
+                        txid: descendant,
+                    });
+                }
+            }
+        }
+        block
+    }
+
+    /// Checks that pooling `tx` keeps every package within the ancestor
+    /// limits: its own, and those of pooled transactions that spend it.
+    fn check_ancestor_limits(
+        &self,
+        txid: TxId,
+        tx: &Transaction,
+        size: usize,
+    ) -> Result<(), HorizError> {
+        let none = HashSet::new();
+        // Each parent's package is already within the limits, so these
+        // walks stay short.
+        let mut ancestors = HashSet::new();
+        for input in &tx.inputs {
+            ancestors.extend(self.package(&input.prev_tx, &none));
+        }
+        self.check_package_limits(&txid, &ancestors, size)?;
+
+        // Children may have arrived first; they gain `tx` and its ancestors.
+        for descendant in self.descendants(&txid).into_iter().skip(1) {
+            let mut joined = ancestors.clone();
+            joined.extend(self.package(&descendant, &none));
+            self.check_package_limits(&descendant, &joined, size)?;
+        }
+        Ok(())
+    }
+
+    /// Checks a package made of the pooled `members` plus a newcomer of
+    /// `size` bytes.
+    fn check_package_limits(
+        &self,
+        txid: &TxId,
+        members: &HashSet<TxId>,
+        size: usize,
+    ) -> Result<(), HorizError> {
+        let count = members.len() + 1;
+        if count > self.max_ancestors {
+            return Err(invalid(format!(
+                "package of {txid} would hold {count} transactions, over the limit of {}",
+                self.max_ancestors
+            )));
+        }
+        let bytes = members
+            .iter()
+            .map(|member| self.entries[member].size)
+            .sum::<usize>()
+            + size;
+        if bytes > self.max_ancestor_bytes {
+            return Err(invalid(format!(
+                "package of {txid} would be {bytes} bytes, over the limit of {}",
+                self.max_ancestor_bytes
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns the transactions to evict so that `tx`, costing `incoming`,
+    /// fits under the cap.
+    fn plan_eviction(
+        &self,
+        tx: &Transaction,
+        incoming: PackageCost,
+    ) -> Result<Vec<TxId>, HorizError> {
+        if incoming.size > self.max_size_bytes {
+            return Err(invalid(format!(
+                "transaction of {} bytes exceeds the pool cap of {}",
+                incoming.size, self.max_size_bytes
+            )));
+        }
+        let needed = (self.total_bytes + incoming.size).saturating_sub(self.max_size_bytes);
+        if needed == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Evicting a transaction evicts its descendants too, so rank by
+        // the rate of that whole package.
+        let mut ranked: Vec<_> = self
+            .entries
+            .keys()
+            .map(|txid| (*txid, self.cost(&self.descendants(txid))))
+            .collect();
+        ranked.sort_by(|(a_id, a), (b_id, b)| a.cmp_fee_rate(*b).then_with(|| a_id.cmp(b_id)));
+        let mut evicted = HashSet::new();
+        let mut order = Vec::new();
+        let mut freed = 0;
+        for (txid, _) in ranked {
+            if freed >= needed {
+                break;
+            }
+            if evicted.contains(&txid) {
+                continue;
+            }
+            // Earlier evictions may have taken some of its descendants.
+            let package: Vec<TxId> = self
+                .descendants(&txid)
+                .into_iter()
+                .filter(|member| !evicted.contains(member))
+                .collect();
+            let cost = self.cost(&package);
+            if cost.cmp_fee_rate(incoming).is_ge() {
+                return Err(invalid(format!(
+                    "fee rate of {} is not above the {} of the cheapest package to evict",
+                    incoming.fee_rate(),
+                    cost.fee_rate()
+                )));
+            }
+            freed += cost.size;
+            evicted.extend(package.iter().copied());
+            order.extend(package);
+        }
+        if tx
+            .inputs
+            .iter()
+            .any(|input| evicted.contains(&input.prev_tx))
+        {
+            return Err(invalid("making room would evict an ancestor"));
+        }
+        Ok(order)
+    }
+
+    /// Returns `txid` and every pooled transaction spending from it,
+    /// directly or indirectly, `txid` first.
+    fn descendants(&self, txid: &TxId) -> Vec<TxId> {
+        let mut found = vec![*txid];
+        let mut seen = HashSet::from([*txid]);
+        let mut next = 0;
+        while let Some(parent) = found.get(next).copied() {
+            next += 1;
+            for child in self.children.get(&parent).into_iter().flatten() {
+                if seen.insert(*child) {
+                    found.push(*child);
+                }
+            }
+        }
+        found
+    }
+
+    /// Returns `txid` and its pooled ancestors outside `exclude`, parents
+    /// before children and `txid` last.
+    fn package(&self, txid: &TxId, exclude: &HashSet<TxId>) -> Vec<TxId> {
+        let mut out = Vec::new();
+        let mut emitted = HashSet::new();
+        let mut expanded = HashSet::new();
+        // A depth-first walk kept on the heap: each transaction is pushed
+        // once to visit its parents and once more to be emitted after them.
+        let mut stack = vec![(*txid, false)];
+        while let Some((current, parents_done)) = stack.pop() {
+            if parents_done {
+                if emitted.insert(current) {
+                    out.push(current);
+                }
+                continue;
+            }
+            if exclude.contains(&current) || !expanded.insert(current) {
+                continue;
+            }
+            let Some(entry) = self.entries.get(&current) else {
+                continue;
+            };
+            stack.push((current, true));
+            for input in entry.tx.inputs.iter().rev() {
+                stack.push((input.prev_tx, false));
+            }
+        }
+        out
+    }
+
+    fn cost(&self, package: &[TxId]) -> PackageCost {
+        package.iter().fold(PackageCost::default(), |cost, txid| {
+            let entry = &self.entries[txid];
+            PackageCost {
+                fee: cost.fee.saturating_add(entry.fee),
+                size: cost.size + entry.size,
+            }
+        })
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> HorizError {
+    HorizError::InvalidTransaction(reason.into())
+}
+
 #[cfg(test)]
 mod tests {
+    use horizcoin_crypto::PrivateKey;
+    use horizcoin_tx::{TxInput, TxOutput};
+
+    use super::*;
+
+    fn spend(byte: u8, index: u32) -> Transaction {
+        Transaction::new(
+            vec![TxInput::new(
+                TxId::new([byte; 32]),
+                index,
+                PrivateKey::generate().public_key(),
+            )],
+            vec![TxOutput::new(Amount::new(1_000), "hz1payee")],
+            None,
+        )
+    }
+
+    fn child_of(parent: &Transaction) -> Transaction {
+        Transaction::new(
+            vec![TxInput::new(
+                parent.txid().unwrap(),
+                0,
+                PrivateKey::generate().public_key(),
+            )],
+            vec![TxOutput::new(Amount::new(900), "hz1payee")],
+            None,
+        )
+    }
+
+    fn size(tx: &Transaction) -> usize {
+        tx.serialized_size().unwrap()
+    }
+
+    #[test]
+    fn selects_by_fee_rate() {
+        let mut pool = Mempool::new();
+        let cheap = spend(1, 0);
+        let rich = spend(2, 0);
+        let middling = spend(3, 0);
+        pool.add(cheap.clone(), Amount::new(100)).unwrap();
+        pool.add(rich.clone(), Amount::new(10_000)).unwrap();
+        pool.add(middling.clone(), Amount::new(1_000)).unwrap();
+
+        assert_eq!(pool.len(), 3);
+        assert_eq!(pool.select_for_block(usize::MAX), [rich, middling, cheap]);
+    }
+
     #[test]
-    fn placeholder_test() {
-        // Placeholder test to ensure the crate compiles
+    fn selection_respects_size_limit() {
+        let mut pool = Mempool::new();
+        let first = spend(1, 0);
+        let second = spend(2, 0);
+        let third = spend(3, 0);
+        let budget = size(&first) + size(&second) + size(&third) - 1;
+        pool.add(first.clone(), Amount::new(3_000)).unwrap();
+        pool.add(second.clone(), Amount::new(2_000)).unwrap();
+        pool.add(third, Amount::new(1_000)).unwrap();
+
+        assert_eq!(pool.select_for_block(budget), [first, second]);
+        assert!(pool.select_for_block(0).is_empty());
+        assert_eq!(pool.total_bytes(), budget + 1);
+    }
+
+    #[test]
+    fn rejects_duplicates_and_double_spends() {
+        let mut pool = Mempool::new();
+        let tx = spend(1, 0);
+        pool.add(tx.clone(), Amount::new(500)).unwrap();
+        assert!(pool.add(tx.clone(), Amount::new(500)).is_err());
+
+        let double_spend = spend(1, 0);
+        assert!(pool.add(double_spend.clone(), Amount::new(5_000)).is_err());
+        assert!(pool.add(spend(1, 1), Amount::new(500)).is_ok());
+        assert!(pool
+            .add(
+                Transaction::coinbase(vec![TxOutput::new(Amount::new(50), "hz1miner")], None),
+                Amount::ZERO
+            )
+            .is_err());
+
+        pool.remove(&tx.txid().unwrap());
+        assert!(!pool.contains(&tx.txid().unwrap()));
+        pool.add(double_spend, Amount::new(5_000)).unwrap();
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn child_pays_for_parent() {
+        let mut pool = Mempool::new();
+        let parent = spend(1, 0);
+        let child = child_of(&parent);
+        let rival = spend(2, 0);
+        let (parent_id, child_id) = (parent.txid().unwrap(), child.txid().unwrap());
+        // The child arrives first; dependencies are found whatever the order.
+        pool.add(child.clone(), Amount::new(20_000)).unwrap();
+        pool.add(parent.clone(), Amount::new(10)).unwrap();
+        pool.add(rival.clone(), Amount::new(5_000)).unwrap();
+
+        assert_eq!(pool.ancestors(&child_id), [parent_id]);
+        assert!(pool.ancestors(&parent_id).is_empty());
+        let package_size = u64::try_from(size(&parent) + size(&child)).unwrap();
+        assert_eq!(
+            pool.package_fee_rate(&child_id),
+            Amount::new(20_010 / package_size)
+        );
+        assert!(pool.package_fee_rate(&parent_id) < pool.package_fee_rate(&rival.txid().unwrap()));
+        assert_eq!(pool.package_fee_rate(&TxId::new([9; 32])), Amount::ZERO);
+
+        let everything = pool.select_for_block(usize::MAX);
+        assert_eq!(everything, [parent.clone(), child.clone(), rival]);
+
+        // Room for the package alone: the rival loses despite beating the
+        // parent on its own.
+        let budget = size(&parent) + size(&child);
+        assert_eq!(pool.select_for_block(budget), [parent, child]);
+    }
+
+    #[test]
+    fn skipped_parent_blocks_its_descendants() {
+        let mut pool = Mempool::new();
+        let grandparent = spend(1, 0);
+        let parent = child_of(&grandparent);
+        let child = child_of(&parent);
+        pool.add(grandparent.clone(), Amount::new(10)).unwrap();
+        pool.add(parent.clone(), Amount::new(100_000)).unwrap();
+        pool.add(child.clone(), Amount::new(50)).unwrap();
+
+        // Room for any one transaction but not for the parent's package.
+        // The child must not be picked without the parent it spends.
+        let budget = size(&grandparent) + size(&parent) - 1;
+        assert_eq!(
+            pool.select_for_block(budget),
+            std::slice::from_ref(&grandparent)
+        );
+
+        let everything = pool.select_for_block(usize::MAX);
+        assert_eq!(everything, [grandparent, parent, child]);
+    }
+
+    #[test]
+    fn packages_are_bounded() {
+        let a = spend(1, 0);
+        let b = child_of(&a);
+        let c = child_of(&b);
+        let d = child_of(&c);
+
+        let mut pool = Mempool::new().with_ancestor_limits(3, usize::MAX);
+        for tx in [&a, &b, &c] {
+            pool.add(tx.clone(), Amount::new(1_000)).unwrap();
+        }
+        assert!(pool.add(d.clone(), Amount::new(1_000)).is_err());
+        pool.remove(&a.txid().unwrap());
+        pool.add(d, Amount::new(1_000)).unwrap();
+
+        let mut by_size = Mempool::new().with_ancestor_limits(10, size(&a) + size(&b));
+        by_size.add(a.clone(), Amount::new(1_000)).unwrap();
+        by_size.add(b.clone(), Amount::new(1_000)).unwrap();
+        assert!(by_size.add(c.clone(), Amount::new(1_000)).is_err());
+
+        // A parent arriving after its children lengthens their packages.
+        let mut orphans_first = Mempool::new().with_ancestor_limits(2, usize::MAX);
+        orphans_first.add(c, Amount::new(1_000)).unwrap();
+        orphans_first.add(b, Amount::new(1_000)).unwrap();
+        assert!(orphans_first.add(a, Amount::new(1_000)).is_err());
+        assert_eq!(orphans_first.len(), 2);
+    }
+
+    #[test]
+    fn long_chains_select_in_order() {
+        let mut pool = Mempool::new().with_ancestor_limits(usize::MAX, usize::MAX);
+        let mut chain = vec![spend(1, 0)];
+        for _ in 0..200 {
+            chain.push(child_of(chain.last().unwrap()));
+        }
+        // Added tip first, so every package is found through missing links.
+        for tx in chain.iter().rev() {
+            pool.add(tx.clone(), Amount::new(1_000)).unwrap();
+        }
+        assert_eq!(pool.ancestors(&chain[200].txid().unwrap()).len(), 200);
+        assert_eq!(pool.select_for_block(usize::MAX), chain);
+    }
+
+    #[test]
+    fn full_pool_evicts_the_cheapest() {
+        let cheap = spend(1, 0);
+        let middling = spend(2, 0);
+        let rich = spend(3, 0);
+        let tx_size = size(&cheap);
+        let mut pool = Mempool::with_max_size(3 * tx_size);
+        pool.add(cheap.clone(), Amount::new(1_000)).unwrap();
+        pool.add(middling, Amount::new(2_000)).unwrap();
+        pool.add(rich, Amount::new(3_000)).unwrap();
+        assert!(pool.add(child_of(&cheap), Amount::new(5_000)).is_err());
+        assert_eq!(pool.total_bytes(), pool.max_size_bytes());
+
+        let floor = pool.min_fee_rate();
+        assert_eq!(floor, Amount::new(1_000 / u64::try_from(tx_size).unwrap()));
+        let err = pool.add(spend(4, 0), Amount::new(500)).unwrap_err();
+        assert!(matches!(err, HorizError::InvalidTransaction(_)));
+        assert!(pool.add(spend(5, 0), Amount::new(1_000)).is_err());
+        assert_eq!(pool.len(), 3);
+
+        let newcomer = spend(6, 0);
+        pool.add(newcomer.clone(), Amount::new(1_500)).unwrap();
+        assert_eq!(pool.len(), 3);
+        assert!(!pool.contains(&cheap.txid().unwrap()));
+        assert!(pool.contains(&newcomer.txid().unwrap()));
+        assert!(pool.min_fee_rate() > floor);
+        assert!(pool.total_bytes() <= pool.max_size_bytes());
+    }
+
+    #[test]
+    fn eviction_protects_parents_of_rich_children() {
+        let parent = spend(1, 0);
+        let child = child_of(&parent);
+        let middling = spend(2, 0);
+        let mut pool = Mempool::with_max_size(size(&parent) + size(&child) + size(&middling));
+        pool.add(parent.clone(), Amount::new(100)).unwrap();
+        pool.add(child.clone(), Amount::new(50_000)).unwrap();
+        pool.add(middling.clone(), Amount::new(2_000)).unwrap();
+
+        // The parent alone pays less than the newcomer, but evicting it
+        // would take the child too, and that package pays far more.
+        assert!(pool.add(spend(3, 0), Amount::new(1_500)).is_err());
+        assert_eq!(pool.len(), 3);
+
+        let newcomer = spend(4, 0);
+        pool.add(newcomer.clone(), Amount::new(3_000)).unwrap();
+        assert!(!pool.contains(&middling.txid().unwrap()));
+        assert!(pool.contains(&parent.txid().unwrap()));
+        assert!(pool.contains(&child.txid().unwrap()));
+        assert!(pool.contains(&newcomer.txid().unwrap()));
+
+        pool.remove(&child.txid().unwrap());
+        assert!(!pool.children.contains_key(&parent.txid().unwrap()));
+    }
+
+    #[test]
+    fn floor_admits_newcomers_just_above_it() {
+        let parent = spend(1, 0);
+        let child = child_of(&parent);
+        let middling = spend(2, 0);
+        let mut pool = Mempool::with_max_size(size(&parent) + size(&child) + size(&middling));
+        pool.add(parent.clone(), Amount::new(100)).unwrap();
+        pool.add(child, Amount::new(50_000)).unwrap();
+        pool.add(middling.clone(), Amount::new(2_000)).unwrap();
+
+        // The cheap parent is never evicted on its own, so the floor is set
+        // by the cheapest package eviction would actually take.
+        let tx_size = u64::try_from(size(&middling)).unwrap();
+        let floor = pool.min_fee_rate();
+        assert_eq!(floor, Amount::new(2_000 / tx_size));
+
+        let newcomer = spend(3, 0);
+        let fee = Amount::new((floor.as_u64() + 1) * tx_size);
+        pool.add(newcomer.clone(), fee).unwrap();
+        assert!(pool.contains(&newcomer.txid().unwrap()));
+        assert!(!pool.contains(&middling.txid().unwrap()));
+        assert!(pool.contains(&parent.txid().unwrap()));
     }
 }
@@ -0,0 +1,170 @@
+//! Frozen views of the pool for building consistent block templates.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use horizcoin_primitives::TxId;
+use horizcoin_tx::Transaction;
+
+use crate::Mempool;
+
+/// One transaction captured by a [`MempoolSnapshot`].
+#[derive(Debug)]
+struct SnapshotEntry {
+    txid: TxId,
+    tx: Transaction,
+    size: usize,
+}
+
+/// The pool's transactions at one moment, in the fee order block selection
+/// would pick them, parents always before their children.
+///
+/// A miner polling for templates can keep building from the same snapshot
+/// and get the same result until it takes a fresh
+/// [`Mempool::snapshot`](crate::Mempool::snapshot). Clones share one
+/// allocation.
+#[derive(Debug, Clone)]
+pub struct MempoolSnapshot {
+    entries: Arc<[SnapshotEntry]>,
+}
+
+impl Mempool {
+    /// Captures every pooled transaction in the order
+    /// [`select_for_block`](Self::select_for_block) would pick them.
+    ///
+    /// The snapshot shares its contents behind an `Arc`, so it is cheap to
+    /// clone and hand out, and later changes to the pool do not affect it.
+    #[must_use]
+    pub fn snapshot(&self) -> MempoolSnapshot {
+        let entries = self
+            .select(usize::MAX)
+            .into_iter()
+            .map(|txid| {
+                let entry = &self.entries[&txid];
+                SnapshotEntry {
+                    txid,
+                    tx: entry.tx.clone(),
+                    size: entry.size,
+                }
+            })
+            .collect();
+        MempoolSnapshot { entries }
+    }
+}
+
+impl MempoolSnapshot {
+    /// Returns the number of captured transactions.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the pool was empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the captured txids, best first.
+    pub fn txids(&self) -> impl Iterator<Item = &TxId> {
+        self.entries.iter().map(|entry| &entry.txid)
+    }
+
+    /// Returns transactions whose sizes sum to at most `max_bytes`, in an
+    /// order a block can include them.
+    ///
+    /// Transactions are taken in snapshot order. One that does not fit is
+    /// skipped, along with everything spending it, and smaller ones after
+    /// it may still fill the space.
+    #[must_use]
+    pub fn block_template(&self, max_bytes: usize) -> Vec<Transaction> {
+        let mut skipped = HashSet::new();
+        let mut remaining = max_bytes;
+        let mut template = Vec::new();
+        for entry in self.entries.iter() {
+            let orphaned = entry
+                .tx
+                .inputs
+                .iter()
+                .any(|input| skipped.contains(&input.prev_tx));
+            if orphaned || entry.size > remaining {
+                skipped.insert(entry.txid);
+                continue;
+            }
+            remaining -= entry.size;
+            template.push(entry.tx.clone());
+        }
+        template
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_crypto::PrivateKey;
+    use horizcoin_primitives::Amount;
+    use horizcoin_tx::{TxInput, TxOutput};
+
+    use super::*;
+
+    fn spend(prev_tx: TxId) -> Transaction {
+        Transaction::new(
+            vec![TxInput::new(
+                prev_tx,
+                0,
+                PrivateKey::generate().public_key(),
+            )],
+            vec![TxOutput::new(Amount::new(1_000), "hz1payee")],
+            None,
+        )
+    }
+
+    #[test]
+    fn templates_from_one_snapshot_match() {
+        let mut pool = Mempool::new();
+        let parent = spend(TxId::new([1; 32]));
+        let child = spend(parent.txid().unwrap());
+        let other = spend(TxId::new([2; 32]));
+        pool.add(parent.clone(), Amount::new(100)).unwrap();
+        pool.add(child.clone(), Amount::new(9_000)).unwrap();
+        pool.add(other.clone(), Amount::new(2_000)).unwrap();
+
+        let snapshot = pool.snapshot();
+        assert_eq!(snapshot.len(), 3);
+        let template = snapshot.block_template(usize::MAX);
+        assert_eq!(template, pool.select_for_block(usize::MAX));
+        assert_eq!(template, [parent.clone(), child, other.clone()]);
+
+        // The pool moving on leaves the snapshot, and its clones, alone.
+        pool.remove(&other.txid().unwrap());
+        pool.add(spend(TxId::new([3; 32])), Amount::new(50_000))
+            .unwrap();
+        let shared = snapshot.clone();
+        assert_eq!(shared.block_template(usize::MAX), template);
+
+        // A smaller block takes a prefix that keeps parents with children.
+        let budget = parent.serialized_size().unwrap();
+        assert_eq!(snapshot.block_template(budget), [parent]);
+    }
+
+    #[test]
+    fn fresh_snapshot_sees_new_transactions() {
+        let mut pool = Mempool::new();
+        assert!(pool.snapshot().is_empty());
+        let first = spend(TxId::new([1; 32]));
+        pool.add(first.clone(), Amount::new(1_000)).unwrap();
+        let before = pool.snapshot();
+
+        let second = spend(TxId::new([2; 32]));
+        pool.add(second.clone(), Amount::new(5_000)).unwrap();
+        assert_eq!(
+            before.block_template(usize::MAX),
+            std::slice::from_ref(&first)
+        );
+        let after = pool.snapshot();
+        assert_eq!(
+            after.txids().copied().collect::<Vec<_>>(),
+            [second.txid().unwrap(), first.txid().unwrap()]
+        );
+        assert_eq!(after.block_template(usize::MAX), [second, first]);
+    }
+}
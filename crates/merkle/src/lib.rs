@@ -2,11 +2,446 @@
 //!
 //! This crate provides Merkle tree functionality with `SHA-256` hashing
 //! and proof generation for the `HorizCoin` blockchain.
+//!
+//! Leaves and internal nodes are hashed in separate domains: a leaf hashes
+//! as `SHA-256(LEAF_PREFIX || leaf)` and an internal node as
+//! `SHA-256(NODE_PREFIX || left || right)`. This is a consensus rule: it
+//! keeps an internal node from being passed off as a leaf (a second-preimage
+//! attack), and changing it changes every root. When a level has an odd
+//! number of nodes the last one is paired with itself, as in Bitcoin.
+
+pub mod smt;
+
+use horizcoin_crypto::Sha256Hasher;
+use horizcoin_primitives::{Hash, HorizError};
+use serde::{Deserialize, Serialize};
+
+/// Domain prefix hashed before a leaf.
+pub const LEAF_PREFIX: u8 = 0x00;
+
+/// Domain prefix hashed before the two children of an internal node.
+pub const NODE_PREFIX: u8 = 0x01;
+
+/// A Merkle tree that keeps every level, so proofs can be produced without
+/// rehashing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTree {
+    leaves: Vec<Hash>,
+    /// `levels[0]` are the leaf hashes and the last level holds only the root.
+    levels: Vec<Vec<Hash>>,
+    root: Hash,
+}
+
+impl MerkleTree {
+    /// Builds a tree over `leaves`.
+    ///
+    /// An empty tree has the all-zero root.
+    #[must_use]
+    pub fn from_leaves(leaves: Vec<Hash>) -> Self {
+        if leaves.is_empty() {
+            return Self {
+                leaves,
+                levels: Vec::new(),
+                root: Hash::ZERO,
+            };
+        }
+
+        let mut levels = vec![leaves.iter().map(hash_leaf).collect::<Vec<_>>()];
+        while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+            let next = level
+                .chunks(2)
+                .map(|pair| hash_nodes(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+                .collect();
+            levels.push(next);
+        }
+        let root = levels[levels.len() - 1][0];
+        Self {
+            leaves,
+            levels,
+            root,
+        }
+    }
+
+    /// Appends `leaf`, rehashing only the last node of each level.
+    ///
+    /// The result is identical to rebuilding with [`MerkleTree::from_leaves`].
+    pub fn push_leaf(&mut self, leaf: Hash) {
+        self.leaves.push(leaf);
+        if self.levels.is_empty() {
+            self.levels.push(Vec::new());
+        }
+        self.levels[0].push(hash_leaf(&leaf));
+
+        let mut depth = 0;
+        while self.levels[depth].len() > 1 {
+            let level = &self.levels[depth];
+            let position = (level.len() - 1) / 2;
+            let left = level[2 * position];
+            let parent = hash_nodes(&left, level.get(2 * position + 1).unwrap_or(&left));
+
+            if depth + 1 == self.levels.len() {
+                self.levels.push(Vec::new());
+            }
+            let above = &mut self.levels[depth + 1];
+            if position < above.len() {
+                above[position] = parent;
+            } else {
+                above.push(parent);
+            }
+            depth += 1;
+        }
+        self.root = self.levels[depth][0];
+    }
+
+    /// Returns the root hash.
+    #[must_use]
+    pub const fn root(&self) -> Hash {
+        self.root
+    }
+
+    /// Returns the leaves in order.
+    #[must_use]
+    pub fn leaves(&self) -> &[Hash] {
+        &self.leaves
+    }
+
+    /// Returns the number of leaves.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Returns `true` if the tree has no leaves.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Returns a proof that the leaf at `index` is part of this tree.
+    pub fn proof(&self, index: usize) -> Result<MerkleProof, HorizError> {
+        if index >= self.leaves.len() {
+            return Err(HorizError::Merkle(format!(
+                "leaf index {index} out of range for {} leaves",
+                self.leaves.len()
+            )));
+        }
+
+        let mut siblings = Vec::with_capacity(self.levels.len() - 1);
+        let mut position = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            // A node without a right neighbour was paired with itself.
+            let sibling = level.get(position ^ 1).unwrap_or(&level[position]);
+            siblings.push(*sibling);
+            position /= 2;
+        }
+        Ok(MerkleProof {
+            leaf_index: index,
+            siblings,
+        })
+    }
+
+    /// Returns a single proof covering every leaf in `indices`.
+    ///
+    /// Siblings shared between the paths, or derivable from other proven
+    /// leaves, are included only once. Duplicate indices are ignored.
+    pub fn multi_proof(&self, indices: &[usize]) -> Result<MerkleMultiProof, HorizError> {
+        if indices.is_empty() {
+            return Err(HorizError::Merkle(
+                "multi-proof needs at least one leaf".into(),
+            ));
+        }
+        let mut sorted = indices.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if let Some(&index) = sorted.iter().find(|&&index| index >= self.leaves.len()) {
+            return Err(HorizError::Merkle(format!(
+                "leaf index {index} out of range for {} leaves",
+                self.leaves.len()
+            )));
+        }
+
+        let leaves: Vec<_> = sorted
+            .into_iter()
+            .map(|index| (index, self.leaves[index]))
+            .collect();
+        let mut hashes = Vec::new();
+        let mut known = hash_known_leaves(&leaves);
+        for level in &self.levels[..self.levels.len() - 1] {
+            known = fold_level(&known, level.len(), |position| {
+                hashes.push(level[position]);
+                Some(level[position])
+            })
+            .expect("tree levels supply every sibling");
+        }
+
+        Ok(MerkleMultiProof {
+            leaf_count: self.leaves.len(),
+            leaves,
+            hashes,
+        })
+    }
+}
+
+/// A path of sibling hashes from one leaf up to the root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    /// Position of the proven leaf; its bits give the side of each sibling.
+    pub leaf_index: usize,
+    /// Sibling hashes from the leaf level upwards.
+    pub siblings: Vec<Hash>,
+}
+
+impl MerkleProof {
+    /// Recomputes the root implied by this proof for `leaf`.
+    #[must_use]
+    pub fn compute_root(&self, leaf: Hash) -> Hash {
+        let mut position = self.leaf_index;
+        let mut current = hash_leaf(&leaf);
+        for sibling in &self.siblings {
+            current = if position & 1 == 0 {
+                hash_nodes(&current, sibling)
+            } else {
+                hash_nodes(sibling, &current)
+            };
+            position /= 2;
+        }
+        current
+    }
+
+    /// Returns `true` if this proof links `leaf` to `root`.
+    #[must_use]
+    pub fn verify(&self, leaf: Hash, root: Hash) -> bool {
+        self.compute_root(leaf) == root
+    }
+}
+
+/// A proof that several leaves belong to one tree.
+///
+/// Only the sibling hashes that cannot be derived from the proven leaves are
+/// carried, in the order [`MerkleMultiProof::verify`] consumes them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleMultiProof {
+    /// Number of leaves in the tree.
+    pub leaf_count: usize,
+    /// Proven leaves as `(index, hash)`, sorted by index.
+    pub leaves: Vec<(usize, Hash)>,
+    /// Sibling hashes consumed level by level, left to right.
+    pub hashes: Vec<Hash>,
+}
+
+impl MerkleMultiProof {
+    /// Returns `true` if the proof reconstructs exactly `root`.
+    #[must_use]
+    pub fn verify(&self, root: Hash) -> bool {
+        let sorted = self.leaves.windows(2).all(|pair| pair[0].0 < pair[1].0);
+        let in_range = self
+            .leaves
+            .last()
+            .is_some_and(|&(index, _)| index < self.leaf_count);
+        if !sorted || !in_range {
+            return false;
+        }
+
+        let mut hashes = self.hashes.iter().copied();
+        let mut known = hash_known_leaves(&self.leaves);
+        let mut level_len = self.leaf_count;
+        while level_len > 1 {
+            match fold_level(&known, level_len, |_| hashes.next()) {
+                Some(next) => known = next,
+                None => return false,
+            }
+            level_len = (level_len + 1) / 2;
+        }
+        hashes.next().is_none() && known == [(0, root)]
+    }
+}
+
+/// Computes the Merkle root of `leaves` without keeping the tree.
+#[must_use]
+pub fn compute_merkle_root(leaves: &[Hash]) -> Hash {
+    if leaves.is_empty() {
+        return Hash::ZERO;
+    }
+    let mut level: Vec<_> = leaves.iter().map(hash_leaf).collect();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| hash_nodes(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+    }
+    level[0]
+}
+
+/// Combines the known `(position, hash)` nodes of one level, sorted by
+/// position, into the known nodes of the level above. Siblings that are not
+/// known are requested from `sibling` by position.
+fn fold_level(
+    known: &[(usize, Hash)],
+    level_len: usize,
+    mut sibling: impl FnMut(usize) -> Option<Hash>,
+) -> Option<Vec<(usize, Hash)>> {
+    let mut parents = Vec::with_capacity(known.len());
+    let mut i = 0;
+    while i < known.len() {
+        let (position, hash) = known[i];
+        let parent = if position & 1 == 0 {
+            let right = match known.get(i + 1) {
+                Some(&(next, next_hash)) if next == position + 1 => {
+                    i += 1;
+                    next_hash
+                }
+                _ if position + 1 >= level_len => hash,
+                _ => sibling(position + 1)?,
+            };
+            hash_nodes(&hash, &right)
+        } else {
+            hash_nodes(&sibling(position - 1)?, &hash)
+        };
+        parents.push((position / 2, parent));
+        i += 1;
+    }
+    Some(parents)
+}
+
+fn hash_known_leaves(leaves: &[(usize, Hash)]) -> Vec<(usize, Hash)> {
+    leaves
+        .iter()
+        .map(|(index, leaf)| (*index, hash_leaf(leaf)))
+        .collect()
+}
+
+fn hash_leaf(leaf: &Hash) -> Hash {
+    let mut hasher = Sha256Hasher::new();
+    hasher.update(&[LEAF_PREFIX]);
+    hasher.update(leaf.as_bytes());
+    hasher.finalize()
+}
+
+fn hash_nodes(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256Hasher::new();
+    hasher.update(&[NODE_PREFIX]);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    hasher.finalize()
+}
 
 #[cfg(test)]
 mod tests {
+    use horizcoin_crypto::sha256;
+
+    use super::*;
+
+    fn leaves(n: u8) -> Vec<Hash> {
+        (0..n).map(|i| sha256(&[i])).collect()
+    }
+
+    #[test]
+    fn root_matches_manual_computation() {
+        let l = leaves(3);
+        let h: Vec<_> = l.iter().map(hash_leaf).collect();
+        let left = hash_nodes(&h[0], &h[1]);
+        let right = hash_nodes(&h[2], &h[2]);
+        let tree = MerkleTree::from_leaves(l.clone());
+        assert_eq!(tree.root(), hash_nodes(&left, &right));
+        assert_eq!(compute_merkle_root(&l), tree.root());
+
+        let mut prefixed = vec![LEAF_PREFIX];
+        prefixed.extend_from_slice(l[0].as_bytes());
+        assert_eq!(
+            MerkleTree::from_leaves(vec![l[0]]).root(),
+            sha256(&prefixed)
+        );
+        assert_eq!(MerkleTree::from_leaves(Vec::new()).root(), Hash::ZERO);
+    }
+
+    #[test]
+    fn last_leaf_of_odd_tree_verifies() {
+        for n in [3, 5] {
+            let tree = MerkleTree::from_leaves(leaves(n));
+            let last = usize::from(n) - 1;
+            let proof = tree.proof(last).unwrap();
+            assert!(proof.verify(tree.leaves()[last], tree.root()));
+        }
+    }
+
+    #[test]
+    fn every_leaf_verifies_and_tampering_fails() {
+        for n in 1..=9 {
+            let tree = MerkleTree::from_leaves(leaves(n));
+            for (index, leaf) in tree.leaves().iter().enumerate() {
+                let proof = tree.proof(index).unwrap();
+                assert!(
+                    proof.verify(*leaf, tree.root()),
+                    "{n} leaves, index {index}"
+                );
+                assert!(!proof.verify(sha256(b"forged"), tree.root()));
+            }
+            assert!(tree.proof(usize::from(n)).is_err());
+        }
+    }
+
+    #[test]
+    fn multi_proof_verifies_and_is_compact() {
+        let tree = MerkleTree::from_leaves(leaves(8));
+        let indices = [0, 2, 5];
+        let proof = tree.multi_proof(&indices).unwrap();
+        assert!(proof.verify(tree.root()));
+
+        let separate: usize = indices
+            .iter()
+            .map(|&index| tree.proof(index).unwrap().siblings.len())
+            .sum();
+        assert_eq!(separate, 9);
+        assert_eq!(proof.hashes.len(), 4);
+
+        for n in 1..=9 {
+            let tree = MerkleTree::from_leaves(leaves(n));
+            let all: Vec<_> = (0..usize::from(n)).collect();
+            assert!(tree.multi_proof(&all).unwrap().verify(tree.root()));
+            let last = [usize::from(n) - 1];
+            assert!(tree.multi_proof(&last).unwrap().verify(tree.root()));
+        }
+    }
+
+    #[test]
+    fn multi_proof_rejects_wrong_leaf() {
+        let tree = MerkleTree::from_leaves(leaves(8));
+        let mut proof = tree.multi_proof(&[0, 2, 5]).unwrap();
+        proof.leaves[1].1 = sha256(b"forged");
+        assert!(!proof.verify(tree.root()));
+
+        let mut extra = tree.multi_proof(&[0, 2, 5]).unwrap();
+        extra.hashes.push(Hash::ZERO);
+        assert!(!extra.verify(tree.root()));
+
+        assert!(tree.multi_proof(&[]).is_err());
+        assert!(tree.multi_proof(&[8]).is_err());
+    }
+
+    #[test]
+    fn internal_node_does_not_verify_as_leaf() {
+        let tree = MerkleTree::from_leaves(leaves(4));
+        let internal = tree.levels[1][0];
+        let forged = MerkleProof {
+            leaf_index: 0,
+            siblings: vec![tree.levels[1][1]],
+        };
+        assert!(!forged.verify(internal, tree.root()));
+        assert_ne!(
+            MerkleTree::from_leaves(tree.levels[1].clone()).root(),
+            tree.root()
+        );
+    }
+
     #[test]
-    fn placeholder_test() {
-        // Placeholder test to ensure the crate compiles
+    fn push_leaf_matches_rebuild() {
+        let all = leaves(17);
+        let mut tree = MerkleTree::from_leaves(Vec::new());
+        for (count, leaf) in all.iter().enumerate() {
+            tree.push_leaf(*leaf);
+            let rebuilt = MerkleTree::from_leaves(all[..=count].to_vec());
+            assert_eq!(tree.root(), rebuilt.root());
+            assert_eq!(tree, rebuilt);
+        }
     }
 }
@@ -0,0 +1,304 @@
+//! Sparse Merkle tree over 256-bit keys.
+//!
+//! Every possible key has a leaf slot, selected by the key's bits from the
+//! most significant down. Empty slots hold [`Hash::ZERO`], and the root of
+//! an empty subtree of each height is precomputed, so only nodes above
+//! occupied slots are stored. Proofs show either that a key maps to a value
+//! (inclusion) or that its slot is empty (exclusion).
+
+use std::{collections::HashMap, sync::OnceLock};
+
+use horizcoin_crypto::Sha256Hasher;
+use horizcoin_primitives::Hash;
+use serde::{Deserialize, Serialize};
+
+use crate::{hash_nodes, LEAF_PREFIX};
+
+/// Number of levels between a leaf and the root.
+pub const SMT_DEPTH: usize = 256;
+
+/// A sparse Merkle tree mapping keys to values.
+#[derive(Debug, Clone, Default)]
+pub struct SparseMerkleTree {
+    values: HashMap<Hash, Hash>,
+    /// Non-default nodes keyed by `(height, path)`, where `path` is the key
+    /// with the bits below the node cleared. Height 0 holds the leaves.
+    nodes: HashMap<(usize, Hash), Hash>,
+}
+
+impl SparseMerkleTree {
+    /// Creates an empty tree.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the root hash.
+    #[must_use]
+    pub fn root(&self) -> Hash {
+        self.node(SMT_DEPTH, &Hash::ZERO)
+    }
+
+    /// Returns the value stored under `key`.
+    #[must_use]
+    pub fn get(&self, key: &Hash) -> Option<Hash> {
+        self.values.get(key).copied()
+    }
+
+    /// Returns the number of keys with a value.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if no key has a value.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Sets `key` to `value`, returning the previous value.
+    pub fn insert(&mut self, key: Hash, value: Hash) -> Option<Hash> {
+        let previous = self.values.insert(key, value);
+        self.update_path(key, hash_leaf(&key, &value));
+        previous
+    }
+
+    /// Clears `key`, returning the value it held.
+    pub fn remove(&mut self, key: &Hash) -> Option<Hash> {
+        let previous = self.values.remove(key)?;
+        self.update_path(*key, defaults()[0]);
+        Some(previous)
+    }
+
+    /// Returns a proof of the current value of `key`, or of its absence.
+    #[must_use]
+    pub fn prove(&self, key: Hash) -> SmtProof {
+        let mut bitmap = [0u8; SMT_DEPTH / 8];
+        let mut siblings = Vec::new();
+        let mut path = key;
+        for height in 0..SMT_DEPTH {
+            let sibling = self.node(height, &flip_bit(&path, SMT_DEPTH - 1 - height));
+            if sibling != defaults()[height] {
+                bitmap[height / 8] |= 0x80 >> (height % 8);
+                siblings.push(sibling);
+            }
+            path = clear_bit(&path, SMT_DEPTH - 1 - height);
+        }
+        SmtProof {
+            key,
+            value: self.get(&key),
+            bitmap,
+            siblings,
+        }
+    }
+
+    fn node(&self, height: usize, path: &Hash) -> Hash {
+        self.nodes
+            .get(&(height, *path))
+            .copied()
+            .unwrap_or_else(|| defaults()[height])
+    }
+
+    /// Stores `leaf` in the slot for `key` and rehashes the path to the root.
+    fn update_path(&mut self, key: Hash, leaf: Hash) {
+        let mut current = leaf;
+        let mut path = key;
+        for height in 0..SMT_DEPTH {
+            self.set_node(height, path, current);
+            let bit = SMT_DEPTH - 1 - height;
+            let sibling = self.node(height, &flip_bit(&path, bit));
+            current = if get_bit(&path, bit) {
+                hash_nodes(&sibling, &current)
+            } else {
+                hash_nodes(&current, &sibling)
+            };
+            path = clear_bit(&path, bit);
+        }
+        self.set_node(SMT_DEPTH, path, current);
+    }
+
+    fn set_node(&mut self, height: usize, path: Hash, hash: Hash) {
+        if hash == defaults()[height] {
+            self.nodes.remove(&(height, path));
+        } else {
+            self.nodes.insert((height, path), hash);
+        }
+    }
+}
+
+/// An inclusion or exclusion proof for one key of a [`SparseMerkleTree`].
+///
+/// Siblings equal to the empty-subtree hash of their height are omitted and
+/// flagged in a bitmap instead.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SmtProof {
+    key: Hash,
+    value: Option<Hash>,
+    /// Bit `h` (most significant first) is set if the sibling at height `h`
+    /// is carried in `siblings`.
+    bitmap: [u8; SMT_DEPTH / 8],
+    siblings: Vec<Hash>,
+}
+
+impl SmtProof {
+    /// Returns the key this proof is about.
+    #[must_use]
+    pub const fn key(&self) -> Hash {
+        self.key
+    }
+
+    /// Returns the proven value, or `None` for an exclusion proof.
+    #[must_use]
+    pub const fn value(&self) -> Option<Hash> {
+        self.value
+    }
+
+    /// Returns `true` if the proof reconstructs `root`.
+    #[must_use]
+    pub fn verify(&self, root: Hash) -> bool {
+        let mut siblings = self.siblings.iter();
+        let mut current = self
+            .value
+            .map_or_else(|| defaults()[0], |value| hash_leaf(&self.key, &value));
+        for height in 0..SMT_DEPTH {
+            let sibling = if self.bitmap[height / 8] & (0x80 >> (height % 8)) == 0 {
+                defaults()[height]
+            } else {
+                match siblings.next() {
+                    Some(sibling) => *sibling,
+                    None => return false,
+                }
+            };
+            current = if get_bit(&self.key, SMT_DEPTH - 1 - height) {
+                hash_nodes(&sibling, &current)
+            } else {
+                hash_nodes(&current, &sibling)
+            };
+        }
+        siblings.next().is_none() && current == root
+    }
+}
+
+/// Roots of empty subtrees, indexed by height.
+fn defaults() -> &'static [Hash; SMT_DEPTH + 1] {
+    static DEFAULTS: OnceLock<[Hash; SMT_DEPTH + 1]> = OnceLock::new();
+    DEFAULTS.get_or_init(|| {
+        let mut defaults = [Hash::ZERO; SMT_DEPTH + 1];
+        for height in 1..=SMT_DEPTH {
+            defaults[height] = hash_nodes(&defaults[height - 1], &defaults[height - 1]);
+        }
+        defaults
+    })
+}
+
+fn hash_leaf(key: &Hash, value: &Hash) -> Hash {
+    let mut hasher = Sha256Hasher::new();
+    hasher.update(&[LEAF_PREFIX]);
+    hasher.update(key.as_bytes());
+    hasher.update(value.as_bytes());
+    hasher.finalize()
+}
+
+/// Bits are numbered from the most significant bit of the first byte.
+const fn get_bit(hash: &Hash, bit: usize) -> bool {
+    hash.as_bytes()[bit / 8] & (0x80 >> (bit % 8)) != 0
+}
+
+const fn flip_bit(hash: &Hash, bit: usize) -> Hash {
+    let mut bytes = *hash.as_bytes();
+    bytes[bit / 8] ^= 0x80 >> (bit % 8);
+    Hash::new(bytes)
+}
+
+const fn clear_bit(hash: &Hash, bit: usize) -> Hash {
+    let mut bytes = *hash.as_bytes();
+    bytes[bit / 8] &= !(0x80 >> (bit % 8));
+    Hash::new(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_crypto::sha256;
+
+    use super::*;
+
+    fn filled() -> SparseMerkleTree {
+        let mut tree = SparseMerkleTree::new();
+        for i in 0..10u8 {
+            tree.insert(sha256(&[i]), sha256(&[i, i]));
+        }
+        tree
+    }
+
+    #[test]
+    fn inclusion_proofs_verify() {
+        let tree = filled();
+        assert_eq!(tree.len(), 10);
+        for i in 0..10u8 {
+            let proof = tree.prove(sha256(&[i]));
+            assert_eq!(proof.value(), Some(sha256(&[i, i])));
+            assert!(proof.verify(tree.root()));
+        }
+
+        let mut forged = tree.prove(sha256(&[3]));
+        forged.value = Some(sha256(b"forged"));
+        assert!(!forged.verify(tree.root()));
+    }
+
+    #[test]
+    fn exclusion_proofs_verify() {
+        let tree = filled();
+        let absent = sha256(b"absent");
+        let proof = tree.prove(absent);
+        assert_eq!(proof.value(), None);
+        assert!(proof.verify(tree.root()));
+
+        // Claiming absence for a present key must fail.
+        let mut forged = tree.prove(sha256(&[3]));
+        forged.value = None;
+        assert!(!forged.verify(tree.root()));
+
+        let empty = SparseMerkleTree::new();
+        assert!(empty.prove(absent).verify(empty.root()));
+        assert_eq!(empty.root(), defaults()[SMT_DEPTH]);
+    }
+
+    #[test]
+    fn update_then_reprove() {
+        let mut tree = filled();
+        let key = sha256(&[4]);
+        let old_root = tree.root();
+        let old_proof = tree.prove(key);
+
+        assert_eq!(tree.insert(key, sha256(b"new")), Some(sha256(&[4, 4])));
+        assert_ne!(tree.root(), old_root);
+        assert!(!old_proof.verify(tree.root()));
+        let new_proof = tree.prove(key);
+        assert_eq!(new_proof.value(), Some(sha256(b"new")));
+        assert!(new_proof.verify(tree.root()));
+
+        assert_eq!(tree.remove(&key), Some(sha256(b"new")));
+        assert_eq!(tree.remove(&key), None);
+        let removed = tree.prove(key);
+        assert_eq!(removed.value(), None);
+        assert!(removed.verify(tree.root()));
+    }
+
+    #[test]
+    fn root_depends_only_on_contents() {
+        let mut forward = SparseMerkleTree::new();
+        let mut backward = SparseMerkleTree::new();
+        for i in 0..10u8 {
+            forward.insert(sha256(&[i]), sha256(&[i, i]));
+            backward.insert(sha256(&[9 - i]), sha256(&[9 - i, 9 - i]));
+        }
+        assert_eq!(forward.root(), backward.root());
+
+        for i in 0..10u8 {
+            forward.remove(&sha256(&[i]));
+        }
+        assert_eq!(forward.root(), SparseMerkleTree::new().root());
+        assert!(forward.nodes.is_empty());
+    }
+}
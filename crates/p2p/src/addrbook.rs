@@ -0,0 +1,262 @@
+//! Persistent book of known peer addresses.
+
+use std::cmp::Ordering;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use horizcoin_primitives::HorizError;
+use horizcoin_storage::{Storage, TypedStorage};
+use serde::{Deserialize, Serialize};
+
+/// Column family holding the address book.
+pub const ADDR_BOOK_CF: &str = "addrbook";
+
+/// Default cap on the number of addresses in an [`AddrBook`].
+pub const DEFAULT_MAX_ADDRS: usize = 4_096;
+
+/// What is known about one peer address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddrInfo {
+    /// The address.
+    pub addr: SocketAddr,
+    /// Unix time in seconds the address was last heard of or connected to.
+    pub last_seen: u64,
+    /// Unix time in seconds of the last successful connection, or 0.
+    pub last_success: u64,
+    /// Connections that succeeded.
+    pub successes: u32,
+    /// Connections that failed.
+    pub failures: u32,
+}
+
+impl AddrInfo {
+    /// Returns `true` if a connection ever succeeded and failures do not
+    /// outnumber successes.
+    #[must_use]
+    pub const fn is_reliable(&self) -> bool {
+        self.successes > 0 && self.failures <= self.successes
+    }
+
+    /// Orders better dialing candidates first: reliable addresses, then the
+    /// most recent success, then the most recently seen, then the most
+    /// successes.
+    fn rank(&self, other: &Self) -> Ordering {
+        other
+            .is_reliable()
+            .cmp(&self.is_reliable())
+            .then(other.last_success.cmp(&self.last_success))
+            .then(other.last_seen.cmp(&self.last_seen))
+            .then(other.successes.cmp(&self.successes))
+            .then(self.addr.cmp(&other.addr))
+    }
+}
+
+/// Peer addresses with connection history, written through to a
+/// [`Storage`] so the node can reconnect to good peers after a restart.
+///
+/// The book holds a bounded number of addresses. When it is full, a new
+/// address replaces the worst entry only if that entry never proved itself,
+/// while a successful connection always makes room.
+#[derive(Debug)]
+pub struct AddrBook {
+    entries: TypedStorage<AddrInfo>,
+    max_addrs: usize,
+}
+
+impl AddrBook {
+    /// Opens the book kept in `storage`.
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            entries: TypedStorage::new(storage, ADDR_BOOK_CF),
+            max_addrs: DEFAULT_MAX_ADDRS,
+        }
+    }
+
+    /// Keeps at most `max_addrs` addresses.
+    #[must_use]
+    pub const fn with_max_addrs(mut self, max_addrs: usize) -> Self {
+        self.max_addrs = max_addrs;
+        self
+    }
+
+    /// Records that `addr` exists, e.g. because a peer advertised it.
+    pub fn add(&self, addr: SocketAddr) -> Result<(), HorizError> {
+        self.add_at(addr, unix_now())
+    }
+
+    /// Like [`add`](Self::add), as if heard of at `now` in Unix seconds.
+    pub fn add_at(&self, addr: SocketAddr, now: u64) -> Result<(), HorizError> {
+        self.update(addr, false, |info| info.last_seen = info.last_seen.max(now))
+    }
+
+    /// Records a successful connection to `addr`.
+    pub fn mark_success(&self, addr: SocketAddr) -> Result<(), HorizError> {
+        self.mark_success_at(addr, unix_now())
+    }
+
+    /// Like [`mark_success`](Self::mark_success), as if connected at `now`
+    /// in Unix seconds.
+    pub fn mark_success_at(&self, addr: SocketAddr, now: u64) -> Result<(), HorizError> {
+        self.update(addr, true, |info| {
+            info.last_seen = info.last_seen.max(now);
+            info.last_success = info.last_success.max(now);
+            info.successes = info.successes.saturating_add(1);
+        })
+    }
+
+    /// Records a failed connection attempt to `addr`.
+    pub fn mark_failure(&self, addr: SocketAddr) -> Result<(), HorizError> {
+        self.update(addr, false, |info| {
+            info.failures = info.failures.saturating_add(1);
+        })
+    }
+
+    /// Returns what is known about `addr`.
+    pub fn get(&self, addr: SocketAddr) -> Result<Option<AddrInfo>, HorizError> {
+        self.entries.get(&key(addr))
+    }
+
+    /// Returns every known address.
+    pub fn all(&self) -> Result<Vec<AddrInfo>, HorizError> {
+        Ok(self
+            .entries
+            .scan_prefix(b"")?
+            .into_iter()
+            .map(|(_, info)| info)
+            .collect())
+    }
+
+    /// Returns up to `n` addresses to dial, best first.
+    ///
+    /// Reliable addresses, which connected at least once and whose failures
+    /// do not outnumber their successes, come before the rest; within each
+    /// group the most recent successes come first, then the most recently
+    /// seen, then those with more successes.
+    pub fn select_candidates(&self, n: usize) -> Result<Vec<SocketAddr>, HorizError> {
+        let mut entries = self.all()?;
+        entries.sort_by(AddrInfo::rank);
+        Ok(entries.into_iter().take(n).map(|info| info.addr).collect())
+    }
+
+    /// Applies `f` to the entry for `addr`, creating it if there is room.
+    ///
+    /// A full book evicts its worst entry for the newcomer if `succeeded` or
+    /// that entry is not reliable, and otherwise ignores the newcomer.
+    fn update(
+        &self,
+        addr: SocketAddr,
+        succeeded: bool,
+        f: impl FnOnce(&mut AddrInfo),
+    ) -> Result<(), HorizError> {
+        let key = key(addr);
+        let mut info = if let Some(info) = self.entries.get(&key)? {
+            info
+        } else {
+            let entries = self.all()?;
+            if entries.len() >= self.max_addrs {
+                match entries.into_iter().max_by(AddrInfo::rank) {
+                    Some(worst) if succeeded || !worst.is_reliable() => {
+                        self.entries.delete(&self::key(worst.addr))?;
+                    }
+                    _ => return Ok(()),
+                }
+            }
+            AddrInfo {
+                addr,
+                last_seen: 0,
+                last_success: 0,
+                successes: 0,
+                failures: 0,
+            }
+        };
+        f(&mut info);
+        self.entries.put(&key, &info)
+    }
+}
+
+fn key(addr: SocketAddr) -> Vec<u8> {
+    addr.to_string().into_bytes()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_storage::MemoryStorage;
+
+    use super::*;
+
+    fn addr(last_octet: u8) -> SocketAddr {
+        SocketAddr::from(([10, 0, 0, last_octet], 8333))
+    }
+
+    #[test]
+    fn selection_prefers_recent_successes() {
+        let book = AddrBook::new(Arc::new(MemoryStorage::new()));
+        book.add_at(addr(1), 1_000).unwrap();
+        book.mark_success_at(addr(2), 500).unwrap();
+        book.mark_success_at(addr(3), 2_000).unwrap();
+        book.mark_success_at(addr(4), 3_000).unwrap();
+        book.mark_failure(addr(4)).unwrap();
+        book.mark_failure(addr(4)).unwrap();
+
+        // addr(1) was only heard of, so even the older success of addr(2)
+        // ranks ahead of it.
+        assert_eq!(
+            book.select_candidates(10).unwrap(),
+            [addr(3), addr(2), addr(4), addr(1)]
+        );
+        assert_eq!(book.select_candidates(2).unwrap(), [addr(3), addr(2)]);
+        assert!(book.select_candidates(0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn full_book_keeps_proven_peers() {
+        let book = AddrBook::new(Arc::new(MemoryStorage::new())).with_max_addrs(2);
+        book.add_at(addr(1), 1_000).unwrap();
+        book.mark_success_at(addr(2), 500).unwrap();
+
+        // Untried addresses displace each other, never a proven peer.
+        book.add_at(addr(3), 2_000).unwrap();
+        assert!(book.get(addr(1)).unwrap().is_none());
+        book.mark_failure(addr(4)).unwrap();
+        assert!(book.get(addr(3)).unwrap().is_none());
+
+        book.mark_success_at(addr(5), 3_000).unwrap();
+        assert!(book.get(addr(4)).unwrap().is_none());
+        book.add_at(addr(6), 4_000).unwrap();
+        assert!(book.get(addr(6)).unwrap().is_none());
+        assert_eq!(book.select_candidates(10).unwrap(), [addr(5), addr(2)]);
+    }
+
+    #[test]
+    fn book_survives_reopening() {
+        let storage = Arc::new(MemoryStorage::new());
+        {
+            let book = AddrBook::new(storage.clone());
+            book.mark_success_at(addr(7), 1_234).unwrap();
+            book.mark_failure(addr(8)).unwrap();
+            book.add(addr(9)).unwrap();
+        }
+
+        let reopened = AddrBook::new(storage);
+        assert_eq!(
+            reopened.get(addr(7)).unwrap(),
+            Some(AddrInfo {
+                addr: addr(7),
+                last_seen: 1_234,
+                last_success: 1_234,
+                successes: 1,
+                failures: 0,
+            })
+        );
+        assert_eq!(reopened.get(addr(8)).unwrap().unwrap().failures, 1);
+        assert!(reopened.get(addr(9)).unwrap().unwrap().last_seen > 1_234);
+        assert_eq!(reopened.all().unwrap().len(), 3);
+    }
+}
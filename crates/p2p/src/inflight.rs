@@ -0,0 +1,189 @@
+//! Deduplication of outstanding transaction requests.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use horizcoin_primitives::TxId;
+
+use crate::PeerId;
+
+/// How long a request may go unanswered before it can be re-issued.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default cap on the number of requests tracked at once.
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 5_000;
+
+/// Remembers which announced transactions have been requested, and from
+/// whom, so each is fetched from only one peer at a time.
+///
+/// At most a fixed number of requests are tracked; once full, new requests
+/// are refused until answered or timed-out ones make room.
+#[derive(Debug, Clone)]
+pub struct InFlightTracker {
+    timeout: Duration,
+    max_in_flight: usize,
+    requests: HashMap<TxId, (PeerId, Instant)>,
+}
+
+impl Default for InFlightTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_REQUEST_TIMEOUT)
+    }
+}
+
+impl InFlightTracker {
+    /// Creates a tracker letting requests time out after `timeout`.
+    #[must_use]
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            requests: HashMap::new(),
+        }
+    }
+
+    /// Tracks at most `max_in_flight` requests at once.
+    #[must_use]
+    pub const fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    /// Records that `item` is being requested from `peer`.
+    ///
+    /// Returns `false`, recording nothing, if `item` is already in flight
+    /// and has not timed out, or if the tracker is full even after dropping
+    /// timed-out requests; the caller should not send the request.
+    pub fn request(&mut self, item: TxId, peer: PeerId) -> bool {
+        self.request_at(item, peer, Instant::now())
+    }
+
+    /// Like [`request`](Self::request), as if issued at `now`.
+    pub fn request_at(&mut self, item: TxId, peer: PeerId, now: Instant) -> bool {
+        if let Some(&(_, sent)) = self.requests.get(&item) {
+            if now.duration_since(sent) < self.timeout {
+                return false;
+            }
+        } else if self.requests.len() >= self.max_in_flight {
+            self.prune_expired(now);
+            if self.requests.len() >= self.max_in_flight {
+                return false;
+            }
+        }
+        self.requests.insert(item, (peer, now));
+        true
+    }
+
+    /// Drops every request that has gone unanswered for the timeout as of
+    /// `now`.
+    pub fn prune_expired(&mut self, now: Instant) {
+        let timeout = self.timeout;
+        self.requests
+            .retain(|_, &mut (_, sent)| now.duration_since(sent) < timeout);
+    }
+
+    /// Clears `item` once it has arrived.
+    pub fn received(&mut self, item: &TxId) {
+        self.requests.remove(item);
+    }
+
+    /// Returns the peer `item` was last requested from, if it is tracked.
+    #[must_use]
+    pub fn peer_for(&self, item: &TxId) -> Option<PeerId> {
+        self.requests.get(item).map(|&(peer, _)| peer)
+    }
+
+    /// Drops every request sent to `peer`, typically after it disconnects,
+    /// so they can be re-issued at once.
+    pub fn remove_peer(&mut self, peer: PeerId) {
+        self.requests
+            .retain(|_, (requested_from, _)| *requested_from != peer);
+    }
+
+    /// Returns the number of tracked requests.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Returns `true` if nothing is in flight.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITEM: TxId = TxId::new([1; 32]);
+
+    #[test]
+    fn deduplicates_announcements() {
+        let mut tracker = InFlightTracker::default();
+        let now = Instant::now();
+        assert!(tracker.request_at(ITEM, PeerId(1), now));
+        assert!(!tracker.request_at(ITEM, PeerId(2), now));
+        assert!(!tracker.request_at(ITEM, PeerId(1), now + Duration::from_secs(1)));
+        assert!(tracker.request_at(TxId::new([2; 32]), PeerId(2), now));
+        assert_eq!(tracker.peer_for(&ITEM), Some(PeerId(1)));
+        assert_eq!(tracker.len(), 2);
+    }
+
+    #[test]
+    fn timeout_allows_rerequest() {
+        let mut tracker = InFlightTracker::new(Duration::from_secs(5));
+        let now = Instant::now();
+        assert!(tracker.request_at(ITEM, PeerId(1), now));
+        assert!(!tracker.request_at(ITEM, PeerId(2), now + Duration::from_millis(4_999)));
+        assert!(tracker.request_at(ITEM, PeerId(2), now + Duration::from_secs(5)));
+        assert_eq!(tracker.peer_for(&ITEM), Some(PeerId(2)));
+
+        tracker.remove_peer(PeerId(2));
+        assert!(tracker.request_at(ITEM, PeerId(3), now + Duration::from_secs(6)));
+    }
+
+    #[test]
+    fn expired_requests_are_pruned() {
+        let mut tracker = InFlightTracker::new(Duration::from_secs(5));
+        let now = Instant::now();
+        assert!(tracker.request_at(ITEM, PeerId(1), now));
+        assert!(tracker.request_at(TxId::new([2; 32]), PeerId(1), now + Duration::from_secs(3)));
+
+        tracker.prune_expired(now + Duration::from_secs(5));
+        assert_eq!(tracker.peer_for(&ITEM), None);
+        assert_eq!(tracker.len(), 1);
+        tracker.prune_expired(now + Duration::from_secs(8));
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn capacity_bounds_requests() {
+        let mut tracker = InFlightTracker::new(Duration::from_secs(5)).with_max_in_flight(2);
+        let now = Instant::now();
+        assert!(tracker.request_at(ITEM, PeerId(1), now));
+        assert!(tracker.request_at(TxId::new([2; 32]), PeerId(1), now + Duration::from_secs(1)));
+        assert!(!tracker.request_at(TxId::new([3; 32]), PeerId(1), now + Duration::from_secs(1)));
+        assert_eq!(tracker.len(), 2);
+
+        // Re-issuing a timed-out request does not need room.
+        assert!(tracker.request_at(ITEM, PeerId(2), now + Duration::from_secs(5)));
+        assert!(!tracker.request_at(TxId::new([3; 32]), PeerId(1), now + Duration::from_secs(5)));
+
+        // Once the oldest times out, a new request takes its place.
+        assert!(tracker.request_at(TxId::new([3; 32]), PeerId(1), now + Duration::from_secs(6)));
+        assert_eq!(tracker.peer_for(&TxId::new([2; 32])), None);
+        assert_eq!(tracker.len(), 2);
+    }
+
+    #[test]
+    fn receipt_clears_state() {
+        let mut tracker = InFlightTracker::default();
+        assert!(tracker.request(ITEM, PeerId(1)));
+        tracker.received(&ITEM);
+        assert!(tracker.is_empty());
+        assert_eq!(tracker.peer_for(&ITEM), None);
+        assert!(tracker.request(ITEM, PeerId(2)));
+    }
+}
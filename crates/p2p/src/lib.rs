@@ -3,10 +3,19 @@
 //! This crate provides gossip-based networking with headers-first sync
 //! and anti-`DoS` protection for the `HorizCoin` blockchain.
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn placeholder_test() {
-        // Placeholder test to ensure the crate compiles
-    }
-}
+mod addrbook;
+mod inflight;
+mod message;
+mod peer;
+mod score;
+mod sync;
+
+pub use addrbook::{AddrBook, AddrInfo, ADDR_BOOK_CF, DEFAULT_MAX_ADDRS};
+pub use inflight::{InFlightTracker, DEFAULT_MAX_IN_FLIGHT, DEFAULT_REQUEST_TIMEOUT};
+pub use message::{
+    decode_message, encode_message, Message, MessageKind, CHECKSUM_LEN, FRAME_HEADER_LEN, MAGIC,
+    PROTOCOL_VERSION,
+};
+pub use peer::PeerId;
+pub use score::{Misbehavior, PeerScore, ScoreConfig};
+pub use sync::{HeaderSync, DEFAULT_MAX_FORK_DEPTH, DEFAULT_MAX_SIDE_HEADERS, MAX_LOCATOR_LEN};
@@ -0,0 +1,264 @@
+//! Wire messages and their framing.
+//!
+//! A frame is the [`MAGIC`] bytes, one [`MessageKind`] byte, a checksum of
+//! the payload, then the payload as a length-prefixed frame from
+//! `horizcoin-codec`.
+
+use horizcoin_block::BlockHeader;
+use horizcoin_codec::LENGTH_PREFIX_LEN;
+use horizcoin_crypto::double_sha256;
+use horizcoin_primitives::{BlockId, HorizError, TxId};
+use horizcoin_tx::Transaction;
+use serde::{Deserialize, Serialize};
+
+/// Bytes opening every frame, so peers on other protocols are rejected
+/// early.
+pub const MAGIC: [u8; 4] = *b"HRZN";
+
+/// Version of the protocol spoken by this node.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Length of the payload checksum: the first bytes of its double `SHA-256`.
+pub const CHECKSUM_LEN: usize = 4;
+
+/// Length of everything before the payload's length prefix.
+pub const FRAME_HEADER_LEN: usize = MAGIC.len() + 1 + CHECKSUM_LEN;
+
+/// A message exchanged between peers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Message {
+    /// Opens a connection, announcing what the sender speaks and has.
+    Version {
+        /// The sender's [`PROTOCOL_VERSION`].
+        protocol_version: u32,
+        /// Height of the sender's best block.
+        best_height: u64,
+    },
+    /// Acknowledges a [`Version`](Self::Version).
+    VerAck,
+    /// Liveness probe carrying a nonce to echo.
+    Ping(u64),
+    /// Reply to a [`Ping`](Self::Ping) with its nonce.
+    Pong(u64),
+    /// Asks for headers following the first locator entry the peer knows.
+    GetHeaders {
+        /// Known block ids, newest first, thinning out towards genesis.
+        locator: Vec<BlockId>,
+        /// Last header wanted, or zero for as many as the peer will send.
+        stop: BlockId,
+    },
+    /// Headers in chain order.
+    Headers(Vec<BlockHeader>),
+    /// Announces transactions the sender has.
+    Inv(Vec<TxId>),
+    /// A full transaction.
+    Tx(Transaction),
+}
+
+/// The type of a [`Message`], sent as a single byte ahead of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageKind {
+    /// [`Message::Version`].
+    Version,
+    /// [`Message::VerAck`].
+    VerAck,
+    /// [`Message::Ping`].
+    Ping,
+    /// [`Message::Pong`].
+    Pong,
+    /// [`Message::GetHeaders`].
+    GetHeaders,
+    /// [`Message::Headers`].
+    Headers,
+    /// [`Message::Inv`].
+    Inv,
+    /// [`Message::Tx`].
+    Tx,
+}
+
+impl MessageKind {
+    /// All kinds, in wire order.
+    pub const ALL: [Self; 8] = [
+        Self::Version,
+        Self::VerAck,
+        Self::Ping,
+        Self::Pong,
+        Self::GetHeaders,
+        Self::Headers,
+        Self::Inv,
+        Self::Tx,
+    ];
+
+    /// Returns the byte identifying this kind on the wire.
+    #[must_use]
+    pub const fn to_byte(self) -> u8 {
+        self as u8
+    }
+
+    /// Parses the byte produced by [`to_byte`](Self::to_byte).
+    #[must_use]
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        Self::ALL.get(usize::from(byte)).copied()
+    }
+}
+
+impl Message {
+    /// Returns the type of this message.
+    #[must_use]
+    pub const fn kind(&self) -> MessageKind {
+        match self {
+            Self::Version { .. } => MessageKind::Version,
+            Self::VerAck => MessageKind::VerAck,
+            Self::Ping(_) => MessageKind::Ping,
+            Self::Pong(_) => MessageKind::Pong,
+            Self::GetHeaders { .. } => MessageKind::GetHeaders,
+            Self::Headers(_) => MessageKind::Headers,
+            Self::Inv(_) => MessageKind::Inv,
+            Self::Tx(_) => MessageKind::Tx,
+        }
+    }
+}
+
+/// Encodes `message` as one frame.
+pub fn encode_message(message: &Message) -> Result<Vec<u8>, HorizError> {
+    let payload = horizcoin_codec::encode_with_length(message)?;
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+    frame.extend_from_slice(&MAGIC);
+    frame.push(message.kind().to_byte());
+    frame.extend_from_slice(&checksum(&payload[LENGTH_PREFIX_LEN..]));
+    frame.extend_from_slice(&payload);
+    Ok(frame)
+}
+
+/// Decodes one frame produced by [`encode_message`], returning the message
+/// and the number of bytes consumed.
+///
+/// Fails on foreign magic, an unknown or mismatched type byte, a bad
+/// checksum, or a payload that does not decode.
+pub fn decode_message(bytes: &[u8]) -> Result<(Message, usize), HorizError> {
+    let header = bytes.get(..FRAME_HEADER_LEN).ok_or_else(|| {
+        malformed(format!(
+            "frame of {} bytes is shorter than its header",
+            bytes.len()
+        ))
+    })?;
+    let (magic, rest) = header.split_at(MAGIC.len());
+    if magic != MAGIC {
+        return Err(malformed("bad magic"));
+    }
+    let kind = MessageKind::from_byte(rest[0])
+        .ok_or_else(|| malformed(format!("unknown message type {}", rest[0])))?;
+    let expected_checksum = &rest[1..];
+
+    let body = &bytes[FRAME_HEADER_LEN..];
+    let (message, consumed): (Message, usize) = horizcoin_codec::decode_with_length(body)?;
+    if checksum(&body[LENGTH_PREFIX_LEN..consumed]) != expected_checksum {
+        return Err(malformed("checksum mismatch"));
+    }
+    if message.kind() != kind {
+        return Err(malformed(format!(
+            "{:?} frame carries a {:?} message",
+            kind,
+            message.kind()
+        )));
+    }
+    Ok((message, FRAME_HEADER_LEN + consumed))
+}
+
+fn checksum(payload: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut out = [0u8; CHECKSUM_LEN];
+    out.copy_from_slice(&double_sha256(payload).as_bytes()[..CHECKSUM_LEN]);
+    out
+}
+
+fn malformed(reason: impl Into<String>) -> HorizError {
+    HorizError::Serialization(reason.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_crypto::PrivateKey;
+    use horizcoin_primitives::{Amount, Hash};
+    use horizcoin_tx::{TxInput, TxOutput};
+
+    use super::*;
+
+    fn samples() -> Vec<Message> {
+        let header = BlockHeader {
+            prev_block_id: BlockId::new([1; 32]),
+            merkle_root: Hash::new([2; 32]),
+            timestamp: 1_700_000_000,
+            height: 3,
+            difficulty: 1,
+            nonce: 4,
+        };
+        let tx = Transaction::new(
+            vec![TxInput::new(
+                TxId::new([5; 32]),
+                0,
+                PrivateKey::generate().public_key(),
+            )],
+            vec![TxOutput::new(Amount::new(10), "hz1payee")],
+            Some("hi".into()),
+        );
+        vec![
+            Message::Version {
+                protocol_version: PROTOCOL_VERSION,
+                best_height: 42,
+            },
+            Message::VerAck,
+            Message::Ping(7),
+            Message::Pong(7),
+            Message::GetHeaders {
+                locator: vec![BlockId::new([9; 32]), BlockId::ZERO],
+                stop: BlockId::ZERO,
+            },
+            Message::Headers(vec![header]),
+            Message::Inv(vec![TxId::new([6; 32]), TxId::new([8; 32])]),
+            Message::Tx(tx),
+        ]
+    }
+
+    #[test]
+    fn every_variant_round_trips() {
+        let messages = samples();
+        assert_eq!(messages.len(), MessageKind::ALL.len());
+        for message in messages {
+            let mut frame = encode_message(&message).unwrap();
+            assert_eq!(frame[..MAGIC.len()], MAGIC);
+            assert_eq!(frame[MAGIC.len()], message.kind().to_byte());
+
+            let len = frame.len();
+            frame.extend_from_slice(b"next frame");
+            assert_eq!(decode_message(&frame), Ok((message, len)));
+        }
+        assert_eq!(MessageKind::from_byte(8), None);
+    }
+
+    #[test]
+    fn rejects_corrupted_checksum() {
+        let mut frame = encode_message(&Message::Ping(1)).unwrap();
+        frame[MAGIC.len() + 1] ^= 0xff;
+        assert!(decode_message(&frame).is_err());
+
+        // A flipped payload bit is caught the same way.
+        let mut frame = encode_message(&Message::Ping(1)).unwrap();
+        *frame.last_mut().unwrap() ^= 1;
+        assert!(decode_message(&frame).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_framing() {
+        let frame = encode_message(&Message::Ping(1)).unwrap();
+        assert!(decode_message(&frame[..FRAME_HEADER_LEN - 1]).is_err());
+        assert!(decode_message(&frame[..frame.len() - 1]).is_err());
+
+        let mut foreign = frame.clone();
+        foreign[0] = b'X';
+        assert!(decode_message(&foreign).is_err());
+
+        let mut mislabelled = frame;
+        mislabelled[MAGIC.len()] = MessageKind::Pong.to_byte();
+        assert!(decode_message(&mislabelled).is_err());
+    }
+}
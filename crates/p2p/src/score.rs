@@ -0,0 +1,219 @@
+//! Per-peer rate limits and misbehavior scoring.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::{MessageKind, PeerId};
+
+/// Length of the window message and byte budgets are counted over.
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Limits a [`PeerScore`] enforces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScoreConfig {
+    /// Messages a peer may send per second.
+    pub max_messages_per_sec: u32,
+    /// Payload bytes a peer may send per second.
+    pub max_bytes_per_sec: usize,
+    /// Misbehavior points at which a peer is disconnected.
+    pub ban_threshold: u32,
+}
+
+impl Default for ScoreConfig {
+    fn default() -> Self {
+        Self {
+            max_messages_per_sec: 100,
+            max_bytes_per_sec: 1024 * 1024,
+            ban_threshold: 100,
+        }
+    }
+}
+
+/// Protocol violations and the points each one costs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Misbehavior {
+    /// A frame failed to decode.
+    Malformed,
+    /// A second `Version` on the same connection.
+    DuplicateVersion,
+    /// A message beyond the per-second message or byte budget.
+    RateExceeded,
+}
+
+impl Misbehavior {
+    /// Returns the points this violation adds.
+    #[must_use]
+    pub const fn points(self) -> u32 {
+        match self {
+            Self::Malformed => 100,
+            Self::DuplicateVersion => 20,
+            Self::RateExceeded => 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PeerState {
+    window_start: Instant,
+    messages: u32,
+    bytes: usize,
+    seen_version: bool,
+    points: u32,
+}
+
+/// Tracks what each peer sends and decides when to drop it.
+///
+/// Every message over the per-second budgets, and every reported
+/// [`Misbehavior`], adds points; a peer reaching
+/// [`ban_threshold`](ScoreConfig::ban_threshold) should be disconnected.
+#[derive(Debug, Clone, Default)]
+pub struct PeerScore {
+    config: ScoreConfig,
+    peers: HashMap<PeerId, PeerState>,
+}
+
+impl PeerScore {
+    /// Creates a tracker enforcing `config`.
+    #[must_use]
+    pub fn new(config: ScoreConfig) -> Self {
+        Self {
+            config,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Records a message of `kind` and `bytes` payload bytes from `peer`.
+    pub fn record_message(&mut self, peer: PeerId, kind: MessageKind, bytes: usize) {
+        self.record_message_at(peer, kind, bytes, Instant::now());
+    }
+
+    /// Like [`record_message`](Self::record_message), as if received at
+    /// `now`.
+    pub fn record_message_at(
+        &mut self,
+        peer: PeerId,
+        kind: MessageKind,
+        bytes: usize,
+        now: Instant,
+    ) {
+        let config = self.config;
+        let state = self.state(peer, now);
+        if now.duration_since(state.window_start) >= RATE_WINDOW {
+            state.window_start = now;
+            state.messages = 0;
+            state.bytes = 0;
+        }
+        state.messages = state.messages.saturating_add(1);
+        state.bytes = state.bytes.saturating_add(bytes);
+
+        let mut violations = Vec::new();
+        if state.messages > config.max_messages_per_sec || state.bytes > config.max_bytes_per_sec {
+            violations.push(Misbehavior::RateExceeded);
+        }
+        if kind == MessageKind::Version {
+            if state.seen_version {
+                violations.push(Misbehavior::DuplicateVersion);
+            }
+            state.seen_version = true;
+        }
+        for violation in violations {
+            self.report_at(peer, violation, now);
+        }
+    }
+
+    /// Adds the points for `violation` to `peer`.
+    pub fn report(&mut self, peer: PeerId, violation: Misbehavior) {
+        self.report_at(peer, violation, Instant::now());
+    }
+
+    /// Returns the misbehavior points `peer` has accumulated.
+    #[must_use]
+    pub fn points(&self, peer: PeerId) -> u32 {
+        self.peers.get(&peer).map_or(0, |state| state.points)
+    }
+
+    /// Returns `true` once `peer` has reached the ban threshold.
+    #[must_use]
+    pub fn should_disconnect(&self, peer: PeerId) -> bool {
+        self.points(peer) >= self.config.ban_threshold
+    }
+
+    /// Forgets `peer`, typically after it disconnects.
+    pub fn remove(&mut self, peer: PeerId) {
+        self.peers.remove(&peer);
+    }
+
+    fn report_at(&mut self, peer: PeerId, violation: Misbehavior, now: Instant) {
+        let state = self.state(peer, now);
+        state.points = state.points.saturating_add(violation.points());
+    }
+
+    fn state(&mut self, peer: PeerId, now: Instant) -> &mut PeerState {
+        self.peers.entry(peer).or_insert_with(|| PeerState {
+            window_start: now,
+            messages: 0,
+            bytes: 0,
+            seen_version: false,
+            points: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLOODER: PeerId = PeerId(1);
+    const POLITE: PeerId = PeerId(2);
+
+    #[test]
+    fn flooding_peer_is_disconnected() {
+        let mut score = PeerScore::default();
+        let start = Instant::now();
+        for _ in 0..200 {
+            score.record_message_at(FLOODER, MessageKind::Inv, 100, start);
+        }
+        assert!(score.should_disconnect(FLOODER));
+
+        // Byte floods count too.
+        let mut score = PeerScore::default();
+        for _ in 0..20 {
+            score.record_message_at(FLOODER, MessageKind::Tx, 1024 * 1024, start);
+        }
+        assert!(score.should_disconnect(FLOODER));
+    }
+
+    #[test]
+    fn well_behaved_peer_is_kept() {
+        let mut score = PeerScore::default();
+        let start = Instant::now();
+        score.record_message_at(POLITE, MessageKind::Version, 20, start);
+        for tick in 0..10_000u32 {
+            let now = start + Duration::from_millis(u64::from(tick) * 20);
+            score.record_message_at(POLITE, MessageKind::Ping, 8, now);
+        }
+        assert_eq!(score.points(POLITE), 0);
+        assert!(!score.should_disconnect(POLITE));
+    }
+
+    #[test]
+    fn violations_add_points() {
+        let mut score = PeerScore::new(ScoreConfig {
+            ban_threshold: 50,
+            ..ScoreConfig::default()
+        });
+        let start = Instant::now();
+        score.record_message_at(FLOODER, MessageKind::Version, 20, start);
+        score.record_message_at(FLOODER, MessageKind::Version, 20, start);
+        assert_eq!(
+            score.points(FLOODER),
+            Misbehavior::DuplicateVersion.points()
+        );
+        assert!(!score.should_disconnect(FLOODER));
+
+        score.report(FLOODER, Misbehavior::Malformed);
+        assert!(score.should_disconnect(FLOODER));
+        score.remove(FLOODER);
+        assert!(!score.should_disconnect(FLOODER));
+    }
+}
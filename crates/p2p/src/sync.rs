@@ -0,0 +1,432 @@
+//! Headers-first synchronization.
+
+use std::collections::HashMap;
+
+use horizcoin_block::BlockHeader;
+use horizcoin_consensus::{select_best_tip, ChainWork, Consensus, SealedHeader};
+use horizcoin_primitives::{BlockId, HorizError};
+
+/// Most entries [`HeaderSync::locator`] returns.
+pub const MAX_LOCATOR_LEN: usize = 32;
+
+/// Default for how far below the best tip a side branch may fork off.
+pub const DEFAULT_MAX_FORK_DEPTH: u64 = 1_000;
+
+/// Default cap on known headers that are not on the best chain.
+pub const DEFAULT_MAX_SIDE_HEADERS: usize = 10_000;
+
+/// A known header and the work of the chain ending in it.
+#[derive(Debug, Clone)]
+struct Known {
+    header: BlockHeader,
+    work: ChainWork,
+}
+
+/// Tracks every header received from peers and the best chain among them.
+///
+/// Headers are accepted only in batches that link, one after another, onto
+/// an already known header, so the tree never holds orphans, and each must
+/// pass the [`Consensus`] engine, so claimed difficulty is backed by work.
+/// The best chain is the one with the most [`ChainWork`].
+///
+/// Side branches are bounded: a batch may not fork off further than the
+/// maximum fork depth below the best tip, headers that fall that far behind
+/// are pruned, and only so many side headers are kept at once.
+#[derive(Debug, Clone)]
+pub struct HeaderSync {
+    known: HashMap<BlockId, Known>,
+    best_chain: Vec<BlockId>,
+    max_fork_depth: u64,
+    max_side_headers: usize,
+}
+
+impl HeaderSync {
+    /// Starts from `genesis`, the only header known up front.
+    pub fn new(genesis: BlockHeader) -> Result<Self, HorizError> {
+        let id = genesis.block_id()?;
+        let work = ChainWork::ZERO.with_block(genesis.difficulty);
+        let known = HashMap::from([(
+            id,
+            Known {
+                header: genesis,
+                work,
+            },
+        )]);
+        Ok(Self {
+            known,
+            best_chain: vec![id],
+            max_fork_depth: DEFAULT_MAX_FORK_DEPTH,
+            max_side_headers: DEFAULT_MAX_SIDE_HEADERS,
+        })
+    }
+
+    /// Accepts forks at most `max_fork_depth` blocks below the best tip and
+    /// keeps at most `max_side_headers` headers off the best chain.
+    #[must_use]
+    pub const fn with_fork_limits(mut self, max_fork_depth: u64, max_side_headers: usize) -> Self {
+        self.max_fork_depth = max_fork_depth;
+        self.max_side_headers = max_side_headers;
+        self
+    }
+
+    /// Returns the ids of the best chain, genesis first.
+    #[must_use]
+    pub fn best_chain(&self) -> &[BlockId] {
+        &self.best_chain
+    }
+
+    /// Returns the header `id`, if known.
+    #[must_use]
+    pub fn header(&self, id: &BlockId) -> Option<&BlockHeader> {
+        self.known.get(id).map(|known| &known.header)
+    }
+
+    /// Adds `headers`, which must be in chain order with the first one's
+    /// parent already known, and returns the resulting best chain.
+    ///
+    /// Each header is checked against its parent by `consensus` before it
+    /// counts towards any chain's work. The batch is checked as a whole
+    /// before anything is stored, so an orphan, out-of-order or invalid
+    /// header, or a fork outside the limits, leaves the tracker unchanged.
+    pub fn process_headers(
+        &mut self,
+        consensus: &dyn Consensus,
+        headers: Vec<SealedHeader>,
+    ) -> Result<Vec<BlockId>, HorizError> {
+        let best = self.best_chain[self.best_chain.len() - 1];
+        let best_height = self.known[&best].header.height;
+        let mut linked = Vec::with_capacity(headers.len());
+        for sealed in headers {
+            let id = sealed.header.block_id()?;
+            let parent = if let Some((parent_id, parent)) = linked.last() {
+                if sealed.header.prev_block_id != *parent_id {
+                    return Err(invalid(format!("{id} does not follow {parent_id}")));
+                }
+                parent
+            } else {
+                let parent = self
+                    .known
+                    .get(&sealed.header.prev_block_id)
+                    .ok_or_else(|| invalid(format!("parent of {id} is unknown")))?;
+                if parent.header.height.saturating_add(self.max_fork_depth) < best_height {
+                    return Err(invalid(format!(
+                        "{id} forks more than {} blocks below the tip",
+                        self.max_fork_depth
+                    )));
+                }
+                parent
+            };
+            if parent.header.height.checked_add(1) != Some(sealed.header.height) {
+                return Err(invalid(format!(
+                    "{id} claims height {} after {}",
+                    sealed.header.height, parent.header.height
+                )));
+            }
+            consensus.validate_block_header(&sealed, &parent.header)?;
+            let header = sealed.header;
+            let work = parent.work.with_block(header.difficulty);
+            linked.push((id, Known { header, work }));
+        }
+
+        let Some((tip, tip_work)) = linked.last().map(|(id, known)| (*id, known.work)) else {
+            return Ok(self.best_chain.clone());
+        };
+        let candidates = [(best, self.known[&best].work), (tip, tip_work)];
+        let takes_over = select_best_tip(&candidates) != Some(best);
+        if !takes_over {
+            let side = self.known.len() - self.best_chain.len();
+            let added = linked
+                .iter()
+                .filter(|(id, _)| !self.known.contains_key(id))
+                .count();
+            if side + added > self.max_side_headers {
+                return Err(invalid(format!(
+                    "side branches would exceed {} headers",
+                    self.max_side_headers
+                )));
+            }
+        }
+        self.known.extend(linked);
+        if takes_over {
+            self.best_chain = self.chain_to(tip);
+            self.prune_side_branches();
+        }
+        Ok(self.best_chain.clone())
+    }
+
+    /// Returns a block locator for the best chain: the tip and its recent
+    /// ancestors one by one, then exponentially sparser ones, ending with
+    /// genesis.
+    ///
+    /// A peer answers with headers following the first entry it knows.
+    #[must_use]
+    pub fn locator(&self) -> Vec<BlockId> {
+        let mut locator = Vec::new();
+        let mut index = self.best_chain.len() - 1;
+        let mut step = 1;
+        while index > 0 && locator.len() < MAX_LOCATOR_LEN - 1 {
+            locator.push(self.best_chain[index]);
+            if locator.len() >= 10 {
+                step *= 2;
+            }
+            index = index.saturating_sub(step);
+        }
+        locator.push(self.best_chain[0]);
+        locator
+    }
+
+    /// Forgets side branches that fork off more than the maximum fork depth
+    /// below the best tip; no batch could extend them any more. A branch goes
+    /// as a whole, so every kept header still links back to the best chain.
+    fn prune_side_branches(&mut self) {
+        let base_height = self.known[&self.best_chain[0]].header.height;
+        let tip_height = self.known[&self.best_chain[self.best_chain.len() - 1]]
+            .header
+            .height;
+        let on_best = |id: &BlockId, height: u64| {
+            usize::try_from(height - base_height)
+                .ok()
+                .and_then(|index| self.best_chain.get(index))
+                == Some(id)
+        };
+
+        let mut keep = HashMap::new();
+        for (id, known) in &self.known {
+            if on_best(id, known.header.height) {
+                continue;
+            }
+            // Walk down to the fork point, or to a header already decided.
+            let mut path = Vec::new();
+            let mut current = (*id, known);
+            let verdict = loop {
+                let (current_id, current_known) = current;
+                let height = current_known.header.height;
+                if on_best(&current_id, height) {
+                    break height.saturating_add(self.max_fork_depth) >= tip_height;
+                }
+                if let Some(&verdict) = keep.get(&current_id) {
+                    break verdict;
+                }
+                path.push(current_id);
+                let parent = current_known.header.prev_block_id;
+                current = (parent, &self.known[&parent]);
+            };
+            keep.extend(path.into_iter().map(|id| (id, verdict)));
+        }
+        self.known
+            .retain(|id, _| keep.get(id).copied().unwrap_or(true));
+    }
+
+    /// Returns the ids from genesis to `tip`, which must be known.
+    fn chain_to(&self, tip: BlockId) -> Vec<BlockId> {
+        let mut chain = vec![tip];
+        let mut current = &self.known[&tip].header;
+        while let Some(parent) = self.known.get(&current.prev_block_id) {
+            chain.push(current.prev_block_id);
+            current = &parent.header;
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> HorizError {
+    HorizError::InvalidBlock(reason.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_consensus::PowConsensus;
+    use horizcoin_primitives::Hash;
+
+    use super::*;
+
+    /// Difficulty 1 targets every hash, so the test headers need no mining.
+    const POW: PowConsensus = PowConsensus::new(1);
+
+    fn genesis() -> BlockHeader {
+        BlockHeader {
+            prev_block_id: BlockId::ZERO,
+            merkle_root: Hash::ZERO,
+            timestamp: 1_700_000_000,
+            height: 0,
+            difficulty: 1,
+            nonce: 0,
+        }
+    }
+
+    /// Returns `count` headers extending `parent`, tagged by `branch` so
+    /// sibling branches differ.
+    fn extend(parent: &BlockHeader, count: u64, branch: u8) -> Vec<BlockHeader> {
+        let mut headers: Vec<BlockHeader> = Vec::new();
+        for _ in 0..count {
+            let prev = headers.last().unwrap_or(parent);
+            headers.push(BlockHeader {
+                prev_block_id: prev.block_id().unwrap(),
+                merkle_root: Hash::new([branch; 32]),
+                timestamp: prev.timestamp + 60,
+                height: prev.height + 1,
+                difficulty: 1,
+                nonce: 0,
+            });
+        }
+        headers
+    }
+
+    fn sealed(headers: &[BlockHeader]) -> Vec<SealedHeader> {
+        headers
+            .iter()
+            .map(|header| SealedHeader {
+                header: header.clone(),
+                seal: Vec::new(),
+            })
+            .collect()
+    }
+
+    fn ids(headers: &[BlockHeader]) -> Vec<BlockId> {
+        headers
+            .iter()
+            .map(|header| header.block_id().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn accepts_linear_batch() {
+        let genesis = genesis();
+        let mut sync = HeaderSync::new(genesis.clone()).unwrap();
+        let batch = extend(&genesis, 5, 1);
+
+        let chain = sync.process_headers(&POW, sealed(&batch)).unwrap();
+        let mut expected = vec![genesis.block_id().unwrap()];
+        expected.extend(ids(&batch));
+        assert_eq!(chain, expected);
+        assert_eq!(sync.best_chain(), expected);
+        assert_eq!(sync.header(&expected[5]), Some(&batch[4]));
+
+        // Resending known headers changes nothing.
+        assert_eq!(
+            sync.process_headers(&POW, sealed(&batch)).unwrap(),
+            expected
+        );
+        assert_eq!(sync.process_headers(&POW, Vec::new()).unwrap(), expected);
+    }
+
+    #[test]
+    fn rejects_out_of_order_and_orphan_batches() {
+        let genesis = genesis();
+        let mut sync = HeaderSync::new(genesis.clone()).unwrap();
+        let batch = extend(&genesis, 4, 1);
+
+        let mut shuffled = batch.clone();
+        shuffled.swap(1, 2);
+        assert!(matches!(
+            sync.process_headers(&POW, sealed(&shuffled)),
+            Err(HorizError::InvalidBlock(_))
+        ));
+        assert!(sync.process_headers(&POW, sealed(&batch[2..])).is_err());
+        assert_eq!(sync.best_chain(), [genesis.block_id().unwrap()]);
+        assert!(sync.header(&batch[0].block_id().unwrap()).is_none());
+
+        let mut lying = batch[..1].to_vec();
+        lying[0].height = 5;
+        assert!(sync.process_headers(&POW, sealed(&lying)).is_err());
+    }
+
+    #[test]
+    fn heavier_fork_takes_over() {
+        let genesis = genesis();
+        let mut sync = HeaderSync::new(genesis.clone()).unwrap();
+        let main = extend(&genesis, 5, 1);
+        sync.process_headers(&POW, sealed(&main)).unwrap();
+
+        // A fork from height 2 that is still shorter leaves the best chain.
+        let fork = extend(&main[1], 4, 2);
+        let chain = sync.process_headers(&POW, sealed(&fork[..2])).unwrap();
+        assert_eq!(chain.last(), Some(&main[4].block_id().unwrap()));
+
+        let chain = sync.process_headers(&POW, sealed(&fork[2..])).unwrap();
+        let mut expected = vec![genesis.block_id().unwrap()];
+        expected.extend(ids(&main[..2]));
+        expected.extend(ids(&fork));
+        assert_eq!(chain, expected);
+    }
+
+    #[test]
+    fn unworked_difficulty_is_rejected() {
+        let genesis = genesis();
+        let mut sync = HeaderSync::new(genesis.clone()).unwrap();
+        let main = extend(&genesis, 3, 1);
+        sync.process_headers(&POW, sealed(&main)).unwrap();
+
+        // One header claiming enormous difficulty would outweigh the whole
+        // chain if its work were taken on trust.
+        let mut forged = extend(&genesis, 1, 2);
+        forged[0].difficulty = u32::MAX;
+        assert!(matches!(
+            sync.process_headers(&POW, sealed(&forged)),
+            Err(HorizError::InvalidBlock(_))
+        ));
+        assert_eq!(sync.best_chain().last(), Some(&main[2].block_id().unwrap()));
+        assert!(sync.header(&forged[0].block_id().unwrap()).is_none());
+
+        let mut with_seal = sealed(&extend(&main[2], 1, 1));
+        with_seal[0].seal = vec![1];
+        assert!(sync.process_headers(&POW, with_seal).is_err());
+    }
+
+    #[test]
+    fn side_branches_are_bounded() {
+        let genesis = genesis();
+        let mut sync = HeaderSync::new(genesis.clone())
+            .unwrap()
+            .with_fork_limits(4, 4);
+        let main = extend(&genesis, 6, 1);
+        sync.process_headers(&POW, sealed(&main)).unwrap();
+
+        // Forking off more than four blocks below the tip is refused.
+        assert!(sync
+            .process_headers(&POW, sealed(&extend(&main[0], 1, 2)))
+            .is_err());
+        let deep = extend(&main[1], 2, 2);
+        sync.process_headers(&POW, sealed(&deep)).unwrap();
+        let shallow = extend(&main[3], 1, 3);
+        sync.process_headers(&POW, sealed(&shallow)).unwrap();
+
+        // Only four headers may sit off the best chain.
+        assert!(sync
+            .process_headers(&POW, sealed(&extend(&main[2], 2, 4)))
+            .is_err());
+        assert_eq!(sync.known.len(), 7 + 3);
+
+        // Once the best chain moves on, branches forking too deep are
+        // forgotten whole while the best chain itself stays.
+        let chain = sync
+            .process_headers(&POW, sealed(&extend(&main[5], 2, 1)))
+            .unwrap();
+        assert_eq!(chain.len(), 9);
+        assert!(chain.iter().all(|id| sync.header(id).is_some()));
+        assert_eq!(sync.known.len(), 9 + 1);
+        assert!(sync.header(&shallow[0].block_id().unwrap()).is_some());
+        assert!(ids(&deep).iter().all(|id| sync.header(id).is_none()));
+    }
+
+    #[test]
+    fn locator_thins_out_towards_genesis() {
+        let genesis = genesis();
+        let mut sync = HeaderSync::new(genesis.clone()).unwrap();
+        assert_eq!(sync.locator(), [genesis.block_id().unwrap()]);
+
+        sync.process_headers(&POW, sealed(&extend(&genesis, 100, 1)))
+            .unwrap();
+        let locator = sync.locator();
+        let chain = sync.best_chain();
+        let heights: Vec<_> = locator
+            .iter()
+            .map(|id| chain.iter().position(|known| known == id).unwrap())
+            .collect();
+        assert_eq!(
+            heights,
+            [100, 99, 98, 97, 96, 95, 94, 93, 92, 91, 89, 85, 77, 61, 29, 0]
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Coin amounts in base units.
+//!
+//! [`Amount`] wraps a `u64` so amounts cannot be confused with heights,
+//! sizes or other integers, and so that arithmetic on them goes through
+//! checked operations that respect [`MAX_MONEY`]. It serializes exactly
+//! like the bare integer; [`Amount::to_decimal_string`] and
+//! [`Amount::from_decimal_string`] convert to and from whole coins with
+//! [`DECIMALS`] places for display.
+
+use alloc::format;
+use alloc::string::String;
+use core::{fmt, iter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{COIN, DECIMALS, MAX_MONEY};
+use crate::HorizError;
+
+/// A quantity of coins, counted in the smallest indivisible unit.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Amount(u64);
+
+impl Amount {
+    /// No coins.
+    pub const ZERO: Self = Self(0);
+
+    /// Wraps a count of base units.
+    #[must_use]
+    pub const fn new(units: u64) -> Self {
+        Self(units)
+    }
+
+    /// Returns the count of base units.
+    #[must_use]
+    pub const fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    /// Returns `self + rhs`, or [`HorizError::AmountOverflow`] if the sum
+    /// exceeds [`MAX_MONEY`].
+    pub const fn checked_add(self, rhs: Self) -> Result<Self, HorizError> {
+        match self.0.checked_add(rhs.0) {
+            Some(sum) if sum <= MAX_MONEY.0 => Ok(Self(sum)),
+            _ => Err(HorizError::AmountOverflow),
+        }
+    }
+
+    /// Returns `self - rhs`, or [`HorizError::InsufficientFunds`] if `rhs`
+    /// is the larger.
+    pub const fn checked_sub(self, rhs: Self) -> Result<Self, HorizError> {
+        match self.0.checked_sub(rhs.0) {
+            Some(difference) => Ok(Self(difference)),
+            None => Err(HorizError::InsufficientFunds {
+                required: rhs,
+                available: self,
+            }),
+        }
+    }
+
+    /// Returns `self + rhs`, clamped to [`MAX_MONEY`].
+    #[must_use]
+    pub const fn saturating_add(self, rhs: Self) -> Self {
+        match self.0.checked_add(rhs.0) {
+            Some(sum) if sum <= MAX_MONEY.0 => Self(sum),
+            _ => MAX_MONEY,
+        }
+    }
+
+    /// Formats the amount in whole coins with exactly [`DECIMALS`]
+    /// fractional digits, so `100000000` becomes `"1.00000000"`.
+    #[must_use]
+    pub fn to_decimal_string(&self) -> String {
+        format!(
+            "{}.{:0width$}",
+            self.0 / COIN,
+            self.0 % COIN,
+            width = DECIMALS as usize
+        )
+    }
+
+    /// Parses an amount in whole coins, such as `"1"`, `"0.5"` or
+    /// `"1.00000000"`.
+    ///
+    /// Accepts ASCII digits with an optional `.` followed by at most
+    /// [`DECIMALS`] digits. Fails with [`HorizError::Serialization`] on any
+    /// other input and with [`HorizError::AmountOverflow`] above
+    /// [`MAX_MONEY`].
+    pub fn from_decimal_string(s: &str) -> Result<Self, HorizError> {
+        let (whole, fraction) = match s.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (s, "0"),
+        };
+        if !is_digits(whole) || !is_digits(fraction) {
+            return Err(HorizError::Serialization(format!(
+                "invalid amount {s:?}: expected digits with an optional fractional part"
+            )));
+        }
+        if fraction.len() > DECIMALS as usize {
+            return Err(HorizError::Serialization(format!(
+                "invalid amount {s:?}: more than {DECIMALS} decimal places"
+            )));
+        }
+
+        // Right-pad the fraction with zeros to exactly DECIMALS digits.
+        let fraction = fraction
+            .bytes()
+            .chain(iter::repeat(b'0'))
+            .take(DECIMALS as usize)
+            .fold(0, |units, digit| units * 10 + u64::from(digit - b'0'));
+        let whole = whole
+            .parse::<u64>()
+            .ok()
+            .and_then(|whole| whole.checked_mul(COIN))
+            .ok_or(HorizError::AmountOverflow)?;
+        Self(whole).checked_add(Self(fraction))
+    }
+}
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn addition_is_capped_at_max_money() {
+        let one = Amount::new(1);
+        assert_eq!(
+            MAX_MONEY.checked_sub(one).unwrap().checked_add(one),
+            Ok(MAX_MONEY)
+        );
+        assert_eq!(MAX_MONEY.checked_add(one), Err(HorizError::AmountOverflow));
+        assert_eq!(MAX_MONEY.saturating_add(MAX_MONEY), MAX_MONEY);
+        assert_eq!(Amount::ZERO.saturating_add(one), one);
+    }
+
+    #[test]
+    fn addition_near_u64_max_does_not_wrap() {
+        let huge = Amount::new(u64::MAX - 1);
+        assert_eq!(
+            huge.checked_add(Amount::new(1)),
+            Err(HorizError::AmountOverflow)
+        );
+        assert_eq!(huge.checked_add(huge), Err(HorizError::AmountOverflow));
+        assert_eq!(huge.saturating_add(huge), MAX_MONEY);
+    }
+
+    #[test]
+    fn subtraction_reports_the_shortfall() {
+        let five = Amount::new(5);
+        let seven = Amount::new(7);
+        assert_eq!(seven.checked_sub(five), Ok(Amount::new(2)));
+        assert_eq!(five.checked_sub(five), Ok(Amount::ZERO));
+        assert_eq!(
+            five.checked_sub(seven),
+            Err(HorizError::InsufficientFunds {
+                required: seven,
+                available: five,
+            })
+        );
+    }
+
+    #[test]
+    fn decimal_round_trip() {
+        for (units, text) in [
+            (1, "0.00000001"),
+            (COIN, "1.00000000"),
+            (150 * COIN + 25, "150.00000025"),
+            (0, "0.00000000"),
+            (MAX_MONEY.as_u64(), "210240000.00000000"),
+        ] {
+            let amount = Amount::new(units);
+            assert_eq!(amount.to_decimal_string(), text);
+            assert_eq!(Amount::from_decimal_string(text), Ok(amount));
+        }
+
+        assert_eq!(Amount::from_decimal_string("1"), Ok(Amount::new(COIN)));
+        assert_eq!(
+            Amount::from_decimal_string("0.5"),
+            Ok(Amount::new(COIN / 2))
+        );
+    }
+
+    #[test]
+    fn decimal_parsing_is_strict() {
+        assert!(matches!(
+            Amount::from_decimal_string("0.000000001"),
+            Err(HorizError::Serialization(_))
+        ));
+        for bad in [
+            "", "abc", "1.2.3", "-1", "+1", " 1", "1.", ".5", "1e8", "1,5",
+        ] {
+            assert!(
+                matches!(
+                    Amount::from_decimal_string(bad),
+                    Err(HorizError::Serialization(_))
+                ),
+                "{bad:?} was accepted"
+            );
+        }
+        assert_eq!(
+            Amount::from_decimal_string("210240000.00000001"),
+            Err(HorizError::AmountOverflow)
+        );
+        assert_eq!(
+            Amount::from_decimal_string("99999999999999999999"),
+            Err(HorizError::AmountOverflow)
+        );
+    }
+
+    #[test]
+    fn serializes_as_a_bare_integer() {
+        let amount = Amount::new(1_500);
+        assert_eq!(serde_json::to_string(&amount).unwrap(), "1500");
+        assert_eq!(
+            bincode::serialize(&amount).unwrap(),
+            bincode::serialize(&1_500u64).unwrap()
+        );
+        assert_eq!(amount.to_string(), "1500");
+    }
+}
@@ -0,0 +1,42 @@
+//! Protocol-wide constants.
+
+use crate::Amount;
+
+/// Maximum length of a transaction memo, in `UTF-8` encoded bytes.
+pub const MEMO_MAX_LENGTH: usize = 128;
+
+/// Unix time in seconds of the genesis block: 2025-01-01T00:00:00Z.
+pub const GENESIS_TIMESTAMP: u64 = 1_735_689_600;
+
+/// Intended average time between blocks, in seconds.
+pub const TARGET_BLOCK_TIME: u64 = 60;
+
+/// How far, in seconds, a block's timestamp may run ahead of the
+/// validating node's clock.
+pub const MAX_FUTURE_BLOCK_TIME: u64 = 2 * 60 * 60;
+
+/// Number of preceding blocks whose median timestamp a new block must
+/// exceed.
+pub const MEDIAN_TIME_SPAN: usize = 11;
+
+/// Blocks that must follow a coinbase before its outputs can be spent.
+pub const COINBASE_MATURITY: u64 = 100;
+
+/// Maximum encoded size of a block, header included, in bytes.
+pub const MAX_BLOCK_SIZE: usize = 1_000_000;
+
+/// Maximum number of transactions in one block, coinbase included.
+pub const MAX_BLOCK_TRANSACTIONS: usize = 10_000;
+
+/// Number of decimal places shown for an [`Amount`] in whole coins.
+pub const DECIMALS: u32 = 8;
+
+/// Base units in one whole coin.
+pub const COIN: u64 = 10u64.pow(DECIMALS);
+
+/// Subsidy of a block before any halving, in base units.
+pub const INITIAL_BLOCK_REWARD: Amount = Amount::new(50 * COIN);
+
+/// Upper bound on any amount, in base units: the total the halving
+/// schedule can ever issue, rounded up to whole coins.
+pub const MAX_MONEY: Amount = Amount::new(210_240_000 * COIN);
@@ -0,0 +1,59 @@
+//! Error type shared by the `HorizCoin` workspace crates.
+
+use alloc::string::String;
+use core::fmt;
+
+use crate::Amount;
+
+/// Errors produced by `HorizCoin` components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HorizError {
+    /// A value could not be encoded or decoded.
+    Serialization(String),
+    /// A key, signature or other cryptographic value was invalid.
+    Crypto(String),
+    /// An address string could not be decoded.
+    InvalidAddress(String),
+    /// A transaction violated a structural or consensus rule.
+    InvalidTransaction(String),
+    /// The coins on hand cannot pay for what a transaction needs.
+    InsufficientFunds {
+        /// Total needed, fees included.
+        required: Amount,
+        /// Total of the coins that were available.
+        available: Amount,
+    },
+    /// An amount calculation exceeded [`MAX_MONEY`](crate::constants::MAX_MONEY).
+    AmountOverflow,
+    /// A block or block header violated a consensus rule.
+    InvalidBlock(String),
+    /// A Merkle tree operation was given invalid input.
+    Merkle(String),
+    /// A storage backend operation failed.
+    Storage(String),
+}
+
+impl fmt::Display for HorizError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Serialization(msg) => write!(f, "serialization error: {msg}"),
+            Self::Crypto(msg) => write!(f, "crypto error: {msg}"),
+            Self::InvalidAddress(msg) => write!(f, "invalid address: {msg}"),
+            Self::InvalidTransaction(msg) => write!(f, "invalid transaction: {msg}"),
+            Self::InsufficientFunds {
+                required,
+                available,
+            } => write!(
+                f,
+                "insufficient funds: {required} required, {available} available"
+            ),
+            Self::AmountOverflow => f.write_str("amount exceeds the maximum money supply"),
+            Self::InvalidBlock(msg) => write!(f, "invalid block: {msg}"),
+            Self::Merkle(msg) => write!(f, "merkle error: {msg}"),
+            Self::Storage(msg) => write!(f, "storage error: {msg}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HorizError {}
@@ -0,0 +1,212 @@
+//! Fixed-size 32-byte hash identifiers.
+//!
+//! [`Hash`], [`TxId`] and [`BlockId`] share one representation but are kept
+//! as distinct types so identifiers cannot be mixed up by accident.
+//!
+//! Serialization is format-aware: human-readable formats such as JSON see a
+//! lowercase hex string, while binary formats such as bincode keep the
+//! compact 32-byte layout used on the wire.
+
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::HorizError;
+
+/// Length in bytes of every hash identifier.
+pub const HASH_LEN: usize = 32;
+
+macro_rules! hash_type {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, core::hash::Hash)]
+        pub struct $name([u8; HASH_LEN]);
+
+        impl $name {
+            /// The all-zero value.
+            pub const ZERO: Self = Self([0u8; HASH_LEN]);
+
+            /// Wraps raw bytes.
+            #[must_use]
+            pub const fn new(bytes: [u8; HASH_LEN]) -> Self {
+                Self(bytes)
+            }
+
+            /// Returns the underlying bytes.
+            #[must_use]
+            pub const fn as_bytes(&self) -> &[u8; HASH_LEN] {
+                &self.0
+            }
+
+            /// Returns `true` if every byte is zero.
+            #[must_use]
+            pub fn is_zero(&self) -> bool {
+                self.0 == [0u8; HASH_LEN]
+            }
+
+            /// Encodes the value as a lowercase hex string.
+            #[must_use]
+            pub fn to_hex(&self) -> String {
+                hex::encode(self.0)
+            }
+
+            /// Parses a 64-character hex string.
+            pub fn from_hex(s: &str) -> Result<Self, HorizError> {
+                let mut bytes = [0u8; HASH_LEN];
+                hex::decode_to_slice(s, &mut bytes).map_err(|e| {
+                    HorizError::Serialization(format!(
+                        "invalid {} hex: {e}",
+                        stringify!($name)
+                    ))
+                })?;
+                Ok(Self(bytes))
+            }
+        }
+
+        impl From<[u8; HASH_LEN]> for $name {
+            fn from(bytes: [u8; HASH_LEN]) -> Self {
+                Self(bytes)
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.to_hex())
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}({})", stringify!($name), self.to_hex())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                if serializer.is_human_readable() {
+                    serializer.serialize_str(&self.to_hex())
+                } else {
+                    self.0.serialize(serializer)
+                }
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                if deserializer.is_human_readable() {
+                    let s = <Cow<'de, str>>::deserialize(deserializer)?;
+                    Self::from_hex(&s).map_err(de::Error::custom)
+                } else {
+                    <[u8; HASH_LEN]>::deserialize(deserializer).map(Self)
+                }
+            }
+        }
+    };
+}
+
+hash_type! {
+    /// A generic 32-byte hash digest.
+    Hash
+}
+
+hash_type! {
+    /// Identifier of a transaction.
+    TxId
+}
+
+hash_type! {
+    /// Identifier of a block.
+    BlockId
+}
+
+macro_rules! hash_conversions {
+    ($($name:ident),*) => {
+        $(
+            impl From<Hash> for $name {
+                fn from(hash: Hash) -> Self {
+                    Self(hash.0)
+                }
+            }
+
+            impl From<$name> for Hash {
+                fn from(id: $name) -> Self {
+                    Self(id.0)
+                }
+            }
+        )*
+    };
+}
+
+hash_conversions!(TxId, BlockId);
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record {
+        txid: TxId,
+        block: BlockId,
+    }
+
+    fn sample() -> Record {
+        let mut tx = [0u8; HASH_LEN];
+        tx[0] = 0xab;
+        tx[31] = 0x01;
+        Record {
+            txid: TxId::new(tx),
+            block: BlockId::new([0x11; HASH_LEN]),
+        }
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        let hash = Hash::new([0x5a; HASH_LEN]);
+        assert_eq!(Hash::from_hex(&hash.to_hex()).unwrap(), hash);
+        assert!(Hash::from_hex("zz").is_err());
+        assert!(Hash::from_hex(&"00".repeat(31)).is_err());
+    }
+
+    #[test]
+    fn json_uses_hex_strings() {
+        let record = sample();
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"txid":"{}","block":"{}"}}"#,
+                record.txid.to_hex(),
+                record.block.to_hex()
+            )
+        );
+        let decoded: Record = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, record);
+    }
+
+    #[test]
+    fn json_rejects_invalid_hex() {
+        let result: Result<TxId, _> = serde_json::from_str(r#""not-hex""#);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn bincode_keeps_compact_bytes() {
+        let record = sample();
+        let bytes = bincode::serialize(&record).unwrap();
+        assert_eq!(bytes.len(), 2 * HASH_LEN);
+        assert_eq!(&bytes[..HASH_LEN], record.txid.as_bytes());
+        let decoded: Record = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded, record);
+    }
+}
@@ -1,5 +1,23 @@
-//! HorizCoin primitives crate - placeholder for workspace compatibility
+//! Core primitive types for `HorizCoin`.
+//!
+//! This crate provides the fixed-size hash identifiers, amounts, protocol
+//! constants and the shared error type used across the `HorizCoin`
+//! workspace crates.
+//!
+//! Without the default `std` feature the crate is `no_std` and needs only
+//! `alloc`; [`HorizError`] then does not implement `std::error::Error`.
 
-pub fn placeholder() {
-    // Minimal placeholder function
-}
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod amount;
+pub mod constants;
+mod error;
+mod hash;
+mod validation;
+
+pub use amount::Amount;
+pub use error::HorizError;
+pub use hash::{BlockId, Hash, TxId, HASH_LEN};
+pub use validation::ValidationContext;
@@ -0,0 +1,52 @@
+//! Tunable limits applied during validation.
+
+use crate::constants::{
+    COINBASE_MATURITY, MAX_BLOCK_SIZE, MAX_BLOCK_TRANSACTIONS, MAX_FUTURE_BLOCK_TIME,
+    MEMO_MAX_LENGTH,
+};
+use crate::Amount;
+
+/// Limits consulted by the validation routines.
+///
+/// Passing the context explicitly lets tests and alternative networks use
+/// different limits without touching global constants. [`Default`] matches
+/// the protocol constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationContext {
+    /// Maximum memo length in bytes.
+    pub memo_max_length: usize,
+    /// Maximum number of inputs in one transaction.
+    pub max_inputs: usize,
+    /// Maximum number of outputs in one transaction.
+    pub max_outputs: usize,
+    /// Smallest amount an output may carry.
+    pub dust_threshold: Amount,
+    /// Blocks that must follow a coinbase before its outputs can be spent.
+    pub coinbase_maturity: u64,
+    /// Maximum encoded size of a block in bytes.
+    pub max_block_size: usize,
+    /// Maximum number of transactions in one block.
+    pub max_block_transactions: usize,
+    /// How far, in seconds, a block's timestamp may run ahead of the
+    /// validating node's clock.
+    pub max_future_block_time: u64,
+}
+
+impl Default for ValidationContext {
+    /// Protocol defaults: the memo limit from [`MEMO_MAX_LENGTH`], no cap on
+    /// input or output counts, only zero-value outputs treated as dust,
+    /// [`COINBASE_MATURITY`], the block limits [`MAX_BLOCK_SIZE`] and
+    /// [`MAX_BLOCK_TRANSACTIONS`], and [`MAX_FUTURE_BLOCK_TIME`].
+    fn default() -> Self {
+        Self {
+            memo_max_length: MEMO_MAX_LENGTH,
+            max_inputs: usize::MAX,
+            max_outputs: usize::MAX,
+            dust_threshold: Amount::new(1),
+            coinbase_maturity: COINBASE_MATURITY,
+            max_block_size: MAX_BLOCK_SIZE,
+            max_block_transactions: MAX_BLOCK_TRANSACTIONS,
+            max_future_block_time: MAX_FUTURE_BLOCK_TIME,
+        }
+    }
+}
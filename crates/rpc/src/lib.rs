@@ -2,11 +2,108 @@
 //!
 //! This crate provides JSON-RPC interface for external applications
 //! to interact with the `HorizCoin` blockchain.
+//!
+//! Requests and responses follow JSON-RPC 2.0 and are served over HTTP by
+//! [`router`]. Method names and the codes for rejected transactions follow
+//! the conventions of Bitcoin Core. Prometheus metrics are served at
+//! `/metrics`.
+
+mod metrics;
+mod server;
+
+pub use metrics::Metrics;
+pub use server::{router, router_with_metrics, ChainState, SharedChainState};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The request body is not valid JSON.
+pub const PARSE_ERROR: i64 = -32700;
+/// The body is JSON but not a JSON-RPC 2.0 request.
+pub const INVALID_REQUEST: i64 = -32600;
+/// No method has the requested name.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// The parameters do not fit the method.
+pub const INVALID_PARAMS: i64 = -32602;
+/// The node failed to carry out a valid request.
+pub const INTERNAL_ERROR: i64 = -32603;
+/// A submitted transaction could not be decoded.
+pub const DESERIALIZATION_ERROR: i64 = -22;
+/// A submitted transaction decoded but was rejected.
+pub const VERIFY_REJECTED: i64 = -26;
+
+/// The version string every request and response carries.
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// A JSON-RPC request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Request {
+    /// Always [`JSONRPC_VERSION`].
+    pub jsonrpc: String,
+    /// Name of the method to call.
+    pub method: String,
+    /// Positional parameters; absent means none.
+    #[serde(default)]
+    pub params: Value,
+    /// Identifier echoed in the response.
+    #[serde(default)]
+    pub id: Value,
+}
+
+/// A JSON-RPC response, carrying either a result or an error.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Response {
+    /// Always [`JSONRPC_VERSION`].
+    pub jsonrpc: String,
+    /// The method's result, if it succeeded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    /// Why the call failed, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+    /// The request's identifier, or `null` if it could not be read.
+    pub id: Value,
+}
+
+impl Response {
+    /// Creates a successful response to request `id`.
+    #[must_use]
+    pub fn success(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.into(),
+            result: Some(result),
+            error: None,
+            id,
+        }
+    }
+
+    /// Creates a failed response to request `id`.
+    #[must_use]
+    pub fn failure(id: Value, error: RpcError) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.into(),
+            result: None,
+            error: Some(error),
+            id,
+        }
+    }
+}
+
+/// The error member of a failed [`Response`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcError {
+    /// One of the error code constants of this crate.
+    pub code: i64,
+    /// Human-readable description.
+    pub message: String,
+}
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn placeholder_test() {
-        // Placeholder test to ensure the crate compiles
+impl RpcError {
+    /// Creates an error with `code` and `message`.
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
     }
 }
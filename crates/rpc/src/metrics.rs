@@ -0,0 +1,84 @@
+//! Prometheus metrics for the node's HTTP endpoint.
+
+use prometheus::{Encoder, IntCounter, IntGauge, Registry, TextEncoder};
+
+use crate::ChainState;
+
+/// Counters and gauges exposed at `/metrics`.
+///
+/// Chain gauges are refreshed from the [`ChainState`] on every scrape;
+/// the peer count is whatever was last set.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounter,
+    block_height: IntGauge,
+    mempool_size: IntGauge,
+    connected_peers: IntGauge,
+}
+
+impl Metrics {
+    /// Creates the metrics, all starting at zero.
+    #[must_use]
+    pub fn new() -> Self {
+        let registry = Registry::new();
+        let requests = IntCounter::new("horizcoin_rpc_requests_total", "JSON-RPC requests served.")
+            .expect("metric is well formed");
+        let block_height = IntGauge::new("horizcoin_block_height", "Height of the best block.")
+            .expect("metric is well formed");
+        let mempool_size = IntGauge::new(
+            "horizcoin_mempool_size",
+            "Transactions waiting to be mined.",
+        )
+        .expect("metric is well formed");
+        let connected_peers = IntGauge::new("horizcoin_connected_peers", "Peers connected.")
+            .expect("metric is well formed");
+        for metric in [&block_height, &mempool_size, &connected_peers] {
+            registry
+                .register(Box::new(metric.clone()))
+                .expect("metric names are unique");
+        }
+        registry
+            .register(Box::new(requests.clone()))
+            .expect("metric names are unique");
+        Self {
+            registry,
+            requests,
+            block_height,
+            mempool_size,
+            connected_peers,
+        }
+    }
+
+    /// Counts one served request.
+    pub fn record_request(&self) {
+        self.requests.inc();
+    }
+
+    /// Sets the number of connected peers.
+    pub fn set_connected_peers(&self, peers: usize) {
+        self.connected_peers
+            .set(i64::try_from(peers).unwrap_or(i64::MAX));
+    }
+
+    /// Refreshes the chain gauges from `chain` and returns every metric in
+    /// the Prometheus text exposition format.
+    #[must_use]
+    pub fn render(&self, chain: &ChainState) -> String {
+        self.block_height
+            .set(i64::try_from(chain.height()).unwrap_or(i64::MAX));
+        self.mempool_size
+            .set(i64::try_from(chain.pending().len()).unwrap_or(i64::MAX));
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("text encoding of gathered metrics succeeds");
+        String::from_utf8(buffer).expect("text exposition format is UTF-8")
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,382 @@
+//! The HTTP endpoint and the chain state it serves.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use horizcoin_block::Block;
+use horizcoin_primitives::{HorizError, TxId, ValidationContext};
+use horizcoin_tx::Transaction;
+use serde_json::{json, Value};
+
+use crate::{
+    Metrics, Request, Response, RpcError, DESERIALIZATION_ERROR, INTERNAL_ERROR, INVALID_PARAMS,
+    INVALID_REQUEST, JSONRPC_VERSION, METHOD_NOT_FOUND, PARSE_ERROR, VERIFY_REJECTED,
+};
+
+/// [`ChainState`] shared between request handlers.
+pub type SharedChainState = Arc<Mutex<ChainState>>;
+
+/// An in-memory chain and the transactions waiting to be mined.
+#[derive(Debug, Clone)]
+pub struct ChainState {
+    blocks: Vec<Block>,
+    pending: BTreeMap<TxId, Transaction>,
+    ctx: ValidationContext,
+}
+
+impl ChainState {
+    /// Creates a chain holding only `genesis`.
+    #[must_use]
+    pub fn new(genesis: Block) -> Self {
+        Self {
+            blocks: vec![genesis],
+            pending: BTreeMap::new(),
+            ctx: ValidationContext::default(),
+        }
+    }
+
+    /// Returns the best block.
+    #[must_use]
+    pub fn tip(&self) -> &Block {
+        self.blocks
+            .last()
+            .expect("the chain holds at least genesis")
+    }
+
+    /// Returns the height of the best block.
+    #[must_use]
+    pub fn height(&self) -> u64 {
+        self.tip().header.height
+    }
+
+    /// Appends `block`, which must extend the best block and pass
+    /// [`Block::validate`].
+    pub fn push_block(&mut self, block: Block) -> Result<(), HorizError> {
+        let tip = self.tip();
+        if block.header.prev_block_id != tip.block_id()?
+            || tip.header.height.checked_add(1) != Some(block.header.height)
+        {
+            return Err(HorizError::InvalidBlock(
+                "block does not extend the best block".into(),
+            ));
+        }
+        block.validate(&self.ctx)?;
+        for tx in &block.transactions {
+            self.pending.remove(&tx.txid()?);
+        }
+        self.blocks.push(block);
+        Ok(())
+    }
+
+    /// Returns the transactions waiting to be mined, by txid.
+    #[must_use]
+    pub const fn pending(&self) -> &BTreeMap<TxId, Transaction> {
+        &self.pending
+    }
+
+    /// Checks `tx` structurally and queues it for mining.
+    ///
+    /// Coinbases, transactions with dust outputs and transactions already
+    /// queued are rejected.
+    pub fn submit(&mut self, tx: Transaction) -> Result<TxId, HorizError> {
+        if tx.is_coinbase() {
+            return Err(HorizError::InvalidTransaction(
+                "coinbase transactions cannot be relayed".into(),
+            ));
+        }
+        tx.validate(&self.ctx)?;
+        tx.check_dust()?;
+        let txid = tx.txid()?;
+        if self.pending.contains_key(&txid) {
+            return Err(HorizError::InvalidTransaction(format!(
+                "{txid} is already pending"
+            )));
+        }
+        self.pending.insert(txid, tx);
+        Ok(txid)
+    }
+}
+
+/// Returns a router answering JSON-RPC 2.0 requests posted to `/` from
+/// `state`, and serving fresh [`Metrics`] at `/metrics`.
+///
+/// Supported methods are `getblockcount`, `getbestblockhash` and
+/// `sendrawtransaction`, which takes the hex of a codec-encoded
+/// transaction. Every response has status `200`; failures are reported in
+/// the response's `error` member.
+pub fn router(state: SharedChainState) -> Router {
+    router_with_metrics(state, Arc::new(Metrics::new()))
+}
+
+/// Like [`router`], but recording into `metrics`, so the caller can keep
+/// updating the gauges it owns.
+pub fn router_with_metrics(state: SharedChainState, metrics: Arc<Metrics>) -> Router {
+    Router::new()
+        .route("/", post(handle))
+        .route("/metrics", get(serve_metrics))
+        .with_state(AppState {
+            chain: state,
+            metrics,
+        })
+}
+
+#[derive(Clone)]
+struct AppState {
+    chain: SharedChainState,
+    metrics: Arc<Metrics>,
+}
+
+async fn handle(State(state): State<AppState>, body: Bytes) -> Json<Response> {
+    state.metrics.record_request();
+    Json(dispatch(&state.chain, &body))
+}
+
+async fn serve_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let body = {
+        let chain = state.chain.lock().unwrap_or_else(PoisonError::into_inner);
+        state.metrics.render(&chain)
+    };
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+fn dispatch(state: &Mutex<ChainState>, body: &[u8]) -> Response {
+    let Ok(value) = serde_json::from_slice::<Value>(body) else {
+        return Response::failure(Value::Null, RpcError::new(PARSE_ERROR, "parse error"));
+    };
+    let id = value.get("id").cloned().unwrap_or(Value::Null);
+    let request = match serde_json::from_value::<Request>(value) {
+        Ok(request) if request.jsonrpc == JSONRPC_VERSION => request,
+        _ => return Response::failure(id, RpcError::new(INVALID_REQUEST, "invalid request")),
+    };
+    match call(state, &request.method, &request.params) {
+        Ok(result) => Response::success(request.id, result),
+        Err(error) => Response::failure(request.id, error),
+    }
+}
+
+fn call(state: &Mutex<ChainState>, method: &str, params: &Value) -> Result<Value, RpcError> {
+    let mut chain = state.lock().unwrap_or_else(PoisonError::into_inner);
+    match method {
+        "getblockcount" => Ok(json!(chain.height())),
+        "getbestblockhash" => chain
+            .tip()
+            .block_id()
+            .map(|id| json!(id.to_hex()))
+            .map_err(|e| RpcError::new(INTERNAL_ERROR, e.to_string())),
+        "sendrawtransaction" => {
+            let tx = decode_raw_tx(params)?;
+            chain
+                .submit(tx)
+                .map(|txid| json!(txid.to_hex()))
+                .map_err(|e| RpcError::new(VERIFY_REJECTED, e.to_string()))
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("method {method:?} not found"),
+        )),
+    }
+}
+
+fn decode_raw_tx(params: &Value) -> Result<Transaction, RpcError> {
+    let hex_tx = params
+        .get(0)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, "expected [hex transaction]"))?;
+    let bytes = hex::decode(hex_tx)
+        .map_err(|e| RpcError::new(DESERIALIZATION_ERROR, format!("invalid hex: {e}")))?;
+    horizcoin_codec::decode_exact(&bytes)
+        .map_err(|e: HorizError| RpcError::new(DESERIALIZATION_ERROR, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request as HttpRequest, StatusCode};
+    use horizcoin_block::BlockHeader;
+    use horizcoin_crypto::PrivateKey;
+    use horizcoin_primitives::{Amount, BlockId, Hash};
+    use horizcoin_tx::{TxInput, TxOutput};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn genesis() -> Block {
+        let header = BlockHeader {
+            prev_block_id: BlockId::ZERO,
+            merkle_root: Hash::ZERO,
+            timestamp: 1_700_000_000,
+            height: 0,
+            difficulty: 1,
+            nonce: 0,
+        };
+        let coinbase =
+            Transaction::coinbase(vec![TxOutput::new(Amount::new(50), "hz1miner")], None);
+        Block::new(header, vec![coinbase]).unwrap()
+    }
+
+    fn signed_tx() -> Transaction {
+        paying(1_000)
+    }
+
+    fn paying(amount: u64) -> Transaction {
+        let key = PrivateKey::generate();
+        let mut tx = Transaction::new(
+            vec![TxInput::new(Hash::new([1; 32]).into(), 0, key.public_key())],
+            vec![TxOutput::new(Amount::new(amount), "hz1payee")],
+            None,
+        );
+        tx.sign_input(0, &key).unwrap();
+        tx
+    }
+
+    async fn post(state: &SharedChainState, body: impl Into<String>) -> Response {
+        let request = HttpRequest::post("/")
+            .header("content-type", "application/json")
+            .body(Body::from(body.into()))
+            .unwrap();
+        let response = router(state.clone()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    fn rpc(method: &str, params: &Value) -> String {
+        json!({ "jsonrpc": "2.0", "id": 7, "method": method, "params": params }).to_string()
+    }
+
+    #[tokio::test]
+    async fn serves_prometheus_metrics() {
+        let state = Arc::new(Mutex::new(ChainState::new(genesis())));
+        let metrics = Arc::new(Metrics::new());
+        let app = router_with_metrics(state.clone(), metrics.clone());
+        metrics.set_connected_peers(3);
+
+        let raw = hex::encode(horizcoin_codec::encode(&signed_tx()).unwrap());
+        for body in [
+            rpc("getblockcount", &json!([])),
+            rpc("sendrawtransaction", &json!([raw])),
+        ] {
+            let request = HttpRequest::post("/").body(Body::from(body)).unwrap();
+            app.clone().oneshot(request).await.unwrap();
+        }
+
+        let request = HttpRequest::get("/metrics").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain"));
+        let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        for expected in [
+            "# TYPE horizcoin_rpc_requests_total counter",
+            "horizcoin_rpc_requests_total 2",
+            "# TYPE horizcoin_block_height gauge",
+            "horizcoin_block_height 0",
+            "horizcoin_mempool_size 1",
+            "# HELP horizcoin_connected_peers Peers connected.",
+            "horizcoin_connected_peers 3",
+        ] {
+            assert!(
+                text.lines().any(|line| line == expected),
+                "missing {expected:?} in\n{text}"
+            );
+        }
+    }
+
+    fn error_code(response: &Response) -> i64 {
+        assert!(response.result.is_none());
+        response.error.as_ref().expect("an error").code
+    }
+
+    #[tokio::test]
+    async fn reports_the_best_block() {
+        let genesis = genesis();
+        let state = Arc::new(Mutex::new(ChainState::new(genesis.clone())));
+
+        let response = post(&state, rpc("getblockcount", &json!([]))).await;
+        assert_eq!(response, Response::success(json!(7), json!(0)));
+
+        let response = post(&state, rpc("getbestblockhash", &Value::Null)).await;
+        let expected = genesis.block_id().unwrap().to_hex();
+        assert_eq!(response.result, Some(json!(expected)));
+
+        let header = BlockHeader {
+            prev_block_id: genesis.block_id().unwrap(),
+            height: 1,
+            ..genesis.header.clone()
+        };
+        let next = Block::new(header, genesis.transactions).unwrap();
+        state.lock().unwrap().push_block(next).unwrap();
+        let response = post(&state, rpc("getblockcount", &json!([]))).await;
+        assert_eq!(response.result, Some(json!(1)));
+    }
+
+    #[tokio::test]
+    async fn accepts_a_valid_transaction() {
+        let state = Arc::new(Mutex::new(ChainState::new(genesis())));
+        let tx = signed_tx();
+        let raw = hex::encode(horizcoin_codec::encode(&tx).unwrap());
+
+        let response = post(&state, rpc("sendrawtransaction", &json!([raw]))).await;
+        assert_eq!(response.result, Some(json!(tx.txid().unwrap().to_hex())));
+        assert_eq!(response.id, json!(7));
+        assert_eq!(state.lock().unwrap().pending().len(), 1);
+
+        let again = post(&state, rpc("sendrawtransaction", &json!([raw]))).await;
+        assert_eq!(error_code(&again), VERIFY_REJECTED);
+    }
+
+    #[tokio::test]
+    async fn relay_policy_rejects_dust() {
+        let state = Arc::new(Mutex::new(ChainState::new(genesis())));
+        let threshold = horizcoin_tx::DUST_THRESHOLD.as_u64();
+
+        let dust = hex::encode(horizcoin_codec::encode(&paying(threshold - 1)).unwrap());
+        let rejected = post(&state, rpc("sendrawtransaction", &json!([dust]))).await;
+        assert_eq!(error_code(&rejected), VERIFY_REJECTED);
+        assert!(rejected.error.unwrap().message.contains("dust threshold"));
+        assert!(state.lock().unwrap().pending().is_empty());
+
+        let tx = paying(threshold);
+        let raw = hex::encode(horizcoin_codec::encode(&tx).unwrap());
+        let accepted = post(&state, rpc("sendrawtransaction", &json!([raw]))).await;
+        assert_eq!(accepted.result, Some(json!(tx.txid().unwrap().to_hex())));
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_transactions_and_requests() {
+        let state = Arc::new(Mutex::new(ChainState::new(genesis())));
+
+        let garbage = post(&state, rpc("sendrawtransaction", &json!(["zz"]))).await;
+        assert_eq!(error_code(&garbage), DESERIALIZATION_ERROR);
+        let truncated = post(&state, rpc("sendrawtransaction", &json!(["0102"]))).await;
+        assert_eq!(error_code(&truncated), DESERIALIZATION_ERROR);
+
+        let mut invalid = signed_tx();
+        invalid.outputs.clear();
+        let raw = hex::encode(horizcoin_codec::encode(&invalid).unwrap());
+        let rejected = post(&state, rpc("sendrawtransaction", &json!([raw]))).await;
+        assert_eq!(error_code(&rejected), VERIFY_REJECTED);
+        assert!(rejected.error.unwrap().message.contains("no outputs"));
+        assert!(state.lock().unwrap().pending().is_empty());
+
+        let missing = post(&state, rpc("sendrawtransaction", &json!([]))).await;
+        assert_eq!(error_code(&missing), INVALID_PARAMS);
+        let unknown = post(&state, rpc("getbalance", &json!([]))).await;
+        assert_eq!(error_code(&unknown), METHOD_NOT_FOUND);
+        let not_rpc = post(&state, r#"{"id": 1, "method": "getblockcount"}"#).await;
+        assert_eq!(error_code(&not_rpc), INVALID_REQUEST);
+        assert_eq!(not_rpc.id, json!(1));
+        let not_json = post(&state, "{").await;
+        assert_eq!(error_code(&not_json), PARSE_ERROR);
+        assert_eq!(not_json.id, Value::Null);
+    }
+}
@@ -3,10 +3,8 @@
 //! This crate provides `UTXO` set management with apply/rollback capabilities
 //! for the `HorizCoin` blockchain.
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn placeholder_test() {
-        // Placeholder test to ensure the crate compiles
-    }
-}
+mod tip;
+mod utxo;
+
+pub use tip::{ChainTip, CHAIN_TIP_KEY};
+pub use utxo::{Coin, UndoData, UtxoSet, ADDRESS_INDEX_CF, UTXO_CF};
@@ -0,0 +1,154 @@
+//! The persisted pointer to the best block.
+
+use std::sync::Arc;
+
+use horizcoin_primitives::{BlockId, HorizError};
+use horizcoin_storage::Storage;
+
+/// Key, in the default column family, holding the encoded tip.
+pub const CHAIN_TIP_KEY: &[u8] = b"chain_tip";
+
+/// The id and height of the best block, kept in a [`Storage`].
+///
+/// The tip is a single value replaced with
+/// [`Storage::compare_and_swap`], so a crash leaves either the old or the
+/// new tip, never a mix, and a writer working from an outdated tip is
+/// refused instead of silently overwriting a newer one.
+#[derive(Clone)]
+pub struct ChainTip {
+    storage: Arc<dyn Storage>,
+}
+
+impl std::fmt::Debug for ChainTip {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChainTip").finish_non_exhaustive()
+    }
+}
+
+impl ChainTip {
+    /// Opens the tip kept in `storage`.
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Returns the stored `(block id, height)`, or `None` before the first
+    /// [`advance`](Self::advance).
+    pub fn get_tip(&self) -> Result<Option<(BlockId, u64)>, HorizError> {
+        self.storage
+            .get(CHAIN_TIP_KEY)?
+            .map(|bytes| horizcoin_codec::decode_exact(&bytes))
+            .transpose()
+    }
+
+    /// Moves the tip from `from` to `to` at `height`.
+    ///
+    /// `from` must be the current tip, or [`BlockId::ZERO`] to set the
+    /// first one. Fails without writing if the stored tip is something
+    /// else, including when another writer moves it between the read and
+    /// the swap.
+    pub fn advance(&self, from: BlockId, to: BlockId, height: u64) -> Result<(), HorizError> {
+        let current = self.storage.get(CHAIN_TIP_KEY)?;
+        let current_id = match &current {
+            Some(bytes) => horizcoin_codec::decode_exact::<(BlockId, u64)>(bytes)?.0,
+            None => BlockId::ZERO,
+        };
+        if current_id != from {
+            return Err(HorizError::Storage(format!(
+                "chain tip is {current_id}, not {from}"
+            )));
+        }
+
+        let new = horizcoin_codec::encode(&(to, height))?;
+        if self
+            .storage
+            .compare_and_swap(CHAIN_TIP_KEY, current.as_deref(), &new)?
+        {
+            Ok(())
+        } else {
+            Err(HorizError::Storage(format!(
+                "chain tip moved away from {from} concurrently"
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_storage::MemoryStorage;
+
+    use super::*;
+
+    fn id(byte: u8) -> BlockId {
+        BlockId::new([byte; 32])
+    }
+
+    #[test]
+    fn advances_from_the_current_tip() {
+        let tip = ChainTip::new(Arc::new(MemoryStorage::new()));
+        assert_eq!(tip.get_tip(), Ok(None));
+
+        tip.advance(BlockId::ZERO, id(1), 0).unwrap();
+        assert_eq!(tip.get_tip(), Ok(Some((id(1), 0))));
+        tip.advance(id(1), id(2), 1).unwrap();
+        assert_eq!(tip.get_tip(), Ok(Some((id(2), 1))));
+    }
+
+    #[test]
+    fn stale_from_is_rejected() {
+        let tip = ChainTip::new(Arc::new(MemoryStorage::new()));
+        assert!(tip.advance(id(1), id(2), 1).is_err());
+        assert_eq!(tip.get_tip(), Ok(None));
+
+        tip.advance(BlockId::ZERO, id(1), 0).unwrap();
+        tip.advance(id(1), id(2), 1).unwrap();
+        assert_eq!(
+            tip.advance(id(1), id(3), 1),
+            Err(HorizError::Storage(format!(
+                "chain tip is {}, not {}",
+                id(2),
+                id(1)
+            )))
+        );
+        assert!(tip.advance(BlockId::ZERO, id(3), 0).is_err());
+        assert_eq!(tip.get_tip(), Ok(Some((id(2), 1))));
+    }
+
+    #[test]
+    fn only_one_concurrent_advance_wins() {
+        let tip = ChainTip::new(Arc::new(MemoryStorage::new()));
+        tip.advance(BlockId::ZERO, id(1), 0).unwrap();
+
+        let wins: usize = std::thread::scope(|scope| {
+            let mut writers = Vec::new();
+            for byte in 2..10u8 {
+                let tip = tip.clone();
+                writers.push(scope.spawn(move || tip.advance(id(1), id(byte), 1).is_ok()));
+            }
+            writers
+                .into_iter()
+                .map(|w| usize::from(w.join().unwrap()))
+                .sum()
+        });
+        assert_eq!(wins, 1);
+        assert_eq!(tip.get_tip().unwrap().unwrap().1, 1);
+    }
+
+    #[test]
+    fn tip_survives_reopening() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let tip = ChainTip::new(Arc::clone(&storage));
+        tip.advance(BlockId::ZERO, id(1), 0).unwrap();
+        tip.advance(id(1), id(2), 1).unwrap();
+        drop(tip);
+
+        // Simulate a restart by copying the store into a fresh one.
+        let restarted: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        storage.checkpoint_into(restarted.as_ref()).unwrap();
+        drop(storage);
+
+        let reopened = ChainTip::new(restarted);
+        assert_eq!(reopened.get_tip(), Ok(Some((id(2), 1))));
+        reopened.advance(id(2), id(3), 2).unwrap();
+        assert_eq!(reopened.get_tip(), Ok(Some((id(3), 2))));
+    }
+}
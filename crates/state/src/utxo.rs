@@ -0,0 +1,717 @@
+//! The set of unspent transaction outputs.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use horizcoin_block::Block;
+use horizcoin_primitives::{Amount, Hash, HorizError, TxId, ValidationContext, HASH_LEN};
+use horizcoin_storage::{Storage, WriteBatch};
+use horizcoin_tx::{Transaction, TxOutput};
+use serde::{Deserialize, Serialize};
+
+/// Column family holding unspent outputs.
+pub const UTXO_CF: &str = "utxos";
+
+/// Column family indexing unspent outputs by recipient address.
+///
+/// Keys are the big-endian address length, the address and the outpoint
+/// key; values are the encoded amount. Multisig outputs, which have no
+/// address, are not indexed.
+pub const ADDRESS_INDEX_CF: &str = "utxos_by_address";
+
+/// Length of a key: the transaction id then the big-endian output index,
+/// so keys sort by outpoint.
+const KEY_LEN: usize = HASH_LEN + 4;
+
+/// An unspent output and where it was created.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Coin {
+    /// The output itself.
+    pub output: TxOutput,
+    /// Height of the block that created it.
+    pub height: u64,
+    /// Whether a coinbase created it, making it subject to maturity.
+    pub is_coinbase: bool,
+}
+
+/// What [`UtxoSet::disconnect_block`] needs to revert a block: the coins
+/// each of its transactions spent, in block and input order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UndoData {
+    /// One list per transaction; empty for the coinbase.
+    pub spent: Vec<Vec<Coin>>,
+}
+
+/// Unspent outputs, keyed by outpoint, kept in a [`Storage`].
+///
+/// Every change is written as one batch, so a failed or interrupted update
+/// leaves the set as it was.
+#[derive(Clone)]
+pub struct UtxoSet {
+    storage: Arc<dyn Storage>,
+    ctx: ValidationContext,
+}
+
+impl std::fmt::Debug for UtxoSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UtxoSet").finish_non_exhaustive()
+    }
+}
+
+impl UtxoSet {
+    /// Opens the set kept in `storage`, validating blocks against the
+    /// default [`ValidationContext`].
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self::with_context(storage, ValidationContext::default())
+    }
+
+    /// Opens the set kept in `storage`, validating blocks against `ctx`.
+    pub fn with_context(storage: Arc<dyn Storage>, ctx: ValidationContext) -> Self {
+        Self { storage, ctx }
+    }
+
+    /// Returns the unspent output `index` of `txid`.
+    pub fn get(&self, txid: &TxId, index: u32) -> Result<Option<TxOutput>, HorizError> {
+        Ok(self.get_coin(txid, index)?.map(|coin| coin.output))
+    }
+
+    /// Returns the unspent output `index` of `txid` with its origin.
+    pub fn get_coin(&self, txid: &TxId, index: u32) -> Result<Option<Coin>, HorizError> {
+        self.load(&outpoint_key(txid, index))
+    }
+
+    /// Returns the total unspent amount paid to `address`.
+    pub fn balance_of(&self, address: &str) -> Result<Amount, HorizError> {
+        self.utxos_of(address)?
+            .into_iter()
+            .try_fold(Amount::ZERO, |total, (_, _, amount)| {
+                total.checked_add(amount)
+            })
+    }
+
+    /// Returns the unspent outputs paid to `address` as `(txid, index,
+    /// amount)`, in outpoint order.
+    pub fn utxos_of(&self, address: &str) -> Result<Vec<(TxId, u32, Amount)>, HorizError> {
+        let prefix = address_prefix(address)?;
+        self.storage
+            .scan_prefix_cf(ADDRESS_INDEX_CF, &prefix)?
+            .into_iter()
+            .map(|(key, value)| {
+                let outpoint = key
+                    .get(prefix.len()..)
+                    .filter(|outpoint| outpoint.len() == KEY_LEN)
+                    .ok_or_else(|| HorizError::Storage("malformed address index key".into()))?;
+                let (txid, index) = outpoint.split_at(HASH_LEN);
+                let mut txid_bytes = [0; HASH_LEN];
+                txid_bytes.copy_from_slice(txid);
+                let mut index_bytes = [0; 4];
+                index_bytes.copy_from_slice(index);
+                Ok((
+                    TxId::new(txid_bytes),
+                    u32::from_be_bytes(index_bytes),
+                    horizcoin_codec::decode_exact(&value)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Returns the root committing to every unspent output.
+    ///
+    /// The root is the Merkle root of one leaf per output, in outpoint
+    /// order, each hashing the outpoint key and the encoded output. It
+    /// depends only on which outputs are unspent, not on how the set got
+    /// there, and is [`Hash::ZERO`] for an empty set.
+    pub fn state_root(&self) -> Result<Hash, HorizError> {
+        let mut entries = self.storage.scan_prefix_cf(UTXO_CF, b"")?;
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        let leaves = entries
+            .into_iter()
+            .map(|(key, value)| {
+                let coin: Coin = horizcoin_codec::decode_exact(&value)?;
+                let mut leaf = key;
+                leaf.extend_from_slice(&horizcoin_codec::encode(&coin.output)?);
+                Ok(horizcoin_crypto::sha256(&leaf))
+            })
+            .collect::<Result<Vec<_>, HorizError>>()?;
+        Ok(horizcoin_merkle::compute_merkle_root(&leaves))
+    }
+
+    /// Spends the outputs `tx` consumes and adds the ones it creates.
+    ///
+    /// Fails, changing nothing, if an input names an output that does not
+    /// exist or was already spent, or if one of the new outputs already
+    /// exists. A coinbase spends nothing. Signatures and maturity are not
+    /// checked and new outputs are recorded at height `0`; use
+    /// [`connect_block`](Self::connect_block) for chain updates.
+    ///
+    /// Returns the coins `tx` spent, in input order.
+    pub fn apply(&mut self, tx: &Transaction) -> Result<Vec<Coin>, HorizError> {
+        let mut pending = Pending::default();
+        let spent = self.stage_apply(tx, 0, &mut pending)?;
+        self.commit(pending)?;
+        Ok(spent)
+    }
+
+    /// Reverts [`apply`](Self::apply): removes the outputs `tx` created and
+    /// restores `spent`, the coins it returned.
+    pub fn undo(&mut self, tx: &Transaction, spent: Vec<Coin>) -> Result<(), HorizError> {
+        let mut pending = Pending::default();
+        self.stage_undo(tx, spent, &mut pending)?;
+        self.commit(pending)
+    }
+
+    /// Applies every transaction of `block` in one batch and returns what
+    /// is needed to revert it.
+    ///
+    /// The block must pass [`Block::validate`] and start with its only
+    /// coinbase. Every other transaction must pass
+    /// [`Transaction::validate`], spend existing mature outputs it is
+    /// authorized to spend, and not pay out more than it spends. Outputs
+    /// created earlier in the same block may be spent. On any failure the
+    /// set is unchanged.
+    pub fn connect_block(&mut self, block: &Block) -> Result<UndoData, HorizError> {
+        block.validate(&self.ctx)?;
+        match block.transactions.split_first() {
+            Some((coinbase, rest))
+                if coinbase.is_coinbase() && !rest.iter().any(Transaction::is_coinbase) => {}
+            _ => {
+                return Err(HorizError::InvalidBlock(
+                    "block must start with its only coinbase".into(),
+                ))
+            }
+        }
+
+        let height = block.header.height;
+        let mut pending = Pending::default();
+        let mut undo = UndoData::default();
+        for tx in &block.transactions {
+            tx.validate(&self.ctx)?;
+            if !tx.is_coinbase() {
+                self.check_spend(tx, height, &pending)?;
+            }
+            undo.spent.push(self.stage_apply(tx, height, &mut pending)?);
+        }
+        self.commit(pending)?;
+        Ok(undo)
+    }
+
+    /// Reverts `block`, the last block connected, using the `undo` data
+    /// [`connect_block`](Self::connect_block) returned for it, in one batch.
+    pub fn disconnect_block(&mut self, block: &Block, undo: &UndoData) -> Result<(), HorizError> {
+        if undo.spent.len() != block.transactions.len() {
+            return Err(HorizError::InvalidBlock(format!(
+                "undo data covers {} of {} transactions",
+                undo.spent.len(),
+                block.transactions.len()
+            )));
+        }
+        let mut pending = Pending::default();
+        for (tx, coins) in block.transactions.iter().zip(&undo.spent).rev() {
+            self.stage_undo(tx, coins.clone(), &mut pending)?;
+        }
+        self.commit(pending)
+    }
+
+    /// Checks that `tx`, mined at `height`, may spend its inputs: they are
+    /// mature, signed for, and cover its outputs.
+    fn check_spend(
+        &self,
+        tx: &Transaction,
+        height: u64,
+        pending: &Pending,
+    ) -> Result<(), HorizError> {
+        // Read every spent coin up front so storage and decoding errors
+        // surface as such rather than as missing outputs.
+        let mut spent = BTreeMap::new();
+        for input in &tx.inputs {
+            let key = outpoint_key(&input.prev_tx, input.output_index);
+            if let Some(coin) = self.lookup(&key, pending)? {
+                let mature_at = coin.height.saturating_add(self.ctx.coinbase_maturity);
+                if coin.is_coinbase && height < mature_at {
+                    return Err(invalid(format!(
+                        "coinbase output {}:{} is immature until height {mature_at}",
+                        input.prev_tx, input.output_index
+                    )));
+                }
+                spent.insert((input.prev_tx, input.output_index), coin.output);
+            }
+        }
+        let lookup = |txid: &TxId, index: u32| spent.get(&(*txid, index)).cloned();
+        tx.verify_against_utxos(lookup)?;
+        tx.fee(lookup)?;
+        Ok(())
+    }
+
+    /// Records in `pending` the effect of applying `tx`, mined at `height`,
+    /// on top of it, and returns the coins `tx` spends.
+    fn stage_apply(
+        &self,
+        tx: &Transaction,
+        height: u64,
+        pending: &mut Pending,
+    ) -> Result<Vec<Coin>, HorizError> {
+        let mut spent = Vec::new();
+        if !tx.is_coinbase() {
+            for input in &tx.inputs {
+                let key = outpoint_key(&input.prev_tx, input.output_index);
+                let coin = self.lookup(&key, pending)?.ok_or_else(|| {
+                    invalid(format!(
+                        "{}:{} is missing or already spent",
+                        input.prev_tx, input.output_index
+                    ))
+                })?;
+                pending.changes.insert(key, None);
+                spent.push(coin);
+            }
+        }
+
+        let txid = tx.txid()?;
+        for (index, output) in (0u32..).zip(&tx.outputs) {
+            let key = outpoint_key(&txid, index);
+            if self.lookup(&key, pending)?.is_some() {
+                return Err(invalid(format!("{txid}:{index} already exists")));
+            }
+            let coin = Coin {
+                output: output.clone(),
+                height,
+                is_coinbase: tx.is_coinbase(),
+            };
+            pending.changes.insert(key, Some(coin));
+        }
+        Ok(spent)
+    }
+
+    /// Records in `pending` the reversal of `tx`, which spent `spent`.
+    fn stage_undo(
+        &self,
+        tx: &Transaction,
+        spent: Vec<Coin>,
+        pending: &mut Pending,
+    ) -> Result<(), HorizError> {
+        let spends = if tx.is_coinbase() { 0 } else { tx.inputs.len() };
+        if spent.len() != spends {
+            return Err(invalid(format!(
+                "{} spent outputs given for {spends} inputs",
+                spent.len()
+            )));
+        }
+
+        let txid = tx.txid()?;
+        for index in (0u32..).take(tx.outputs.len()) {
+            let key = outpoint_key(&txid, index);
+            if self.lookup(&key, pending)?.is_none() {
+                return Err(invalid(format!("{txid}:{index} is not unspent")));
+            }
+            pending.changes.insert(key, None);
+        }
+        for (input, coin) in tx.inputs.iter().zip(spent) {
+            pending
+                .changes
+                .insert(outpoint_key(&input.prev_tx, input.output_index), Some(coin));
+        }
+        Ok(())
+    }
+
+    fn lookup(&self, key: &[u8], pending: &Pending) -> Result<Option<Coin>, HorizError> {
+        pending
+            .changes
+            .get(key)
+            .map_or_else(|| self.load(key), |change| Ok(change.clone()))
+    }
+
+    fn load(&self, key: &[u8]) -> Result<Option<Coin>, HorizError> {
+        self.storage
+            .get_cf(UTXO_CF, key)?
+            .map(|bytes| horizcoin_codec::decode_exact(&bytes))
+            .transpose()
+    }
+
+    /// Writes `pending` and the matching address index updates in one
+    /// batch.
+    fn commit(&self, pending: Pending) -> Result<(), HorizError> {
+        let mut batch = WriteBatch::new();
+        for (key, change) in pending.changes {
+            if let Some(coin) = change {
+                batch.put_cf(UTXO_CF, &key, &horizcoin_codec::encode(&coin)?);
+                if !coin.output.address.is_empty() {
+                    let index_key = address_key(&coin.output.address, &key)?;
+                    let amount = horizcoin_codec::encode(&coin.output.amount)?;
+                    batch.put_cf(ADDRESS_INDEX_CF, &index_key, &amount);
+                }
+            } else {
+                // Outputs created and spent within `pending` were never
+                // stored, so have nothing to unindex.
+                if let Some(coin) = self.load(&key)? {
+                    if !coin.output.address.is_empty() {
+                        batch
+                            .delete_cf(ADDRESS_INDEX_CF, &address_key(&coin.output.address, &key)?);
+                    }
+                }
+                batch.delete_cf(UTXO_CF, &key);
+            }
+        }
+        self.storage.write_batch(batch)
+    }
+}
+
+/// Changes staged on top of storage, `None` marking a spent coin.
+#[derive(Debug, Default)]
+struct Pending {
+    changes: BTreeMap<Vec<u8>, Option<Coin>>,
+}
+
+fn outpoint_key(txid: &TxId, index: u32) -> Vec<u8> {
+    let mut key = Vec::with_capacity(KEY_LEN);
+    key.extend_from_slice(txid.as_bytes());
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+fn address_prefix(address: &str) -> Result<Vec<u8>, HorizError> {
+    let len = u32::try_from(address.len())
+        .map_err(|_| HorizError::InvalidAddress(format!("{} bytes is too long", address.len())))?;
+    let mut prefix = Vec::with_capacity(4 + address.len() + KEY_LEN);
+    prefix.extend_from_slice(&len.to_be_bytes());
+    prefix.extend_from_slice(address.as_bytes());
+    Ok(prefix)
+}
+
+fn address_key(address: &str, outpoint: &[u8]) -> Result<Vec<u8>, HorizError> {
+    let mut key = address_prefix(address)?;
+    key.extend_from_slice(outpoint);
+    Ok(key)
+}
+
+fn invalid(reason: impl Into<String>) -> HorizError {
+    HorizError::InvalidTransaction(reason.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_block::BlockHeader;
+    use horizcoin_crypto::PrivateKey;
+    use horizcoin_primitives::{BlockId, Hash};
+    use horizcoin_storage::MemoryStorage;
+    use horizcoin_tx::TxInput;
+
+    use super::*;
+
+    fn utxos() -> (Arc<MemoryStorage>, UtxoSet) {
+        let storage = Arc::new(MemoryStorage::new());
+        (storage.clone(), UtxoSet::new(storage))
+    }
+
+    fn coinbase(memo: &str) -> Transaction {
+        Transaction::coinbase(
+            vec![
+                TxOutput::new(Amount::new(50), "hz1miner"),
+                TxOutput::new(Amount::new(25), "hz1dev"),
+            ],
+            Some(memo.into()),
+        )
+    }
+
+    fn spend(prev: &Transaction, index: u32, amount: u64) -> Transaction {
+        Transaction::new(
+            vec![TxInput::new(
+                prev.txid().unwrap(),
+                index,
+                PrivateKey::generate().public_key(),
+            )],
+            vec![TxOutput::new(Amount::new(amount), "hz1payee")],
+            None,
+        )
+    }
+
+    #[test]
+    fn apply_moves_outputs() {
+        let (_, mut set) = utxos();
+        let mint = coinbase("block 1");
+        let mint_id = mint.txid().unwrap();
+        set.apply(&mint).unwrap();
+        assert_eq!(set.get(&mint_id, 0).unwrap(), Some(mint.outputs[0].clone()));
+        assert_eq!(set.get(&mint_id, 1).unwrap(), Some(mint.outputs[1].clone()));
+        assert_eq!(set.get(&mint_id, 2).unwrap(), None);
+
+        let pay = spend(&mint, 1, 20);
+        set.apply(&pay).unwrap();
+        assert_eq!(set.get(&mint_id, 1).unwrap(), None);
+        assert_eq!(set.get(&mint_id, 0).unwrap(), Some(mint.outputs[0].clone()));
+        assert_eq!(
+            set.get(&pay.txid().unwrap(), 0).unwrap(),
+            Some(pay.outputs[0].clone())
+        );
+    }
+
+    #[test]
+    fn rejects_missing_and_double_spends() {
+        let (storage, mut set) = utxos();
+        let mint = coinbase("block 1");
+        set.apply(&mint).unwrap();
+        assert!(set.apply(&mint).is_err());
+
+        let pay = spend(&mint, 0, 40);
+        set.apply(&pay).unwrap();
+        let before = storage.scan_prefix_cf(UTXO_CF, b"").unwrap();
+
+        assert!(matches!(
+            set.apply(&spend(&mint, 0, 40)),
+            Err(HorizError::InvalidTransaction(_))
+        ));
+        assert!(set.apply(&spend(&mint, 7, 1)).is_err());
+
+        // Spending the same output twice within one transaction.
+        let mut twice = spend(&mint, 1, 10);
+        twice.inputs.push(twice.inputs[0].clone());
+        assert!(set.apply(&twice).is_err());
+        assert_eq!(storage.scan_prefix_cf(UTXO_CF, b"").unwrap(), before);
+    }
+
+    #[test]
+    fn undo_restores_prior_set() {
+        let (storage, mut set) = utxos();
+        let mint = coinbase("block 1");
+        set.apply(&mint).unwrap();
+        let before = storage.scan_prefix_cf(UTXO_CF, b"").unwrap();
+
+        let pay = spend(&mint, 0, 45);
+        let spent = set.apply(&pay).unwrap();
+        assert_eq!(spent[0].output, mint.outputs[0]);
+        assert!(set.undo(&pay, vec![]).is_err());
+        set.undo(&pay, spent).unwrap();
+        assert_eq!(storage.scan_prefix_cf(UTXO_CF, b"").unwrap(), before);
+
+        set.undo(&mint, vec![]).unwrap();
+        assert!(storage.scan_prefix_cf(UTXO_CF, b"").unwrap().is_empty());
+        assert!(set.undo(&mint, vec![]).is_err());
+    }
+
+    fn block(height: u64, transactions: Vec<Transaction>) -> Block {
+        let header = BlockHeader {
+            prev_block_id: BlockId::ZERO,
+            merkle_root: Hash::ZERO,
+            timestamp: 1_700_000_000 + height * 60,
+            height,
+            difficulty: 1,
+            nonce: 0,
+        };
+        Block::new(header, transactions).unwrap()
+    }
+
+    fn signed_spend(prev: &Transaction, index: u32, owner: &PrivateKey, to: &str) -> Transaction {
+        let amount = prev.outputs[index as usize].amount.as_u64();
+        let mut tx = Transaction::new(
+            vec![TxInput::new(
+                prev.txid().unwrap(),
+                index,
+                owner.public_key(),
+            )],
+            vec![
+                TxOutput::new(Amount::new(amount / 2), to),
+                TxOutput::new(Amount::new(amount / 2 - 1), owner.public_key().to_address()),
+            ],
+            None,
+        );
+        tx.sign_input(0, owner).unwrap();
+        tx
+    }
+
+    fn immediately_mature() -> ValidationContext {
+        ValidationContext {
+            coinbase_maturity: 1,
+            ..ValidationContext::default()
+        }
+    }
+
+    #[test]
+    fn disconnect_reverts_to_previous_block() {
+        let alice = PrivateKey::generate();
+        let storage = Arc::new(MemoryStorage::new());
+        let mut set = UtxoSet::with_context(storage.clone(), immediately_mature());
+
+        let mint = Transaction::coinbase(
+            vec![TxOutput::new(
+                Amount::new(5_000),
+                alice.public_key().to_address(),
+            )],
+            Some("block 1".into()),
+        );
+        set.connect_block(&block(1, vec![mint.clone()])).unwrap();
+        let after_first = storage.scan_prefix_cf(UTXO_CF, b"").unwrap();
+
+        let pay = signed_spend(&mint, 0, &alice, "hz1bob");
+        // The change goes straight back out within the same block.
+        let onward = signed_spend(&pay, 1, &alice, "hz1carol");
+        let second = block(
+            2,
+            vec![
+                Transaction::coinbase(
+                    vec![TxOutput::new(Amount::new(5_000), "hz1miner")],
+                    Some("block 2".into()),
+                ),
+                pay.clone(),
+                onward,
+            ],
+        );
+        let undo = set.connect_block(&second).unwrap();
+        assert_eq!(undo.spent.len(), 3);
+        assert_eq!(undo.spent[1][0].output, mint.outputs[0]);
+        assert!(undo.spent[1][0].is_coinbase);
+        assert_eq!(set.get(&mint.txid().unwrap(), 0).unwrap(), None);
+        let paid = set.get_coin(&pay.txid().unwrap(), 0).unwrap().unwrap();
+        assert_eq!(paid.height, 2);
+
+        assert!(set.disconnect_block(&second, &UndoData::default()).is_err());
+        set.disconnect_block(&second, &undo).unwrap();
+        assert_eq!(storage.scan_prefix_cf(UTXO_CF, b"").unwrap(), after_first);
+    }
+
+    #[test]
+    fn connect_enforces_maturity_and_signatures() {
+        let alice = PrivateKey::generate();
+        let storage = Arc::new(MemoryStorage::new());
+        let mut set = UtxoSet::new(storage.clone());
+        let mint = Transaction::coinbase(
+            vec![TxOutput::new(
+                Amount::new(5_000),
+                alice.public_key().to_address(),
+            )],
+            None,
+        );
+        set.connect_block(&block(1, vec![mint.clone()])).unwrap();
+        let before = storage.scan_prefix_cf(UTXO_CF, b"").unwrap();
+        let next_coinbase =
+            || Transaction::coinbase(vec![TxOutput::new(Amount::new(1), "hz1miner")], None);
+
+        let pay = signed_spend(&mint, 0, &alice, "hz1bob");
+        assert!(set
+            .connect_block(&block(2, vec![next_coinbase(), pay.clone()]))
+            .is_err());
+
+        let mut set = UtxoSet::with_context(storage.clone(), immediately_mature());
+        let mut forged = pay.clone();
+        forged.outputs[0].amount = Amount::new(forged.outputs[0].amount.as_u64() + 1);
+        assert!(set
+            .connect_block(&block(2, vec![next_coinbase(), forged]))
+            .is_err());
+
+        let thief = PrivateKey::generate();
+        let stolen = signed_spend(&mint, 0, &thief, "hz1thief");
+        assert!(set
+            .connect_block(&block(2, vec![next_coinbase(), stolen]))
+            .is_err());
+
+        assert!(set.connect_block(&block(2, vec![pay.clone()])).is_err());
+        let mut tampered = block(2, vec![next_coinbase(), pay]);
+        tampered.transactions.pop();
+        assert!(set.connect_block(&tampered).is_err());
+        assert_eq!(storage.scan_prefix_cf(UTXO_CF, b"").unwrap(), before);
+    }
+
+    #[test]
+    fn corrupt_coin_is_not_reported_missing() {
+        let alice = PrivateKey::generate();
+        let storage = Arc::new(MemoryStorage::new());
+        let mut set = UtxoSet::with_context(storage.clone(), immediately_mature());
+        let mint = Transaction::coinbase(
+            vec![TxOutput::new(
+                Amount::new(5_000),
+                alice.public_key().to_address(),
+            )],
+            None,
+        );
+        set.connect_block(&block(1, vec![mint.clone()])).unwrap();
+        storage
+            .put_cf(UTXO_CF, &outpoint_key(&mint.txid().unwrap(), 0), b"\xff")
+            .unwrap();
+
+        let pay = signed_spend(&mint, 0, &alice, "hz1bob");
+        let coinbase = Transaction::coinbase(vec![TxOutput::new(Amount::new(1), "hz1miner")], None);
+        let err = set
+            .connect_block(&block(2, vec![coinbase, pay]))
+            .unwrap_err();
+        assert!(!matches!(err, HorizError::InvalidTransaction(_)), "{err}");
+    }
+
+    #[test]
+    fn state_root_ignores_order_of_operations() {
+        let first = coinbase("block 1");
+        let second = coinbase("block 2");
+        let pay_first = spend(&first, 0, 30);
+        let pay_second = spend(&second, 1, 5);
+
+        let (_, mut forward) = utxos();
+        let empty = forward.state_root().unwrap();
+        assert_eq!(empty, Hash::ZERO);
+        for tx in [&first, &pay_first, &second, &pay_second] {
+            forward.apply(tx).unwrap();
+        }
+
+        let (_, mut backward) = utxos();
+        backward.apply(&second).unwrap();
+        let after_second = backward.state_root().unwrap();
+        assert_ne!(after_second, empty);
+        backward.apply(&pay_second).unwrap();
+        assert_ne!(backward.state_root().unwrap(), after_second);
+        backward.apply(&first).unwrap();
+        // A detour that nets out leaves no trace.
+        let detour = spend(&first, 1, 25);
+        let spent = backward.apply(&detour).unwrap();
+        backward.undo(&detour, spent).unwrap();
+        backward.apply(&pay_first).unwrap();
+
+        let root = forward.state_root().unwrap();
+        assert_eq!(backward.state_root().unwrap(), root);
+        assert_ne!(root, after_second);
+    }
+
+    #[test]
+    fn address_index_tracks_sends_and_spends() {
+        let alice = PrivateKey::generate();
+        let address = alice.public_key().to_address();
+        let storage = Arc::new(MemoryStorage::new());
+        let mut set = UtxoSet::with_context(storage, immediately_mature());
+        assert_eq!(set.balance_of(&address).unwrap(), Amount::ZERO);
+
+        let mint = Transaction::coinbase(
+            vec![
+                TxOutput::new(Amount::new(3_000), address.clone()),
+                TxOutput::new(Amount::new(2_000), address.clone()),
+            ],
+            None,
+        );
+        set.connect_block(&block(1, vec![mint.clone()])).unwrap();
+        let mint_id = mint.txid().unwrap();
+        assert_eq!(set.balance_of(&address).unwrap(), Amount::new(5_000));
+        assert_eq!(
+            set.utxos_of(&address).unwrap(),
+            vec![
+                (mint_id, 0, Amount::new(3_000)),
+                (mint_id, 1, Amount::new(2_000))
+            ]
+        );
+
+        // Sends half of output 0 to bob, keeping 1_499 as change.
+        let pay = signed_spend(&mint, 0, &alice, "hz1bob");
+        let second = block(
+            2,
+            vec![
+                Transaction::coinbase(vec![TxOutput::new(Amount::new(1), "hz1miner")], None),
+                pay,
+            ],
+        );
+        let undo = set.connect_block(&second).unwrap();
+        assert_eq!(set.balance_of(&address).unwrap(), Amount::new(3_499));
+        assert_eq!(set.balance_of("hz1bob").unwrap(), Amount::new(1_500));
+        assert_eq!(
+            set.utxos_of(&address).unwrap().len(),
+            2,
+            "the spent output leaves the index, the change joins it"
+        );
+
+        set.disconnect_block(&second, &undo).unwrap();
+        assert_eq!(set.balance_of(&address).unwrap(), Amount::new(5_000));
+        assert_eq!(set.balance_of("hz1bob").unwrap(), Amount::ZERO);
+        assert!(set.utxos_of("hz1miner").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+//! Atomic groups of writes.
+
+use crate::DEFAULT_CF;
+
+/// One write in a [`WriteBatch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOp {
+    /// Set `key` to `value` in column family `cf`.
+    Put {
+        /// Column family name.
+        cf: String,
+        /// Key to write.
+        key: Vec<u8>,
+        /// New value.
+        value: Vec<u8>,
+    },
+    /// Remove `key` from column family `cf`.
+    Delete {
+        /// Column family name.
+        cf: String,
+        /// Key to remove.
+        key: Vec<u8>,
+    },
+}
+
+/// Writes applied together by [`Storage::write_batch`](crate::Storage::write_batch),
+/// in insertion order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteBatch {
+    ops: Vec<BatchOp>,
+}
+
+impl WriteBatch {
+    /// Creates an empty batch.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a write of `key` in the default column family.
+    pub fn put(&mut self, key: &[u8], value: &[u8]) -> &mut Self {
+        self.put_cf(DEFAULT_CF, key, value)
+    }
+
+    /// Queues a write of `key` in column family `cf`.
+    pub fn put_cf(&mut self, cf: &str, key: &[u8], value: &[u8]) -> &mut Self {
+        self.ops.push(BatchOp::Put {
+            cf: cf.to_owned(),
+            key: key.to_vec(),
+            value: value.to_vec(),
+        });
+        self
+    }
+
+    /// Queues removal of `key` from the default column family.
+    pub fn delete(&mut self, key: &[u8]) -> &mut Self {
+        self.delete_cf(DEFAULT_CF, key)
+    }
+
+    /// Queues removal of `key` from column family `cf`.
+    pub fn delete_cf(&mut self, cf: &str, key: &[u8]) -> &mut Self {
+        self.ops.push(BatchOp::Delete {
+            cf: cf.to_owned(),
+            key: key.to_vec(),
+        });
+        self
+    }
+
+    /// Returns the number of queued operations.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Returns `true` if nothing is queued.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Returns the queued operations in order.
+    #[must_use]
+    pub fn ops(&self) -> &[BatchOp] {
+        &self.ops
+    }
+
+    /// Consumes the batch, returning its operations in order.
+    #[must_use]
+    pub fn into_ops(self) -> Vec<BatchOp> {
+        self.ops
+    }
+}
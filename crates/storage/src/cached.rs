@@ -0,0 +1,424 @@
+//! A write-back buffer in front of another [`Storage`].
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use horizcoin_primitives::HorizError;
+
+use crate::{BatchOp, KeyValue, KeyValueIter, Snapshot, Storage, WriteBatch, DEFAULT_CF};
+
+/// Default number of buffered writes that triggers a flush.
+pub const DEFAULT_CACHE_CAPACITY: usize = 10_000;
+
+/// Buffered writes per key, `None` marking a delete.
+type Pending = BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+
+/// A [`Storage`] that collects writes in memory and hands them to an inner
+/// store as one [`WriteBatch`].
+///
+/// Writes go to the buffer and are applied to the inner store by
+/// [`Storage::flush`], or automatically once more than `capacity` keys
+/// are pending. Reads and scans consult the buffer first, so a write is
+/// visible through this store as soon as it returns, flushed or not.
+///
+/// [`Storage::put_with_ttl`] is not buffered: it writes through at once,
+/// replacing any pending write of the same key. Scan iterators are not
+/// lazy, since the buffer has to be merged into the inner entries.
+pub struct CachedStorage {
+    inner: Arc<dyn Storage>,
+    capacity: usize,
+    pending: Mutex<BTreeMap<String, Pending>>,
+}
+
+impl fmt::Debug for CachedStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedStorage")
+            .field("capacity", &self.capacity)
+            .field("pending", &self.pending_len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl CachedStorage {
+    /// Buffers writes to `inner`, flushing past [`DEFAULT_CACHE_CAPACITY`]
+    /// pending keys.
+    pub fn new(inner: Arc<dyn Storage>) -> Self {
+        Self::with_capacity(inner, DEFAULT_CACHE_CAPACITY)
+    }
+
+    /// Buffers writes to `inner`, flushing once more than `capacity` keys
+    /// are pending.
+    pub fn with_capacity(inner: Arc<dyn Storage>, capacity: usize) -> Self {
+        Self {
+            inner,
+            capacity,
+            pending: Mutex::default(),
+        }
+    }
+
+    /// Returns the number of keys with a write not yet flushed.
+    pub fn pending_len(&self) -> usize {
+        self.lock().values().map(BTreeMap::len).sum()
+    }
+
+    // The lock is held across the inner write, so readers never see a
+    // write missing from both the buffer and the inner store.
+    fn flush_locked(
+        inner: &Arc<dyn Storage>,
+        pending: &mut BTreeMap<String, Pending>,
+    ) -> Result<(), HorizError> {
+        if pending.values().all(BTreeMap::is_empty) {
+            return Ok(());
+        }
+        let mut batch = WriteBatch::new();
+        for (cf, family) in pending.iter() {
+            for (key, value) in family {
+                match value {
+                    Some(value) => batch.put_cf(cf, key, value),
+                    None => batch.delete_cf(cf, key),
+                };
+            }
+        }
+        inner.write_batch(batch)?;
+        pending.clear();
+        Ok(())
+    }
+
+    /// Records `ops` in the buffer, flushing if it grew past capacity.
+    fn buffer(&self, ops: impl IntoIterator<Item = BatchOp>) -> Result<(), HorizError> {
+        let mut pending = self.lock();
+        for op in ops {
+            match op {
+                BatchOp::Put { cf, key, value } => {
+                    pending.entry(cf).or_default().insert(key, Some(value));
+                }
+                BatchOp::Delete { cf, key } => {
+                    pending.entry(cf).or_default().insert(key, None);
+                }
+            }
+        }
+        self.flush_past_capacity(&mut pending)?;
+        drop(pending);
+        Ok(())
+    }
+
+    /// Writes out `pending` once it holds more writes than the capacity.
+    fn flush_past_capacity(
+        &self,
+        pending: &mut BTreeMap<String, Pending>,
+    ) -> Result<(), HorizError> {
+        if pending.values().map(BTreeMap::len).sum::<usize>() > self.capacity {
+            Self::flush_locked(&self.inner, pending)?;
+        }
+        Ok(())
+    }
+
+    // Every buffer update completes while holding the lock, so poisoning
+    // can be ignored.
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, Pending>> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Storage for CachedStorage {
+    fn get_cf(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>, HorizError> {
+        let pending = self.lock();
+        pending
+            .get(cf)
+            .and_then(|family| family.get(key))
+            .map_or_else(|| self.inner.get_cf(cf, key), |value| Ok(value.clone()))
+    }
+
+    fn put_cf(&self, cf: &str, key: &[u8], value: &[u8]) -> Result<(), HorizError> {
+        self.buffer([BatchOp::Put {
+            cf: cf.to_owned(),
+            key: key.to_vec(),
+            value: value.to_vec(),
+        }])
+    }
+
+    fn delete_cf(&self, cf: &str, key: &[u8]) -> Result<(), HorizError> {
+        self.buffer([BatchOp::Delete {
+            cf: cf.to_owned(),
+            key: key.to_vec(),
+        }])
+    }
+
+    fn scan_prefix_cf(&self, cf: &str, prefix: &[u8]) -> Result<Vec<KeyValue>, HorizError> {
+        let pending = self.lock();
+        let entries = self.inner.scan_prefix_cf(cf, prefix)?;
+        Ok(overlay(entries, pending.get(cf), |key| {
+            key.starts_with(prefix)
+        }))
+    }
+
+    fn scan_range_cf(
+        &self,
+        cf: &str,
+        start: &[u8],
+        end: &[u8],
+    ) -> Result<Vec<KeyValue>, HorizError> {
+        let pending = self.lock();
+        let entries = self.inner.scan_range_cf(cf, start, end)?;
+        Ok(overlay(entries, pending.get(cf), |key| {
+            start <= key && key < end
+        }))
+    }
+
+    fn scan_prefix_iter_cf(&self, cf: &str, prefix: &[u8]) -> KeyValueIter<'_> {
+        match self.scan_prefix_cf(cf, prefix) {
+            Ok(entries) => Box::new(entries.into_iter().map(Ok)),
+            Err(e) => Box::new(std::iter::once(Err(e))),
+        }
+    }
+
+    fn scan_range_iter_cf(&self, cf: &str, start: &[u8], end: &[u8]) -> KeyValueIter<'_> {
+        match self.scan_range_cf(cf, start, end) {
+            Ok(entries) => Box::new(entries.into_iter().map(Ok)),
+            Err(e) => Box::new(std::iter::once(Err(e))),
+        }
+    }
+
+    fn write_batch(&self, batch: WriteBatch) -> Result<(), HorizError> {
+        self.buffer(batch.into_ops())
+    }
+
+    fn snapshot(&self) -> Box<dyn Snapshot + '_> {
+        let pending = self.lock();
+        Box::new(CachedSnapshot {
+            inner: self.inner.snapshot(),
+            pending: pending.clone(),
+        })
+    }
+
+    fn column_families(&self) -> Result<Vec<String>, HorizError> {
+        let pending = self.lock();
+        let mut families = self.inner.column_families()?;
+        families.extend(pending.keys().cloned());
+        drop(pending);
+        families.sort();
+        families.dedup();
+        Ok(families)
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, HorizError> {
+        let mut pending = self.lock();
+        let family = pending.entry(DEFAULT_CF.to_owned()).or_default();
+        let current = match family.get(key) {
+            Some(value) => value.clone(),
+            None => self.inner.get(key)?,
+        };
+        let swapped = current.as_deref() == expected;
+        if swapped {
+            family.insert(key.to_vec(), Some(new.to_vec()));
+            self.flush_past_capacity(&mut pending)?;
+        }
+        drop(pending);
+        Ok(swapped)
+    }
+
+    /// Writes out the buffer first, so pending writes under `prefix` are
+    /// removed and counted like flushed ones.
+    fn delete_prefix(&self, prefix: &[u8]) -> Result<usize, HorizError> {
+        let mut pending = self.lock();
+        Self::flush_locked(&self.inner, &mut pending)?;
+        let removed = self.inner.delete_prefix(prefix);
+        drop(pending);
+        removed
+    }
+
+    /// Applies every pending write to the inner store in one batch, then
+    /// flushes the inner store.
+    ///
+    /// On error the writes stay pending, so a later flush retries them.
+    fn flush(&self) -> Result<(), HorizError> {
+        Self::flush_locked(&self.inner, &mut self.lock())?;
+        self.inner.flush()
+    }
+
+    fn put_with_ttl(&self, key: &[u8], value: &[u8], ttl: Duration) -> Result<(), HorizError> {
+        let mut pending = self.lock();
+        if let Some(family) = pending.get_mut(DEFAULT_CF) {
+            family.remove(key);
+        }
+        let written = self.inner.put_with_ttl(key, value, ttl);
+        drop(pending);
+        written
+    }
+}
+
+/// An inner snapshot with the writes that were pending when it was taken.
+struct CachedSnapshot<'a> {
+    inner: Box<dyn Snapshot + 'a>,
+    pending: BTreeMap<String, Pending>,
+}
+
+impl Snapshot for CachedSnapshot<'_> {
+    fn get_cf(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>, HorizError> {
+        self.pending
+            .get(cf)
+            .and_then(|family| family.get(key))
+            .map_or_else(|| self.inner.get_cf(cf, key), |value| Ok(value.clone()))
+    }
+
+    fn scan_prefix_cf(&self, cf: &str, prefix: &[u8]) -> Result<Vec<KeyValue>, HorizError> {
+        let entries = self.inner.scan_prefix_cf(cf, prefix)?;
+        Ok(overlay(entries, self.pending.get(cf), |key| {
+            key.starts_with(prefix)
+        }))
+    }
+}
+
+/// Applies the pending writes whose key passes `in_scope` to `entries`.
+fn overlay(
+    entries: Vec<KeyValue>,
+    pending: Option<&Pending>,
+    in_scope: impl Fn(&[u8]) -> bool,
+) -> Vec<KeyValue> {
+    let Some(pending) = pending else {
+        return entries;
+    };
+    let mut merged: BTreeMap<Vec<u8>, Vec<u8>> = entries.into_iter().collect();
+    for (key, value) in pending.iter().filter(|(key, _)| in_scope(key)) {
+        match value {
+            Some(value) => merged.insert(key.clone(), value.clone()),
+            None => merged.remove(key),
+        };
+    }
+    merged.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStorage;
+
+    fn cached(capacity: usize) -> (Arc<MemoryStorage>, CachedStorage) {
+        let inner = Arc::new(MemoryStorage::new());
+        let cached = CachedStorage::with_capacity(inner.clone(), capacity);
+        (inner, cached)
+    }
+
+    #[test]
+    fn reads_see_buffered_writes() {
+        let (inner, store) = cached(100);
+        inner.put(b"old", b"flushed").unwrap();
+        inner.put(b"gone", b"flushed").unwrap();
+
+        store.put(b"new", b"1").unwrap();
+        store.put(b"old", b"2").unwrap();
+        store.delete(b"gone").unwrap();
+        store.put_cf("utxos", b"a:0", b"50").unwrap();
+
+        assert_eq!(store.get(b"new").unwrap(), Some(b"1".to_vec()));
+        assert_eq!(store.get(b"old").unwrap(), Some(b"2".to_vec()));
+        assert_eq!(store.get(b"gone").unwrap(), None);
+        assert_eq!(store.get_cf("utxos", b"a:0").unwrap(), Some(b"50".to_vec()));
+        assert_eq!(
+            store.scan_prefix(b"").unwrap(),
+            [
+                (b"new".to_vec(), b"1".to_vec()),
+                (b"old".to_vec(), b"2".to_vec())
+            ]
+        );
+        assert_eq!(store.scan_range_iter(b"n", b"o").count(), 1);
+        assert!(store.column_families().unwrap().contains(&"utxos".into()));
+
+        // Nothing has reached the inner store yet.
+        assert_eq!(store.pending_len(), 4);
+        assert_eq!(inner.get(b"new").unwrap(), None);
+        assert_eq!(inner.get(b"old").unwrap(), Some(b"flushed".to_vec()));
+        assert!(inner.exists(b"gone").unwrap());
+    }
+
+    #[test]
+    fn flush_persists_everything() {
+        let (inner, store) = cached(100);
+        inner.put(b"gone", b"flushed").unwrap();
+        let mut batch = WriteBatch::new();
+        batch.put(b"a", b"1").put_cf("blocks", b"9", b"header");
+        store.write_batch(batch).unwrap();
+        store.delete(b"gone").unwrap();
+        assert!(store.compare_and_swap(b"tip", None, b"9").unwrap());
+        assert!(!store.compare_and_swap(b"tip", None, b"10").unwrap());
+
+        let snapshot = store.snapshot();
+        store.flush().unwrap();
+        assert_eq!(store.pending_len(), 0);
+        store.flush().unwrap();
+
+        assert_eq!(inner.get(b"a").unwrap(), Some(b"1".to_vec()));
+        assert_eq!(inner.get(b"tip").unwrap(), Some(b"9".to_vec()));
+        assert_eq!(inner.get(b"gone").unwrap(), None);
+        assert_eq!(
+            inner.get_cf("blocks", b"9").unwrap(),
+            Some(b"header".to_vec())
+        );
+        assert_eq!(snapshot.get(b"tip").unwrap(), Some(b"9".to_vec()));
+        assert_eq!(snapshot.get(b"gone").unwrap(), None);
+    }
+
+    #[test]
+    fn buffer_flushes_past_capacity() {
+        let (inner, store) = cached(3);
+        for key in [b"a", b"b", b"c"] {
+            store.put(key, b"v").unwrap();
+        }
+        // Rewriting a pending key does not grow the buffer.
+        store.put(b"a", b"w").unwrap();
+        assert_eq!(store.pending_len(), 3);
+        assert!(inner.scan_prefix(b"").unwrap().is_empty());
+
+        store.put(b"d", b"v").unwrap();
+        assert_eq!(store.pending_len(), 0);
+        assert_eq!(inner.scan_prefix(b"").unwrap().len(), 4);
+        assert_eq!(inner.get(b"a").unwrap(), Some(b"w".to_vec()));
+    }
+
+    #[test]
+    fn compare_and_swap_respects_capacity() {
+        let (inner, store) = cached(2);
+        store.put(b"a", b"v").unwrap();
+        assert!(store.compare_and_swap(b"b", None, b"v").unwrap());
+        assert_eq!(store.pending_len(), 2);
+
+        assert!(store.compare_and_swap(b"c", None, b"v").unwrap());
+        assert_eq!(store.pending_len(), 0);
+        assert_eq!(inner.get(b"c").unwrap(), Some(b"v".to_vec()));
+    }
+
+    #[test]
+    fn delete_prefix_covers_pending_writes() {
+        let (inner, store) = cached(100);
+        inner.put(b"old/1", b"flushed").unwrap();
+        store.put(b"old/2", b"buffered").unwrap();
+        store.delete(b"old/1").unwrap();
+        store.put(b"new/1", b"buffered").unwrap();
+
+        assert_eq!(store.delete_prefix(b"old/").unwrap(), 1);
+        assert!(store.scan_prefix(b"old/").unwrap().is_empty());
+        assert_eq!(store.get(b"new/1").unwrap(), Some(b"buffered".to_vec()));
+        assert_eq!(inner.scan_prefix(b"").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn ttl_writes_replace_pending_ones() {
+        let (inner, store) = cached(100);
+        store.put(b"peer", b"buffered").unwrap();
+        store
+            .put_with_ttl(b"peer", b"addr", Duration::from_secs(3600))
+            .unwrap();
+        assert_eq!(store.pending_len(), 0);
+        assert_eq!(store.get(b"peer").unwrap(), Some(b"addr".to_vec()));
+        assert_eq!(inner.get(b"peer").unwrap(), Some(b"addr".to_vec()));
+    }
+}
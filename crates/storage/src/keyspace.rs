@@ -0,0 +1,90 @@
+//! A fixed layout for keys of different kinds sharing one column family.
+
+use horizcoin_primitives::HorizError;
+
+use crate::{KeyValue, Storage};
+
+/// The kind of record a key belongs to, stored as the key's first byte.
+///
+/// Every key built with [`make_key`](Self::make_key) starts with exactly
+/// one tag byte, so keys of different kinds can never collide and a
+/// [`scan`](Self::scan) of one kind never returns another. The tags are
+/// below the printable ASCII range so they also stay clear of older
+/// hand-written keys such as `b"chain_tip"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum KeyspacePrefix {
+    /// Blocks and block headers.
+    Block = 0x01,
+    /// Transactions.
+    Tx = 0x02,
+    /// Unspent transaction outputs.
+    Utxo = 0x03,
+    /// Node metadata such as versions and markers.
+    Meta = 0x04,
+}
+
+impl KeyspacePrefix {
+    /// Every keyspace, in tag order.
+    pub const ALL: [Self; 4] = [Self::Block, Self::Tx, Self::Utxo, Self::Meta];
+
+    /// Returns the tag byte that starts every key of this keyspace.
+    #[must_use]
+    pub const fn tag(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns `suffix` prefixed with this keyspace's tag.
+    #[must_use]
+    pub fn make_key(self, suffix: &[u8]) -> Vec<u8> {
+        let mut key = Vec::with_capacity(1 + suffix.len());
+        key.push(self.tag());
+        key.extend_from_slice(suffix);
+        key
+    }
+
+    /// Returns every entry of this keyspace in the default column family.
+    ///
+    /// Keys are returned whole, tag included, so they can be passed back
+    /// to [`Storage::get`] or [`Storage::delete`] unchanged.
+    pub fn scan(self, storage: &(impl Storage + ?Sized)) -> Result<Vec<KeyValue>, HorizError> {
+        storage.scan_prefix(&[self.tag()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStorage;
+
+    #[test]
+    fn keyspaces_never_see_each_other() {
+        let store = MemoryStorage::new();
+        // The same suffix in every keyspace, plus a suffix that itself
+        // starts with another keyspace's tag.
+        for prefix in KeyspacePrefix::ALL {
+            store.put(&prefix.make_key(b"id"), &[prefix.tag()]).unwrap();
+            store
+                .put(
+                    &prefix.make_key(&KeyspacePrefix::Meta.make_key(b"x")),
+                    b"nested",
+                )
+                .unwrap();
+        }
+        store.put(b"chain_tip", b"legacy").unwrap();
+
+        for prefix in KeyspacePrefix::ALL {
+            let entries = prefix.scan(&store).unwrap();
+            assert_eq!(entries.len(), 2);
+            for (key, _) in &entries {
+                assert_eq!(key[0], prefix.tag());
+            }
+            assert_eq!(
+                store.get(&prefix.make_key(b"id")).unwrap(),
+                Some(vec![prefix.tag()])
+            );
+        }
+        assert_eq!(KeyspacePrefix::Utxo.make_key(b"id"), b"\x03id");
+        assert_eq!(store.scan_prefix(b"").unwrap().len(), 9);
+    }
+}
@@ -2,11 +2,227 @@
 //!
 //! This crate provides `RocksDB` backend with in-memory fallback for testing
 //! for the `HorizCoin` blockchain.
+//!
+//! Keys live in named column families so subsystems do not have to prefix
+//! their keys by hand. The non-`_cf` methods of [`Storage`] operate on
+//! [`DEFAULT_CF`]. Within a family, [`KeyspacePrefix`] tags keys by kind.
+//! The `RocksDB` backend is behind the `rocksdb` feature.
+
+mod batch;
+mod cached;
+mod keyspace;
+mod memory;
+mod migration;
+#[cfg(feature = "rocksdb")]
+mod rocks;
+mod typed;
+
+use std::time::Duration;
+
+pub use batch::{BatchOp, WriteBatch};
+pub use cached::{CachedStorage, DEFAULT_CACHE_CAPACITY};
+use horizcoin_primitives::HorizError;
+pub use keyspace::KeyspacePrefix;
+pub use memory::MemoryStorage;
+pub use migration::{Migration, Migrator, SchemaVersion, SCHEMA_VERSION_KEY};
+#[cfg(feature = "rocksdb")]
+pub use rocks::{RocksDbConfig, RocksDbStorage, StorageStats};
+/// Compression choices for [`RocksDbConfig::compression`].
+#[cfg(feature = "rocksdb")]
+pub use rocksdb::DBCompressionType;
+pub use typed::TypedStorage;
+
+/// Column family used by the methods without a `_cf` suffix.
+pub const DEFAULT_CF: &str = "default";
+
+/// A key and its value, as returned by scans.
+pub type KeyValue = (Vec<u8>, Vec<u8>);
+
+/// A lazy scan over key/value entries, in ascending key order.
+pub type KeyValueIter<'a> = Box<dyn Iterator<Item = Result<KeyValue, HorizError>> + 'a>;
+
+/// A byte-oriented key/value store with column families.
+///
+/// Scans return entries in ascending key order.
+pub trait Storage: Send + Sync {
+    /// Returns the value of `key` in column family `cf`.
+    fn get_cf(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>, HorizError>;
+
+    /// Sets `key` to `value` in column family `cf`.
+    fn put_cf(&self, cf: &str, key: &[u8], value: &[u8]) -> Result<(), HorizError>;
+
+    /// Removes `key` from column family `cf`. Removing a missing key is not
+    /// an error.
+    fn delete_cf(&self, cf: &str, key: &[u8]) -> Result<(), HorizError>;
+
+    /// Returns every entry of column family `cf` whose key starts with
+    /// `prefix`.
+    fn scan_prefix_cf(&self, cf: &str, prefix: &[u8]) -> Result<Vec<KeyValue>, HorizError>;
+
+    /// Returns every entry of column family `cf` with `start <= key < end`.
+    fn scan_range_cf(
+        &self,
+        cf: &str,
+        start: &[u8],
+        end: &[u8],
+    ) -> Result<Vec<KeyValue>, HorizError>;
+
+    /// Like [`scan_prefix_cf`](Self::scan_prefix_cf), but yields entries
+    /// one at a time instead of collecting them.
+    fn scan_prefix_iter_cf(&self, cf: &str, prefix: &[u8]) -> KeyValueIter<'_>;
+
+    /// Like [`scan_range_cf`](Self::scan_range_cf), but yields entries one
+    /// at a time instead of collecting them.
+    fn scan_range_iter_cf(&self, cf: &str, start: &[u8], end: &[u8]) -> KeyValueIter<'_>;
+
+    /// Applies every operation in `batch` atomically.
+    fn write_batch(&self, batch: WriteBatch) -> Result<(), HorizError>;
+
+    /// Returns a point-in-time view that later writes do not affect.
+    fn snapshot(&self) -> Box<dyn Snapshot + '_>;
+
+    /// Returns the names of the column families that exist in the store.
+    fn column_families(&self) -> Result<Vec<String>, HorizError>;
+
+    /// Sets `key` to `new` only if its current value equals `expected`,
+    /// where `None` means the key must be absent. Returns whether the write
+    /// happened.
+    ///
+    /// The comparison and write are atomic with respect to other
+    /// `compare_and_swap` calls on the same store.
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, HorizError>;
+
+    /// Sets `key` to `value` until `ttl` has elapsed, after which reads and
+    /// scans treat the key as absent. A later plain write clears the TTL.
+    fn put_with_ttl(&self, key: &[u8], value: &[u8], ttl: Duration) -> Result<(), HorizError>;
+
+    /// Removes every key in the default column family that starts with
+    /// `prefix` and returns how many live entries were removed.
+    ///
+    /// Entries that had already expired are removed too but not counted.
+    fn delete_prefix(&self, prefix: &[u8]) -> Result<usize, HorizError>;
+
+    /// Forces every write that has returned so far onto durable storage.
+    ///
+    /// Writes are normally durable only as far as the backend chooses;
+    /// call this at shutdown or after connecting a block to be sure they
+    /// survive a crash.
+    fn flush(&self) -> Result<(), HorizError>;
+
+    /// Copies a consistent view of every column family into `dest`, one
+    /// batch per family.
+    ///
+    /// This reads everything through a [`Snapshot`], so writes may continue
+    /// while it runs. Entries are copied as plain values; TTLs are dropped.
+    fn checkpoint_into(&self, dest: &dyn Storage) -> Result<(), HorizError> {
+        let snapshot = self.snapshot();
+        // Listed after the snapshot, so a family created in between reads
+        // as empty rather than being missed.
+        for cf in self.column_families()? {
+            let mut batch = WriteBatch::new();
+            for (key, value) in snapshot.scan_prefix_cf(&cf, &[])? {
+                batch.put_cf(&cf, &key, &value);
+            }
+            dest.write_batch(batch)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the value of `key`.
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, HorizError> {
+        self.get_cf(DEFAULT_CF, key)
+    }
+
+    /// Sets `key` to `value`.
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<(), HorizError> {
+        self.put_cf(DEFAULT_CF, key, value)
+    }
+
+    /// Sets every key in `items` to its value in one atomic batch: either
+    /// all of them are written or none are.
+    ///
+    /// The items are gathered before anything is written. Through a
+    /// `dyn Storage`, build a [`WriteBatch`] instead.
+    fn put_many(
+        &self,
+        items: impl IntoIterator<Item = (Vec<u8>, Vec<u8>)>,
+    ) -> Result<(), HorizError>
+    where
+        Self: Sized,
+    {
+        let mut batch = WriteBatch::new();
+        for (key, value) in items {
+            batch.put(&key, &value);
+        }
+        self.write_batch(batch)
+    }
+
+    /// Removes `key`.
+    fn delete(&self, key: &[u8]) -> Result<(), HorizError> {
+        self.delete_cf(DEFAULT_CF, key)
+    }
+
+    /// Returns `true` if `key` has a value.
+    fn exists(&self, key: &[u8]) -> Result<bool, HorizError> {
+        self.get(key).map(|value| value.is_some())
+    }
+
+    /// Returns every entry whose key starts with `prefix`.
+    fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<KeyValue>, HorizError> {
+        self.scan_prefix_cf(DEFAULT_CF, prefix)
+    }
+
+    /// Returns every entry with `start <= key < end`.
+    fn scan_range(&self, start: &[u8], end: &[u8]) -> Result<Vec<KeyValue>, HorizError> {
+        self.scan_range_cf(DEFAULT_CF, start, end)
+    }
+
+    /// Lazily yields every entry whose key starts with `prefix`.
+    fn scan_prefix_iter(&self, prefix: &[u8]) -> KeyValueIter<'_> {
+        self.scan_prefix_iter_cf(DEFAULT_CF, prefix)
+    }
+
+    /// Lazily yields every entry with `start <= key < end`.
+    fn scan_range_iter(&self, start: &[u8], end: &[u8]) -> KeyValueIter<'_> {
+        self.scan_range_iter_cf(DEFAULT_CF, start, end)
+    }
+}
+
+/// A read-only, consistent view of a [`Storage`] at one point in time.
+pub trait Snapshot: Send + Sync {
+    /// Returns the value `key` had in column family `cf`.
+    fn get_cf(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>, HorizError>;
+
+    /// Returns the entries of column family `cf` whose key starts with
+    /// `prefix`.
+    fn scan_prefix_cf(&self, cf: &str, prefix: &[u8]) -> Result<Vec<KeyValue>, HorizError>;
+
+    /// Returns the value `key` had in the default column family.
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, HorizError> {
+        self.get_cf(DEFAULT_CF, key)
+    }
+
+    /// Returns the entries of the default column family whose key starts
+    /// with `prefix`.
+    fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<KeyValue>, HorizError> {
+        self.scan_prefix_cf(DEFAULT_CF, prefix)
+    }
+}
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn placeholder_test() {
-        // Placeholder test to ensure the crate compiles
+/// Returns the smallest key greater than every key starting with `prefix`,
+/// or `None` if no such key exists (an empty or all-`0xff` prefix).
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
     }
+    None
 }
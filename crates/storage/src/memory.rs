@@ -0,0 +1,632 @@
+//! In-memory [`Storage`] for tests and ephemeral nodes.
+
+use std::{
+    collections::BTreeMap,
+    ops::Bound,
+    sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::{Duration, Instant},
+};
+
+use horizcoin_primitives::HorizError;
+
+use crate::{
+    prefix_end, BatchOp, KeyValue, KeyValueIter, Snapshot, Storage, WriteBatch, DEFAULT_CF,
+};
+
+type Family = BTreeMap<Vec<u8>, Entry>;
+
+/// A stored value and, if it was written with a TTL, when it expires.
+#[derive(Debug, Clone)]
+struct Entry {
+    value: Vec<u8>,
+    expires_at: Option<Instant>,
+}
+
+impl Entry {
+    const fn new(value: Vec<u8>) -> Self {
+        Self {
+            value,
+            expires_at: None,
+        }
+    }
+
+    fn is_live(&self, now: Instant) -> bool {
+        self.expires_at.map_or(true, |expires_at| now < expires_at)
+    }
+}
+
+/// A [`Storage`] backed by one `BTreeMap` per column family.
+///
+/// Entries written with [`Storage::put_with_ttl`] read as absent once they
+/// expire but keep their memory until [`purge_expired`](Self::purge_expired)
+/// runs.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    families: RwLock<BTreeMap<String, Family>>,
+}
+
+impl MemoryStorage {
+    /// Creates an empty store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a deep copy of the store's current contents.
+    pub fn checkpoint(&self) -> Result<Self, HorizError> {
+        let copy = Self::new();
+        self.checkpoint_into(&copy)?;
+        Ok(copy)
+    }
+
+    /// Drops every expired entry and returns how many were removed.
+    pub fn purge_expired(&self) -> usize {
+        let now = Instant::now();
+        self.write()
+            .values_mut()
+            .map(|family| {
+                let before = family.len();
+                family.retain(|_, entry| entry.is_live(now));
+                before - family.len()
+            })
+            .sum()
+    }
+
+    // Every write completes while holding the lock, so a panic elsewhere
+    // cannot leave a family half-updated and poisoning can be ignored.
+    fn read(&self) -> RwLockReadGuard<'_, BTreeMap<String, Family>> {
+        self.families.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, BTreeMap<String, Family>> {
+        self.families
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get_cf(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>, HorizError> {
+        Ok(self
+            .read()
+            .get(cf)
+            .and_then(|family| live_value(family, key, Instant::now())))
+    }
+
+    fn put_cf(&self, cf: &str, key: &[u8], value: &[u8]) -> Result<(), HorizError> {
+        self.write()
+            .entry(cf.to_owned())
+            .or_default()
+            .insert(key.to_vec(), Entry::new(value.to_vec()));
+        Ok(())
+    }
+
+    fn delete_cf(&self, cf: &str, key: &[u8]) -> Result<(), HorizError> {
+        if let Some(family) = self.write().get_mut(cf) {
+            family.remove(key);
+        }
+        Ok(())
+    }
+
+    fn scan_prefix_cf(&self, cf: &str, prefix: &[u8]) -> Result<Vec<KeyValue>, HorizError> {
+        let now = Instant::now();
+        Ok(self
+            .read()
+            .get(cf)
+            .map_or_else(Vec::new, |family| prefix_entries(family, prefix, now)))
+    }
+
+    fn scan_range_cf(
+        &self,
+        cf: &str,
+        start: &[u8],
+        end: &[u8],
+    ) -> Result<Vec<KeyValue>, HorizError> {
+        if start >= end {
+            return Ok(Vec::new());
+        }
+        let now = Instant::now();
+        Ok(self.read().get(cf).map_or_else(Vec::new, |family| {
+            family
+                .range(start.to_vec()..end.to_vec())
+                .filter(|(_, entry)| entry.is_live(now))
+                .map(|(key, entry)| (key.clone(), entry.value.clone()))
+                .collect()
+        }))
+    }
+
+    fn scan_prefix_iter_cf(&self, cf: &str, prefix: &[u8]) -> KeyValueIter<'_> {
+        Box::new(RangeIter::new(self, cf, prefix, prefix_end(prefix)))
+    }
+
+    fn scan_range_iter_cf(&self, cf: &str, start: &[u8], end: &[u8]) -> KeyValueIter<'_> {
+        Box::new(RangeIter::new(self, cf, start, Some(end.to_vec())))
+    }
+
+    fn write_batch(&self, batch: WriteBatch) -> Result<(), HorizError> {
+        let mut families = self.write();
+        for op in batch.into_ops() {
+            match op {
+                BatchOp::Put { cf, key, value } => {
+                    families
+                        .entry(cf)
+                        .or_default()
+                        .insert(key, Entry::new(value));
+                }
+                BatchOp::Delete { cf, key } => {
+                    if let Some(family) = families.get_mut(&cf) {
+                        family.remove(&key);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, HorizError> {
+        let now = Instant::now();
+        let mut families = self.write();
+        let family = families.entry(DEFAULT_CF.to_owned()).or_default();
+        let current = family
+            .get(key)
+            .filter(|entry| entry.is_live(now))
+            .map(|entry| entry.value.as_slice());
+        let swapped = current == expected;
+        if swapped {
+            family.insert(key.to_vec(), Entry::new(new.to_vec()));
+        }
+        drop(families);
+        Ok(swapped)
+    }
+
+    fn put_with_ttl(&self, key: &[u8], value: &[u8], ttl: Duration) -> Result<(), HorizError> {
+        let entry = Entry {
+            value: value.to_vec(),
+            // A TTL too large to represent never expires.
+            expires_at: Instant::now().checked_add(ttl),
+        };
+        self.write()
+            .entry(DEFAULT_CF.to_owned())
+            .or_default()
+            .insert(key.to_vec(), entry);
+        Ok(())
+    }
+
+    fn column_families(&self) -> Result<Vec<String>, HorizError> {
+        Ok(self.read().keys().cloned().collect())
+    }
+
+    fn delete_prefix(&self, prefix: &[u8]) -> Result<usize, HorizError> {
+        let now = Instant::now();
+        let mut families = self.write();
+        let Some(family) = families.get_mut(DEFAULT_CF) else {
+            return Ok(0);
+        };
+        let keys: Vec<Vec<u8>> = family
+            .range(prefix.to_vec()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.clone())
+            .collect();
+        let mut removed = 0;
+        for key in keys {
+            if family.remove(&key).is_some_and(|entry| entry.is_live(now)) {
+                removed += 1;
+            }
+        }
+        drop(families);
+        Ok(removed)
+    }
+
+    /// Does nothing: there is nowhere more durable to put the data.
+    fn flush(&self) -> Result<(), HorizError> {
+        Ok(())
+    }
+
+    fn snapshot(&self) -> Box<dyn Snapshot + '_> {
+        Box::new(MemorySnapshot {
+            families: self.read().clone(),
+            taken_at: Instant::now(),
+        })
+    }
+}
+
+/// A lazy scan of one family.
+///
+/// The read lock is taken per item rather than held for the life of the
+/// iterator, so callers may write to the store while scanning. Each step
+/// resumes after the last key returned, so entries inserted behind the
+/// cursor are not seen and entries ahead of it are.
+struct RangeIter<'a> {
+    storage: &'a MemoryStorage,
+    cf: String,
+    /// Lower bound of the next lookup; `None` once the scan is exhausted.
+    cursor: Option<Bound<Vec<u8>>>,
+    end: Option<Vec<u8>>,
+}
+
+impl<'a> RangeIter<'a> {
+    fn new(storage: &'a MemoryStorage, cf: &str, start: &[u8], end: Option<Vec<u8>>) -> Self {
+        // `BTreeMap::range` panics on inverted bounds.
+        let empty = end.as_deref().is_some_and(|end| start >= end);
+        Self {
+            storage,
+            cf: cf.to_owned(),
+            cursor: (!empty).then(|| Bound::Included(start.to_vec())),
+            end,
+        }
+    }
+}
+
+impl Iterator for RangeIter<'_> {
+    type Item = Result<KeyValue, HorizError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cursor = self.cursor.as_ref()?;
+        let start = match cursor {
+            Bound::Included(key) => Bound::Included(key.as_slice()),
+            Bound::Excluded(key) => Bound::Excluded(key.as_slice()),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let bounds = (
+            start,
+            self.end
+                .as_deref()
+                .map_or(Bound::Unbounded, Bound::Excluded),
+        );
+        let now = Instant::now();
+        let entry = self.storage.read().get(&self.cf).and_then(|family| {
+            family
+                .range::<[u8], _>(bounds)
+                .find(|(_, entry)| entry.is_live(now))
+                .map(|(key, entry)| (key.clone(), entry.value.clone()))
+        });
+        self.cursor = entry.as_ref().map(|(key, _)| Bound::Excluded(key.clone()));
+        entry.map(Ok)
+    }
+}
+
+/// A copy of every family taken under the read lock.
+///
+/// Expiry is judged as of `taken_at`, so a snapshot's contents never change.
+struct MemorySnapshot {
+    families: BTreeMap<String, Family>,
+    taken_at: Instant,
+}
+
+impl Snapshot for MemorySnapshot {
+    fn get_cf(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>, HorizError> {
+        Ok(self
+            .families
+            .get(cf)
+            .and_then(|family| live_value(family, key, self.taken_at)))
+    }
+
+    fn scan_prefix_cf(&self, cf: &str, prefix: &[u8]) -> Result<Vec<KeyValue>, HorizError> {
+        Ok(self.families.get(cf).map_or_else(Vec::new, |family| {
+            prefix_entries(family, prefix, self.taken_at)
+        }))
+    }
+}
+
+fn live_value(family: &Family, key: &[u8], now: Instant) -> Option<Vec<u8>> {
+    family
+        .get(key)
+        .filter(|entry| entry.is_live(now))
+        .map(|entry| entry.value.clone())
+}
+
+fn prefix_entries(family: &Family, prefix: &[u8], now: Instant) -> Vec<KeyValue> {
+    family
+        .range(prefix.to_vec()..)
+        .take_while(|(key, _)| key.starts_with(prefix))
+        .filter(|(_, entry)| entry.is_live(now))
+        .map(|(key, entry)| (key.clone(), entry.value.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_operations() {
+        let store = MemoryStorage::new();
+        assert_eq!(store.get(b"a").unwrap(), None);
+        store.put(b"a", b"1").unwrap();
+        assert_eq!(store.get(b"a").unwrap(), Some(b"1".to_vec()));
+        assert!(store.exists(b"a").unwrap());
+        store.delete(b"a").unwrap();
+        assert!(!store.exists(b"a").unwrap());
+        store.delete(b"a").unwrap();
+    }
+
+    #[test]
+    fn scans_are_ordered_and_bounded() {
+        let store = MemoryStorage::new();
+        for key in [&b"b2"[..], b"a1", b"b1", b"c1", b"b"] {
+            store.put(key, key).unwrap();
+        }
+        let keys = |entries: Vec<KeyValue>| -> Vec<Vec<u8>> {
+            entries.into_iter().map(|(key, _)| key).collect()
+        };
+        assert_eq!(
+            keys(store.scan_prefix(b"b").unwrap()),
+            [&b"b"[..], b"b1", b"b2"]
+        );
+        assert_eq!(
+            keys(store.scan_range(b"a1", b"b2").unwrap()),
+            [&b"a1"[..], b"b", b"b1"]
+        );
+        assert!(store.scan_range(b"c", b"a").unwrap().is_empty());
+    }
+
+    #[test]
+    fn flush_keeps_data_readable() {
+        let store = MemoryStorage::new();
+        store.put(b"tip", b"block 9").unwrap();
+        store.flush().unwrap();
+        assert_eq!(store.get(b"tip").unwrap(), Some(b"block 9".to_vec()));
+    }
+
+    #[test]
+    fn batch_applies_in_order() {
+        let store = MemoryStorage::new();
+        store.put(b"gone", b"x").unwrap();
+        let mut batch = WriteBatch::new();
+        batch
+            .put(b"k", b"1")
+            .put(b"k", b"2")
+            .delete(b"gone")
+            .put_cf("other", b"k", b"3");
+        store.write_batch(batch).unwrap();
+        assert_eq!(store.get(b"k").unwrap(), Some(b"2".to_vec()));
+        assert_eq!(store.get(b"gone").unwrap(), None);
+        assert_eq!(store.get_cf("other", b"k").unwrap(), Some(b"3".to_vec()));
+    }
+
+    #[test]
+    fn put_many_writes_all_or_nothing() {
+        let key = |i: u32| [b"utxo/".as_slice(), &i.to_be_bytes()].concat();
+        let store = MemoryStorage::new();
+        store
+            .put_many((0..1_000).map(|i| (key(i), i.to_le_bytes().to_vec())))
+            .unwrap();
+        assert_eq!(store.scan_prefix(b"utxo/").unwrap().len(), 1_000);
+        for i in [0, 499, 999] {
+            assert_eq!(store.get(&key(i)).unwrap(), Some(i.to_le_bytes().to_vec()));
+        }
+
+        // Input that fails part way through leaves nothing behind.
+        let fresh = MemoryStorage::new();
+        let failed = std::panic::catch_unwind(|| {
+            fresh.put_many((0..1_000).map(|i| {
+                assert!(i < 500, "input failed");
+                (key(i), b"coin".to_vec())
+            }))
+        });
+        assert!(failed.is_err());
+        assert!(fresh.scan_prefix(b"").unwrap().is_empty());
+    }
+
+    #[test]
+    fn delete_prefix_leaves_other_prefixes() {
+        let store = MemoryStorage::new();
+        for key in [
+            &b"old/1"[..],
+            b"old/2",
+            b"old/3",
+            b"old",
+            b"olden",
+            b"new/1",
+        ] {
+            store.put(key, b"v").unwrap();
+        }
+        store.put_with_ttl(b"old/4", b"v", Duration::ZERO).unwrap();
+        store.put_cf("blocks", b"old/1", b"v").unwrap();
+
+        assert_eq!(store.delete_prefix(b"old/").unwrap(), 3);
+        assert!(store.scan_prefix(b"old/").unwrap().is_empty());
+        assert_eq!(store.purge_expired(), 0);
+        let keys: Vec<_> = store
+            .scan_prefix(b"")
+            .unwrap()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, [&b"new/1"[..], b"old", b"olden"]);
+        assert!(store.get_cf("blocks", b"old/1").unwrap().is_some());
+
+        assert_eq!(store.delete_prefix(b"old/").unwrap(), 0);
+        assert_eq!(store.delete_prefix(b"").unwrap(), 3);
+        assert_eq!(MemoryStorage::new().delete_prefix(b"x").unwrap(), 0);
+    }
+
+    #[test]
+    fn column_families_do_not_collide() {
+        let store = MemoryStorage::new();
+        store.put(b"key", b"default").unwrap();
+        store.put_cf("blocks", b"key", b"blocks").unwrap();
+        store.put_cf("blocks", b"key2", b"blocks2").unwrap();
+        store.put_cf("utxos", b"key3", b"utxos").unwrap();
+
+        assert_eq!(store.get(b"key").unwrap(), Some(b"default".to_vec()));
+        assert_eq!(
+            store.get_cf("blocks", b"key").unwrap(),
+            Some(b"blocks".to_vec())
+        );
+        assert_eq!(store.get_cf("utxos", b"key").unwrap(), None);
+
+        let scanned = store.scan_prefix_cf("blocks", b"key").unwrap();
+        assert_eq!(
+            scanned,
+            [
+                (b"key".to_vec(), b"blocks".to_vec()),
+                (b"key2".to_vec(), b"blocks2".to_vec())
+            ]
+        );
+        assert_eq!(store.scan_prefix(b"key").unwrap().len(), 1);
+
+        store.delete_cf("blocks", b"key").unwrap();
+        assert_eq!(store.get(b"key").unwrap(), Some(b"default".to_vec()));
+    }
+
+    #[test]
+    fn snapshot_ignores_later_writes() {
+        let store = MemoryStorage::new();
+        store.put(b"utxo:1", b"a").unwrap();
+        store.put_cf("blocks", b"tip", b"1").unwrap();
+
+        let snapshot = store.snapshot();
+        store.put(b"utxo:2", b"b").unwrap();
+        store.put(b"utxo:1", b"changed").unwrap();
+        store.put_cf("blocks", b"tip", b"2").unwrap();
+
+        assert_eq!(snapshot.get(b"utxo:1").unwrap(), Some(b"a".to_vec()));
+        assert_eq!(snapshot.get(b"utxo:2").unwrap(), None);
+        assert_eq!(snapshot.scan_prefix(b"utxo:").unwrap().len(), 1);
+        assert_eq!(
+            snapshot.get_cf("blocks", b"tip").unwrap(),
+            Some(b"1".to_vec())
+        );
+        assert_eq!(store.scan_prefix(b"utxo:").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn compare_and_swap_is_atomic_across_threads() {
+        const THREADS: u8 = 8;
+        const INCREMENTS: u64 = 50;
+
+        let store = MemoryStorage::new();
+        let claims: usize = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..THREADS)
+                .map(|i| {
+                    let store = &store;
+                    scope.spawn(move || store.compare_and_swap(b"owner", None, &[i]).unwrap())
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|w| usize::from(w.join().unwrap()))
+                .sum()
+        });
+        assert_eq!(claims, 1);
+
+        store.put(b"counter", &0u64.to_le_bytes()).unwrap();
+        std::thread::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    for _ in 0..INCREMENTS {
+                        loop {
+                            let current = store.get(b"counter").unwrap().unwrap();
+                            let value = u64::from_le_bytes(current.as_slice().try_into().unwrap());
+                            let next = (value + 1).to_le_bytes();
+                            if store
+                                .compare_and_swap(b"counter", Some(&current), &next)
+                                .unwrap()
+                            {
+                                break;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        let total = store.get(b"counter").unwrap().unwrap();
+        assert_eq!(total, (u64::from(THREADS) * INCREMENTS).to_le_bytes());
+        assert!(!store.compare_and_swap(b"counter", None, b"x").unwrap());
+    }
+
+    #[test]
+    fn iterators_are_lazy() {
+        let store = MemoryStorage::new();
+        for i in 0..10_000u32 {
+            store
+                .put(&[b"utxo/".as_slice(), &i.to_be_bytes()].concat(), b"coin")
+                .unwrap();
+        }
+        store.put(b"v", b"after").unwrap();
+
+        let mut scan = store.scan_prefix_iter(b"utxo/");
+        let first: Vec<_> = scan.by_ref().take(3).map(Result::unwrap).collect();
+        assert_eq!(first[0].0, b"utxo/\0\0\0\0");
+        assert_eq!(first[2].0, b"utxo/\0\0\0\x02");
+
+        // The lock is not held between items, so writing mid-scan is fine.
+        store
+            .delete(&[b"utxo/".as_slice(), &3u32.to_be_bytes()].concat())
+            .unwrap();
+        assert_eq!(scan.next().unwrap().unwrap().0, b"utxo/\0\0\0\x04");
+        assert_eq!(scan.count(), 10_000 - 5);
+
+        let range: Vec<_> = store
+            .scan_range_iter(b"utxo/\0\0\0\x05", b"utxo/\0\0\0\x08")
+            .map(|entry| entry.unwrap().0)
+            .collect();
+        assert_eq!(range.len(), 3);
+        assert_eq!(store.scan_range_iter(b"b", b"a").count(), 0);
+        // Every remaining coin plus `v`.
+        assert_eq!(store.scan_prefix_iter(b"").count(), 9_999 + 1);
+        assert_eq!(store.scan_prefix_iter_cf("missing", b"").count(), 0);
+    }
+
+    #[test]
+    fn expired_entries_read_as_absent() {
+        let store = MemoryStorage::new();
+        store
+            .put_with_ttl(b"peer", b"addr", Duration::ZERO)
+            .unwrap();
+        store
+            .put_with_ttl(b"tx", b"entry", Duration::from_secs(3600))
+            .unwrap();
+        store.put(b"plain", b"kept").unwrap();
+
+        assert_eq!(store.get(b"peer").unwrap(), None);
+        assert!(!store.exists(b"peer").unwrap());
+        assert!(store.exists(b"tx").unwrap());
+        let keys: Vec<_> = store
+            .scan_prefix(b"")
+            .unwrap()
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(keys, [b"plain".to_vec(), b"tx".to_vec()]);
+        assert_eq!(store.scan_prefix_iter(b"").count(), 2);
+
+        // An expired key counts as absent for compare-and-swap too.
+        assert!(store.compare_and_swap(b"peer", None, b"new").unwrap());
+        store
+            .put_with_ttl(b"peer", b"addr", Duration::ZERO)
+            .unwrap();
+
+        assert_eq!(store.purge_expired(), 1);
+        assert_eq!(store.purge_expired(), 0);
+        assert_eq!(store.get(b"tx").unwrap(), Some(b"entry".to_vec()));
+    }
+
+    #[test]
+    fn checkpoint_copies_every_family() {
+        let store = MemoryStorage::new();
+        store.put(b"tip", b"block 9").unwrap();
+        store.put_cf("blocks", b"9", b"header").unwrap();
+        store.put_cf("utxos", b"a:0", b"50").unwrap();
+
+        let copy = store.checkpoint().unwrap();
+        store.put(b"tip", b"block 10").unwrap();
+
+        assert_eq!(copy.get(b"tip").unwrap(), Some(b"block 9".to_vec()));
+        assert_eq!(
+            copy.get_cf("blocks", b"9").unwrap(),
+            Some(b"header".to_vec())
+        );
+        assert_eq!(copy.get_cf("utxos", b"a:0").unwrap(), Some(b"50".to_vec()));
+        assert_eq!(
+            copy.column_families().unwrap(),
+            store.column_families().unwrap()
+        );
+    }
+}
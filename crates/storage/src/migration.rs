@@ -0,0 +1,230 @@
+//! Ordered, resumable upgrades of the on-disk format.
+
+use std::{fmt, sync::Arc};
+
+use horizcoin_primitives::HorizError;
+
+use crate::Storage;
+
+/// Key, in the default column family, holding the [`SchemaVersion`]; the
+/// `schema_version` entry of [`KeyspacePrefix::Meta`](crate::KeyspacePrefix::Meta).
+pub const SCHEMA_VERSION_KEY: &[u8] = b"\x04schema_version";
+
+/// The number of [`Migration`]s that have been applied to a store.
+///
+/// A store that was never migrated is at version `0`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SchemaVersion(pub u32);
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
+type MigrationFn = dyn Fn(&dyn Storage) -> Result<(), HorizError> + Send + Sync;
+
+/// One step of the on-disk format; the `n`th migration in a list takes a
+/// store from version `n - 1` to `n`.
+pub struct Migration {
+    description: &'static str,
+    run: Box<MigrationFn>,
+}
+
+impl Migration {
+    /// Creates a migration that applies `run` to the store.
+    pub fn new(
+        description: &'static str,
+        run: impl Fn(&dyn Storage) -> Result<(), HorizError> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            description,
+            run: Box::new(run),
+        }
+    }
+
+    /// Returns the human-readable summary given to [`new`](Self::new).
+    #[must_use]
+    pub const fn description(&self) -> &'static str {
+        self.description
+    }
+}
+
+impl fmt::Debug for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migration")
+            .field("description", &self.description)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Applies [`Migration`]s to a [`Storage`], recording progress under
+/// [`SCHEMA_VERSION_KEY`].
+#[derive(Clone)]
+pub struct Migrator {
+    storage: Arc<dyn Storage>,
+}
+
+impl fmt::Debug for Migrator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migrator").finish_non_exhaustive()
+    }
+}
+
+impl Migrator {
+    /// Creates a migrator for `storage`.
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Returns the version recorded in the store.
+    pub fn schema_version(&self) -> Result<SchemaVersion, HorizError> {
+        Ok(self
+            .storage
+            .get(SCHEMA_VERSION_KEY)?
+            .map(|bytes| horizcoin_codec::decode_exact(&bytes))
+            .transpose()?
+            .map(SchemaVersion)
+            .unwrap_or_default())
+    }
+
+    /// Runs, in order, every migration in `migrations` the store has not
+    /// had yet.
+    ///
+    /// The version is bumped after each migration, so a run interrupted by
+    /// an error or a crash resumes with the migration that did not finish;
+    /// a migration should therefore tolerate being re-run after failing
+    /// part way. Fails if the store is newer than `migrations` knows about,
+    /// or if another migrator moves the version concurrently.
+    pub fn migrate(&self, migrations: &[Migration]) -> Result<(), HorizError> {
+        let current = self.schema_version()?;
+        let applied = usize::try_from(current.0).unwrap_or(usize::MAX);
+        if applied > migrations.len() {
+            return Err(HorizError::Storage(format!(
+                "store is at schema {current}, newer than the {} known migrations",
+                migrations.len()
+            )));
+        }
+
+        let mut expected = self.storage.get(SCHEMA_VERSION_KEY)?;
+        for (migration, version) in migrations[applied..].iter().zip(current.0 + 1..) {
+            (migration.run)(self.storage.as_ref()).map_err(|e| {
+                HorizError::Storage(format!(
+                    "migration to schema v{version} ({}) failed: {e}",
+                    migration.description
+                ))
+            })?;
+            let new = horizcoin_codec::encode(&version)?;
+            if !self
+                .storage
+                .compare_and_swap(SCHEMA_VERSION_KEY, expected.as_deref(), &new)?
+            {
+                return Err(HorizError::Storage(format!(
+                    "schema version changed concurrently while migrating to v{version}"
+                )));
+            }
+            expected = Some(new);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{KeyspacePrefix, MemoryStorage};
+
+    fn counting(runs: &Arc<[AtomicUsize; 3]>) -> Vec<Migration> {
+        (0..3)
+            .map(|i| {
+                let runs = Arc::clone(runs);
+                Migration::new("count", move |storage| {
+                    runs[i].fetch_add(1, Ordering::SeqCst);
+                    storage.put(
+                        &KeyspacePrefix::Meta.make_key(&[u8::try_from(i).unwrap()]),
+                        b"done",
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn counters() -> Arc<[AtomicUsize; 3]> {
+        Arc::new([
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+        ])
+    }
+
+    fn run_counts(runs: &[AtomicUsize; 3]) -> Vec<usize> {
+        runs.iter()
+            .map(|count| count.load(Ordering::SeqCst))
+            .collect()
+    }
+
+    #[test]
+    fn fresh_store_runs_every_migration_once() {
+        let migrator = Migrator::new(Arc::new(MemoryStorage::new()));
+        assert_eq!(migrator.schema_version(), Ok(SchemaVersion(0)));
+        assert_eq!(
+            SCHEMA_VERSION_KEY,
+            KeyspacePrefix::Meta.make_key(b"schema_version")
+        );
+
+        let runs = counters();
+        let migrations = counting(&runs);
+        migrator.migrate(&migrations).unwrap();
+        assert_eq!(run_counts(&runs), [1, 1, 1]);
+        assert_eq!(migrator.schema_version(), Ok(SchemaVersion(3)));
+
+        migrator.migrate(&migrations).unwrap();
+        assert_eq!(run_counts(&runs), [1, 1, 1]);
+    }
+
+    #[test]
+    fn partially_migrated_store_runs_the_rest() {
+        let migrator = Migrator::new(Arc::new(MemoryStorage::new()));
+        let runs = counters();
+        let migrations = counting(&runs);
+
+        migrator.migrate(&migrations[..1]).unwrap();
+        assert_eq!(migrator.schema_version(), Ok(SchemaVersion(1)));
+        migrator.migrate(&migrations).unwrap();
+        assert_eq!(run_counts(&runs), [1, 1, 1]);
+        assert_eq!(migrator.schema_version(), Ok(SchemaVersion(3)));
+
+        // A binary that knows fewer migrations than the store has refuses.
+        assert!(migrator.migrate(&migrations[..2]).is_err());
+    }
+
+    #[test]
+    fn failed_migration_is_retried() {
+        let migrator = Migrator::new(Arc::new(MemoryStorage::new()));
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let flaky = {
+            let attempts = Arc::clone(&attempts);
+            Migration::new("flaky", move |_| {
+                if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                    Err(HorizError::Storage("disk full".into()))
+                } else {
+                    Ok(())
+                }
+            })
+        };
+        let migrations = [Migration::new("noop", |_| Ok(())), flaky];
+
+        assert_eq!(
+            migrator.migrate(&migrations),
+            Err(HorizError::Storage(
+                "migration to schema v2 (flaky) failed: storage error: disk full".into()
+            ))
+        );
+        assert_eq!(migrator.schema_version(), Ok(SchemaVersion(1)));
+        migrator.migrate(&migrations).unwrap();
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(migrator.schema_version(), Ok(SchemaVersion(2)));
+    }
+}
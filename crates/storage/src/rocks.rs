@@ -0,0 +1,736 @@
+//! Persistent [`Storage`] on `RocksDB`.
+
+use std::{
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use horizcoin_primitives::HorizError;
+use rocksdb::{
+    checkpoint::Checkpoint,
+    properties::{self, PropName},
+    statistics::Ticker,
+    BlockBasedOptions, BoundColumnFamily, Cache, DBCompressionType, DBWithThreadMode, Direction,
+    IteratorMode, MultiThreaded, Options, SnapshotWithThreadMode,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    prefix_end, BatchOp, KeyValue, KeyValueIter, Snapshot, Storage, WriteBatch, DEFAULT_CF,
+};
+
+type Db = DBWithThreadMode<MultiThreaded>;
+
+/// Envelope tag of a value stored without a TTL.
+const PLAIN_VALUE: u8 = 0;
+/// Envelope tag of a value followed by its big-endian expiry in Unix
+/// milliseconds.
+const EXPIRING_VALUE: u8 = 1;
+const EXPIRY_LEN: usize = 8;
+
+/// Tuning knobs for [`RocksDbStorage::with_config`].
+///
+/// Every field maps onto one `rocksdb::Options` setting, and the settings
+/// apply to every column family. The defaults are those used by
+/// [`RocksDbStorage::new`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RocksDbConfig {
+    /// Compression applied to SST blocks.
+    pub compression: DBCompressionType,
+    /// Capacity of the shared LRU block cache, in bytes.
+    pub block_cache_size: usize,
+    /// Size of each memtable before it is flushed, in bytes.
+    pub write_buffer_size: usize,
+    /// Maximum number of files kept open, or `-1` for no limit.
+    pub max_open_files: i32,
+}
+
+impl Default for RocksDbConfig {
+    fn default() -> Self {
+        Self {
+            compression: DBCompressionType::Lz4,
+            block_cache_size: 32 << 20,
+            write_buffer_size: 64 << 20,
+            max_open_files: -1,
+        }
+    }
+}
+
+impl RocksDbConfig {
+    fn to_options(self) -> Options {
+        let mut table = BlockBasedOptions::default();
+        table.set_block_cache(&Cache::new_lru_cache(self.block_cache_size));
+
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        options.set_compression_type(self.compression);
+        options.set_block_based_table_factory(&table);
+        options.set_write_buffer_size(self.write_buffer_size);
+        options.set_max_open_files(self.max_open_files);
+        options
+    }
+}
+
+/// A [`Storage`] backed by a `RocksDB` database.
+///
+/// Column families are real `RocksDB` column families. Families that do
+/// not exist yet are created on first use.
+///
+/// Every value is stored behind a one-byte envelope tag so that entries
+/// written with [`Storage::put_with_ttl`] can carry their expiry. Expired
+/// entries are filtered out on read; they are not reclaimed from disk.
+pub struct RocksDbStorage {
+    db: Db,
+    /// Options the database was opened with; they own its statistics.
+    options: Options,
+    /// Serializes [`Storage::compare_and_swap`] read-check-write sections.
+    cas_lock: Mutex<()>,
+}
+
+impl RocksDbStorage {
+    /// Opens or creates the database at `path` with the default
+    /// [`RocksDbConfig`]: `LZ4` compression and `RocksDB`'s usual sizes.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, HorizError> {
+        Self::with_config(path, RocksDbConfig::default())
+    }
+
+    /// Opens or creates the database at `path` tuned by `config`.
+    pub fn with_config(path: impl AsRef<Path>, config: RocksDbConfig) -> Result<Self, HorizError> {
+        Self::open(path.as_ref(), config.to_options())
+    }
+
+    /// Writes a consistent copy of the database to `dest`, which must not
+    /// exist yet.
+    ///
+    /// SST files are hard-linked when `dest` is on the same filesystem, so
+    /// this is fast and cheap, and writes are blocked only while the
+    /// memtables are flushed. The copy opens with [`RocksDbStorage::new`].
+    pub fn checkpoint<P: AsRef<Path>>(&self, dest: P) -> Result<(), HorizError> {
+        Checkpoint::new(&self.db)
+            .and_then(|checkpoint| checkpoint.create_checkpoint(dest))
+            .map_err(storage_error)
+    }
+
+    /// Returns size and cache counters for the whole database.
+    ///
+    /// Key counts and sizes are summed over every column family. The key
+    /// count is `RocksDB`'s estimate and can be off after many overwrites
+    /// or deletes; cache counters are cumulative since the store opened.
+    pub fn stats(&self) -> Result<StorageStats, HorizError> {
+        let families = self.column_families()?;
+        let mut stats = StorageStats {
+            block_cache_hits: self.options.get_ticker_count(Ticker::BlockCacheHit),
+            block_cache_misses: self.options.get_ticker_count(Ticker::BlockCacheMiss),
+            ..StorageStats::default()
+        };
+        for family in families {
+            let handle = self.cf(&family)?;
+            let property = |name: &PropName| {
+                self.db
+                    .property_int_value_cf(&handle, name)
+                    .map(Option::unwrap_or_default)
+                    .map_err(storage_error)
+            };
+            stats.estimate_num_keys += property(properties::ESTIMATE_NUM_KEYS)?;
+            stats.total_sst_size += property(properties::TOTAL_SST_FILES_SIZE)?;
+        }
+        Ok(stats)
+    }
+
+    fn open(path: &Path, mut options: Options) -> Result<Self, HorizError> {
+        options.enable_statistics();
+        // A new database has no families to list yet.
+        let families = Db::list_cf(&options, path).unwrap_or_else(|_| vec![DEFAULT_CF.to_owned()]);
+        let db = Db::open_cf(&options, path, families).map_err(storage_error)?;
+        Ok(Self {
+            db,
+            options,
+            cas_lock: Mutex::new(()),
+        })
+    }
+
+    fn cf(&self, name: &str) -> Result<Arc<BoundColumnFamily<'_>>, HorizError> {
+        if let Some(handle) = self.db.cf_handle(name) {
+            return Ok(handle);
+        }
+        if let Err(e) = self.db.create_cf(name, &self.options) {
+            // Another thread may have created it first.
+            return self.db.cf_handle(name).ok_or_else(|| storage_error(e));
+        }
+        self.db
+            .cf_handle(name)
+            .ok_or_else(|| HorizError::Storage(format!("column family {name} not found")))
+    }
+
+    fn range_iter(&self, cf: &str, start: &[u8], end: Option<Vec<u8>>) -> KeyValueIter<'_> {
+        let handle = match self.cf(cf) {
+            Ok(handle) => handle,
+            Err(e) => return Box::new(std::iter::once(Err(e))),
+        };
+        let entries = self
+            .db
+            .iterator_cf(&handle, IteratorMode::From(start, Direction::Forward))
+            .take_while(move |entry| match entry {
+                Ok((key, _)) => end.as_deref().map_or(true, |end| &**key < end),
+                Err(_) => true,
+            });
+        Box::new(live_entries(entries, unix_millis()))
+    }
+}
+
+/// Size and cache counters reported by [`RocksDbStorage::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageStats {
+    /// Estimated number of live keys.
+    pub estimate_num_keys: u64,
+    /// Total size of the SST files on disk, in bytes. Data still in
+    /// memtables is not included.
+    pub total_sst_size: u64,
+    /// Block-cache lookups that found their block.
+    pub block_cache_hits: u64,
+    /// Block-cache lookups that had to read from disk.
+    pub block_cache_misses: u64,
+}
+
+impl std::fmt::Debug for RocksDbStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RocksDbStorage")
+            .field("path", &self.db.path())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Storage for RocksDbStorage {
+    fn get_cf(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>, HorizError> {
+        let raw = self.db.get_cf(&self.cf(cf)?, key).map_err(storage_error)?;
+        open_envelope(raw, unix_millis())
+    }
+
+    fn put_cf(&self, cf: &str, key: &[u8], value: &[u8]) -> Result<(), HorizError> {
+        self.db
+            .put_cf(&self.cf(cf)?, key, seal(value, None))
+            .map_err(storage_error)
+    }
+
+    fn delete_cf(&self, cf: &str, key: &[u8]) -> Result<(), HorizError> {
+        self.db.delete_cf(&self.cf(cf)?, key).map_err(storage_error)
+    }
+
+    fn scan_prefix_cf(&self, cf: &str, prefix: &[u8]) -> Result<Vec<KeyValue>, HorizError> {
+        let handle = self.cf(cf)?;
+        collect_prefix(
+            self.db
+                .iterator_cf(&handle, IteratorMode::From(prefix, Direction::Forward)),
+            prefix,
+            unix_millis(),
+        )
+    }
+
+    fn scan_range_cf(
+        &self,
+        cf: &str,
+        start: &[u8],
+        end: &[u8],
+    ) -> Result<Vec<KeyValue>, HorizError> {
+        self.scan_range_iter_cf(cf, start, end).collect()
+    }
+
+    fn scan_prefix_iter_cf(&self, cf: &str, prefix: &[u8]) -> KeyValueIter<'_> {
+        self.range_iter(cf, prefix, prefix_end(prefix))
+    }
+
+    fn scan_range_iter_cf(&self, cf: &str, start: &[u8], end: &[u8]) -> KeyValueIter<'_> {
+        self.range_iter(cf, start, Some(end.to_vec()))
+    }
+
+    fn write_batch(&self, batch: WriteBatch) -> Result<(), HorizError> {
+        let mut rocks_batch = rocksdb::WriteBatch::default();
+        for op in batch.into_ops() {
+            match op {
+                BatchOp::Put { cf, key, value } => {
+                    rocks_batch.put_cf(&self.cf(&cf)?, key, seal(&value, None));
+                }
+                BatchOp::Delete { cf, key } => rocks_batch.delete_cf(&self.cf(&cf)?, key),
+            }
+        }
+        self.db.write(rocks_batch).map_err(storage_error)
+    }
+
+    fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, HorizError> {
+        let _guard = self.cas_lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let handle = self.cf(DEFAULT_CF)?;
+        let raw = self.db.get_cf(&handle, key).map_err(storage_error)?;
+        if open_envelope(raw, unix_millis())?.as_deref() != expected {
+            return Ok(false);
+        }
+        self.db
+            .put_cf(&handle, key, seal(new, None))
+            .map_err(storage_error)?;
+        Ok(true)
+    }
+
+    fn put_with_ttl(&self, key: &[u8], value: &[u8], ttl: Duration) -> Result<(), HorizError> {
+        let ttl_millis = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+        let expires_at = unix_millis().saturating_add(ttl_millis);
+        self.db
+            .put_cf(&self.cf(DEFAULT_CF)?, key, seal(value, Some(expires_at)))
+            .map_err(storage_error)
+    }
+
+    fn column_families(&self) -> Result<Vec<String>, HorizError> {
+        Db::list_cf(&self.options, self.db.path()).map_err(storage_error)
+    }
+
+    /// Covers the prefix's range with a single range tombstone, which
+    /// snapshots taken earlier do not see. `delete_file_in_range` is not
+    /// used: it includes the range's end key and ignores snapshots. A
+    /// prefix with no upper bound (empty or all `0xff`) falls back to
+    /// deleting key by key.
+    ///
+    /// The count comes from a scan just before the delete, so a concurrent
+    /// write to the range may be removed without being counted.
+    fn delete_prefix(&self, prefix: &[u8]) -> Result<usize, HorizError> {
+        let handle = self.cf(DEFAULT_CF)?;
+        let Some(end) = prefix_end(prefix) else {
+            let entries = self.scan_prefix_cf(DEFAULT_CF, prefix)?;
+            let mut batch = rocksdb::WriteBatch::default();
+            // With no upper bound, every key from `prefix` on matches it.
+            // Expired entries are missing from the scan, so walk the keys
+            // directly.
+            for entry in self
+                .db
+                .iterator_cf(&handle, IteratorMode::From(prefix, Direction::Forward))
+            {
+                batch.delete_cf(&handle, entry.map_err(storage_error)?.0);
+            }
+            self.db.write(batch).map_err(storage_error)?;
+            return Ok(entries.len());
+        };
+        let removed = self
+            .scan_prefix_iter_cf(DEFAULT_CF, prefix)
+            .try_fold(0, |removed, entry| entry.map(|_| removed + 1))?;
+        self.db
+            .delete_range_cf(&handle, prefix, &end)
+            .map_err(storage_error)?;
+        Ok(removed)
+    }
+
+    /// Syncs the write-ahead log, then flushes every column family's
+    /// memtable to SST files.
+    fn flush(&self) -> Result<(), HorizError> {
+        self.db.flush_wal(true).map_err(storage_error)?;
+        for name in self.column_families()? {
+            if let Some(handle) = self.db.cf_handle(&name) {
+                self.db.flush_cf(&handle).map_err(storage_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn snapshot(&self) -> Box<dyn Snapshot + '_> {
+        Box::new(RocksDbSnapshot {
+            storage: self,
+            snapshot: self.db.snapshot(),
+            taken_at: unix_millis(),
+        })
+    }
+}
+
+/// A `RocksDB` snapshot; reads see the database as of its creation.
+///
+/// Expiry is judged as of `taken_at`, so a snapshot's contents never change.
+struct RocksDbSnapshot<'a> {
+    storage: &'a RocksDbStorage,
+    snapshot: SnapshotWithThreadMode<'a, Db>,
+    taken_at: u64,
+}
+
+impl Snapshot for RocksDbSnapshot<'_> {
+    fn get_cf(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>, HorizError> {
+        // A family created after the snapshot was taken is empty in it.
+        let Some(handle) = self.storage.db.cf_handle(cf) else {
+            return Ok(None);
+        };
+        let raw = self.snapshot.get_cf(&handle, key).map_err(storage_error)?;
+        open_envelope(raw, self.taken_at)
+    }
+
+    fn scan_prefix_cf(&self, cf: &str, prefix: &[u8]) -> Result<Vec<KeyValue>, HorizError> {
+        let Some(handle) = self.storage.db.cf_handle(cf) else {
+            return Ok(Vec::new());
+        };
+        collect_prefix(
+            self.snapshot
+                .iterator_cf(&handle, IteratorMode::From(prefix, Direction::Forward)),
+            prefix,
+            self.taken_at,
+        )
+    }
+}
+
+type RawEntry = Result<(Box<[u8]>, Box<[u8]>), rocksdb::Error>;
+
+fn collect_prefix(
+    entries: impl Iterator<Item = RawEntry>,
+    prefix: &[u8],
+    now: u64,
+) -> Result<Vec<KeyValue>, HorizError> {
+    let entries = entries.take_while(|entry| match entry {
+        Ok((key, _)) => key.starts_with(prefix),
+        Err(_) => true,
+    });
+    live_entries(entries, now).collect()
+}
+
+/// Unwraps each entry's envelope, dropping entries that expired by `now`.
+fn live_entries(
+    entries: impl Iterator<Item = RawEntry>,
+    now: u64,
+) -> impl Iterator<Item = Result<KeyValue, HorizError>> {
+    entries.filter_map(move |entry| match entry {
+        Ok((key, raw)) => open_envelope(Some(raw.into_vec()), now)
+            .transpose()
+            .map(|value| value.map(|value| (key.into_vec(), value))),
+        Err(e) => Some(Err(storage_error(e))),
+    })
+}
+
+fn seal(value: &[u8], expires_at: Option<u64>) -> Vec<u8> {
+    let mut sealed = Vec::with_capacity(1 + EXPIRY_LEN + value.len());
+    match expires_at {
+        None => sealed.push(PLAIN_VALUE),
+        Some(expires_at) => {
+            sealed.push(EXPIRING_VALUE);
+            sealed.extend_from_slice(&expires_at.to_be_bytes());
+        }
+    }
+    sealed.extend_from_slice(value);
+    sealed
+}
+
+/// Strips the envelope from a stored value, returning `None` if it is
+/// missing or expired as of `now`.
+fn open_envelope(raw: Option<Vec<u8>>, now: u64) -> Result<Option<Vec<u8>>, HorizError> {
+    let Some(mut raw) = raw else {
+        return Ok(None);
+    };
+    let body_start = match raw.first() {
+        Some(&PLAIN_VALUE) => 1,
+        Some(&EXPIRING_VALUE) => {
+            let expiry = raw
+                .get(1..=EXPIRY_LEN)
+                .ok_or_else(|| HorizError::Storage("truncated value expiry".into()))?;
+            let mut bytes = [0; EXPIRY_LEN];
+            bytes.copy_from_slice(expiry);
+            if now >= u64::from_be_bytes(bytes) {
+                return Ok(None);
+            }
+            1 + EXPIRY_LEN
+        }
+        Some(tag) => {
+            return Err(HorizError::Storage(format!("unknown value envelope {tag}")));
+        }
+        None => return Err(HorizError::Storage("empty value envelope".into())),
+    };
+    raw.drain(..body_start);
+    Ok(Some(raw))
+}
+
+/// Milliseconds since the Unix epoch. Unlike an `Instant`, this survives a
+/// restart, so stored expiries stay meaningful.
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| {
+            u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+        })
+}
+
+fn storage_error(e: rocksdb::Error) -> HorizError {
+    HorizError::Storage(e.into_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_families_persist_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let store = RocksDbStorage::new(dir.path()).unwrap();
+            store.put(b"key", b"default").unwrap();
+            store.put_cf("blocks", b"key", b"blocks").unwrap();
+            store.put_cf("blocks", b"key2", b"blocks2").unwrap();
+        }
+
+        let store = RocksDbStorage::new(dir.path()).unwrap();
+        assert_eq!(store.get(b"key").unwrap(), Some(b"default".to_vec()));
+        assert_eq!(
+            store.get_cf("blocks", b"key").unwrap(),
+            Some(b"blocks".to_vec())
+        );
+        assert_eq!(store.scan_prefix_cf("blocks", b"key").unwrap().len(), 2);
+        assert_eq!(store.scan_prefix(b"key").unwrap().len(), 1);
+        assert_eq!(
+            store
+                .scan_range_cf("blocks", b"key", b"key2")
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn flushed_writes_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let store = RocksDbStorage::new(dir.path()).unwrap();
+            store.put(b"tip", b"block 9").unwrap();
+            store.put_cf("blocks", b"9", b"header").unwrap();
+            store.flush().unwrap();
+            assert!(store.stats().unwrap().total_sst_size > 0);
+        }
+
+        let store = RocksDbStorage::new(dir.path()).unwrap();
+        assert_eq!(store.get(b"tip").unwrap(), Some(b"block 9".to_vec()));
+        assert_eq!(
+            store.get_cf("blocks", b"9").unwrap(),
+            Some(b"header".to_vec())
+        );
+    }
+
+    #[test]
+    fn delete_prefix_leaves_other_prefixes() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RocksDbStorage::new(dir.path()).unwrap();
+        for i in 0..100u32 {
+            store
+                .put(&[b"old/".as_slice(), &i.to_be_bytes()].concat(), b"v")
+                .unwrap();
+        }
+        store.put(b"old", b"v").unwrap();
+        // The first key past the prefix, where the deleted range ends.
+        store.put(b"old0", b"v").unwrap();
+        store.put(b"new/1", b"v").unwrap();
+        store.put_cf("blocks", b"old/1", b"v").unwrap();
+        store.flush().unwrap();
+
+        let snapshot = store.snapshot();
+        assert_eq!(store.delete_prefix(b"old/").unwrap(), 100);
+        assert!(store.scan_prefix(b"old/").unwrap().is_empty());
+        assert_eq!(store.scan_prefix(b"").unwrap().len(), 3);
+        assert_eq!(store.get(b"old0").unwrap(), Some(b"v".to_vec()));
+        assert_eq!(snapshot.scan_prefix(b"old/").unwrap().len(), 100);
+        drop(snapshot);
+        assert!(store.get_cf("blocks", b"old/1").unwrap().is_some());
+        assert_eq!(store.delete_prefix(b"old/").unwrap(), 0);
+
+        assert_eq!(store.delete_prefix(b"").unwrap(), 3);
+        assert!(store.scan_prefix(b"").unwrap().is_empty());
+    }
+
+    #[test]
+    fn batch_is_applied() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RocksDbStorage::new(dir.path()).unwrap();
+        let mut batch = WriteBatch::new();
+        batch
+            .put(b"a", b"1")
+            .put_cf("other", b"b", b"2")
+            .delete(b"a");
+        store.write_batch(batch).unwrap();
+        assert_eq!(store.get(b"a").unwrap(), None);
+        assert_eq!(store.get_cf("other", b"b").unwrap(), Some(b"2".to_vec()));
+    }
+
+    #[test]
+    fn snapshot_ignores_later_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RocksDbStorage::new(dir.path()).unwrap();
+        store.put(b"utxo:1", b"a").unwrap();
+
+        let snapshot = store.snapshot();
+        store.put(b"utxo:2", b"b").unwrap();
+        store.put(b"utxo:1", b"changed").unwrap();
+
+        assert_eq!(snapshot.get(b"utxo:1").unwrap(), Some(b"a".to_vec()));
+        assert_eq!(snapshot.get(b"utxo:2").unwrap(), None);
+        assert_eq!(snapshot.scan_prefix(b"utxo:").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn compare_and_swap_allows_one_winner() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RocksDbStorage::new(dir.path()).unwrap();
+        let wins: usize = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..8u8)
+                .map(|i| {
+                    let store = &store;
+                    scope.spawn(move || store.compare_and_swap(b"tip", None, &[i]).unwrap())
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|w| usize::from(w.join().unwrap()))
+                .sum()
+        });
+        assert_eq!(wins, 1);
+
+        let current = store.get(b"tip").unwrap().unwrap();
+        assert!(!store
+            .compare_and_swap(b"tip", Some(b"stale"), b"x")
+            .unwrap());
+        assert!(store
+            .compare_and_swap(b"tip", Some(&current), b"x")
+            .unwrap());
+        assert_eq!(store.get(b"tip").unwrap(), Some(b"x".to_vec()));
+    }
+
+    #[test]
+    fn prefix_iterator_is_lazy() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RocksDbStorage::new(dir.path()).unwrap();
+        let mut batch = WriteBatch::new();
+        for i in 0..10_000u32 {
+            batch.put(&[b"utxo/".as_slice(), &i.to_be_bytes()].concat(), b"coin");
+        }
+        store.write_batch(batch).unwrap();
+        store.put(b"v", b"after").unwrap();
+
+        let first: Vec<_> = store
+            .scan_prefix_iter(b"utxo/")
+            .take(3)
+            .map(|entry| entry.unwrap().0)
+            .collect();
+        assert_eq!(first.last().unwrap(), b"utxo/\0\0\0\x02");
+        assert_eq!(store.scan_prefix_iter(b"utxo/").count(), 10_000);
+        assert_eq!(
+            store.scan_range_iter(b"utxo/", b"utxo/\0\0\0\x08").count(),
+            8
+        );
+    }
+
+    #[test]
+    fn expired_entries_read_as_absent() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let store = RocksDbStorage::new(dir.path()).unwrap();
+            store
+                .put_with_ttl(b"peer", b"addr", Duration::ZERO)
+                .unwrap();
+            store
+                .put_with_ttl(b"tx", b"entry", Duration::from_secs(3600))
+                .unwrap();
+            assert_eq!(store.get(b"peer").unwrap(), None);
+            assert!(store
+                .compare_and_swap(b"tx", Some(b"entry"), b"swapped")
+                .is_ok());
+        }
+
+        // Expiries are stored with the value, so they survive a reopen.
+        let store = RocksDbStorage::new(dir.path()).unwrap();
+        store
+            .put_with_ttl(b"mempool", b"entry", Duration::from_secs(3600))
+            .unwrap();
+        assert_eq!(store.get(b"peer").unwrap(), None);
+        assert_eq!(store.get(b"tx").unwrap(), Some(b"swapped".to_vec()));
+        assert_eq!(store.scan_prefix(b"").unwrap().len(), 2);
+        assert_eq!(store.scan_prefix_iter(b"").count(), 2);
+        assert_eq!(
+            store.snapshot().get(b"mempool").unwrap(),
+            Some(b"entry".to_vec())
+        );
+    }
+
+    #[test]
+    fn envelope_round_trips() {
+        assert_eq!(
+            open_envelope(Some(seal(b"v", None)), u64::MAX),
+            Ok(Some(b"v".to_vec()))
+        );
+        assert_eq!(
+            open_envelope(Some(seal(b"v", Some(10))), 9),
+            Ok(Some(b"v".to_vec()))
+        );
+        assert_eq!(open_envelope(Some(seal(b"v", Some(10))), 10), Ok(None));
+        assert!(open_envelope(Some(vec![EXPIRING_VALUE, 0]), 0).is_err());
+        assert!(open_envelope(Some(vec![]), 0).is_err());
+    }
+
+    #[test]
+    fn stats_track_contents() {
+        const KEYS: u64 = 2_000;
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = RocksDbStorage::new(dir.path()).unwrap();
+        let mut batch = WriteBatch::new();
+        for i in 0..KEYS {
+            batch.put_cf("blocks", &i.to_be_bytes(), &[0xab; 64]);
+        }
+        store.write_batch(batch).unwrap();
+        store.db.flush_cf(&store.cf("blocks").unwrap()).unwrap();
+        for i in 0..100u64 {
+            assert!(store.get_cf("blocks", &i.to_be_bytes()).unwrap().is_some());
+        }
+
+        let stats = store.stats().unwrap();
+        assert!(
+            (KEYS / 2..=KEYS * 2).contains(&stats.estimate_num_keys),
+            "{stats:?}"
+        );
+        assert!(stats.total_sst_size > 0);
+        assert!(stats.block_cache_hits + stats.block_cache_misses > 0);
+    }
+
+    #[test]
+    fn custom_config_opens() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = RocksDbConfig {
+            compression: DBCompressionType::Zstd,
+            block_cache_size: 1 << 20,
+            write_buffer_size: 1 << 20,
+            max_open_files: 64,
+        };
+        let store = RocksDbStorage::with_config(dir.path(), config).unwrap();
+        store.put(b"key", b"value").unwrap();
+        store.put_cf("blocks", b"key", b"block").unwrap();
+        assert_eq!(store.get(b"key").unwrap(), Some(b"value".to_vec()));
+        assert_eq!(
+            store.get_cf("blocks", b"key").unwrap(),
+            Some(b"block".to_vec())
+        );
+    }
+
+    #[test]
+    fn checkpoint_reopens_with_every_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RocksDbStorage::new(dir.path().join("live")).unwrap();
+        let mut batch = WriteBatch::new();
+        for i in 0..500u32 {
+            batch.put(&i.to_be_bytes(), b"default");
+            batch.put_cf("blocks", &i.to_be_bytes(), b"blocks");
+        }
+        store.write_batch(batch).unwrap();
+
+        let dest = dir.path().join("checkpoint");
+        store.checkpoint(&dest).unwrap();
+        store.put(b"later", b"x").unwrap();
+        assert!(store.checkpoint(&dest).is_err());
+
+        let copy = RocksDbStorage::new(&dest).unwrap();
+        assert_eq!(
+            copy.scan_prefix(b"").unwrap(),
+            store.scan_range(b"", b"later").unwrap()
+        );
+        assert_eq!(copy.scan_prefix_cf("blocks", b"").unwrap().len(), 500);
+        assert_eq!(copy.get(b"later").unwrap(), None);
+    }
+}
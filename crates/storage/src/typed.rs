@@ -0,0 +1,178 @@
+//! [`Storage`] access with values encoded by `horizcoin-codec`.
+
+use std::{fmt, marker::PhantomData, sync::Arc};
+
+use horizcoin_primitives::HorizError;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{KeyValue, Storage};
+
+/// A view of one column family whose values are all of type `T`.
+///
+/// Keys stay raw bytes; values are encoded with [`horizcoin_codec::encode`]
+/// and must decode exactly, so an entry of the wrong type is an error
+/// rather than a misread.
+pub struct TypedStorage<T> {
+    storage: Arc<dyn Storage>,
+    cf: String,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize + DeserializeOwned> TypedStorage<T> {
+    /// Creates a view of column family `cf` in `storage`.
+    pub fn new(storage: Arc<dyn Storage>, cf: impl Into<String>) -> Self {
+        Self {
+            storage,
+            cf: cf.into(),
+            _value: PhantomData,
+        }
+    }
+
+    /// Returns the column family this view reads and writes.
+    #[must_use]
+    pub fn cf(&self) -> &str {
+        &self.cf
+    }
+
+    /// Returns the decoded value of `key`.
+    pub fn get(&self, key: &[u8]) -> Result<Option<T>, HorizError> {
+        self.storage
+            .get_cf(&self.cf, key)?
+            .map(|bytes| horizcoin_codec::decode_exact(&bytes))
+            .transpose()
+    }
+
+    /// Encodes `value` and stores it under `key`.
+    pub fn put(&self, key: &[u8], value: &T) -> Result<(), HorizError> {
+        self.storage
+            .put_cf(&self.cf, key, &horizcoin_codec::encode(value)?)
+    }
+
+    /// Removes `key`.
+    pub fn delete(&self, key: &[u8]) -> Result<(), HorizError> {
+        self.storage.delete_cf(&self.cf, key)
+    }
+
+    /// Returns the stored value of `key`, or stores and returns `f()` if
+    /// there is none.
+    ///
+    /// The read and write are separate operations: two callers racing on a
+    /// missing key may both run `f`, and the later write wins.
+    pub fn get_or_insert_with(&self, key: &[u8], f: impl FnOnce() -> T) -> Result<T, HorizError> {
+        if let Some(value) = self.get(key)? {
+            return Ok(value);
+        }
+        let value = f();
+        self.put(key, &value)?;
+        Ok(value)
+    }
+
+    /// Returns every entry whose key starts with `prefix`, decoded.
+    pub fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, T)>, HorizError> {
+        decode_entries(self.storage.scan_prefix_cf(&self.cf, prefix)?)
+    }
+
+    /// Returns every entry with `start <= key < end`, decoded.
+    pub fn scan_range(&self, start: &[u8], end: &[u8]) -> Result<Vec<(Vec<u8>, T)>, HorizError> {
+        decode_entries(self.storage.scan_range_cf(&self.cf, start, end)?)
+    }
+}
+
+impl<T> fmt::Debug for TypedStorage<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypedStorage")
+            .field("cf", &self.cf)
+            .finish_non_exhaustive()
+    }
+}
+
+fn decode_entries<T: DeserializeOwned>(
+    entries: Vec<KeyValue>,
+) -> Result<Vec<(Vec<u8>, T)>, HorizError> {
+    entries
+        .into_iter()
+        .map(|(key, bytes)| Ok((key, horizcoin_codec::decode_exact(&bytes)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::MemoryStorage;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    struct Peer {
+        score: i32,
+        banned: bool,
+    }
+
+    fn peers() -> (Arc<MemoryStorage>, TypedStorage<Peer>) {
+        let storage = Arc::new(MemoryStorage::new());
+        let typed = TypedStorage::new(storage.clone(), "peers");
+        (storage, typed)
+    }
+
+    #[test]
+    fn scan_range_decodes_bounded_entries() {
+        let (storage, typed) = peers();
+        for (i, key) in [b"a", b"b", b"c", b"d"].into_iter().enumerate() {
+            let score = i32::try_from(i).unwrap();
+            typed
+                .put(
+                    key,
+                    &Peer {
+                        score,
+                        banned: false,
+                    },
+                )
+                .unwrap();
+        }
+
+        let entries = typed.scan_range(b"b", b"d").unwrap();
+        let scores: Vec<_> = entries
+            .iter()
+            .map(|(key, peer)| (key.as_slice(), peer.score))
+            .collect();
+        assert_eq!(scores, [(&b"b"[..], 1), (&b"c"[..], 2)]);
+        assert!(typed.scan_range(b"d", b"b").unwrap().is_empty());
+
+        storage.put_cf("peers", b"bad", b"\x01").unwrap();
+        assert!(typed.scan_range(b"b", b"c").is_err());
+    }
+
+    #[test]
+    fn get_or_insert_with_stores_default_once() {
+        let (_, typed) = peers();
+        let fresh = Peer {
+            score: 0,
+            banned: false,
+        };
+
+        let inserted = typed.get_or_insert_with(b"p1", || fresh.clone()).unwrap();
+        assert_eq!(inserted, fresh);
+        assert_eq!(typed.get(b"p1").unwrap(), Some(fresh));
+
+        typed
+            .put(
+                b"p1",
+                &Peer {
+                    score: -5,
+                    banned: true,
+                },
+            )
+            .unwrap();
+        let existing = typed
+            .get_or_insert_with(b"p1", || unreachable!("value is present"))
+            .unwrap();
+        assert_eq!(
+            existing,
+            Peer {
+                score: -5,
+                banned: true
+            }
+        );
+        assert_eq!(typed.scan_prefix(b"p").unwrap().len(), 1);
+    }
+}
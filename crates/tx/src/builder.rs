@@ -0,0 +1,175 @@
+//! Step-by-step construction of transactions.
+
+use horizcoin_crypto::PrivateKey;
+use horizcoin_primitives::HorizError;
+
+use crate::{invalid, Transaction, TxInput, TxOutput};
+
+/// Collects inputs, outputs and a memo, then assembles a [`Transaction`].
+#[derive(Debug, Clone, Default)]
+pub struct TransactionBuilder {
+    inputs: Vec<TxInput>,
+    outputs: Vec<TxOutput>,
+    memo: Option<String>,
+    lock_time: u64,
+    sorted: bool,
+}
+
+impl TransactionBuilder {
+    /// Creates a builder with nothing added.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `input`.
+    #[must_use]
+    pub fn input(mut self, input: TxInput) -> Self {
+        self.inputs.push(input);
+        self
+    }
+
+    /// Appends `output`.
+    #[must_use]
+    pub fn output(mut self, output: TxOutput) -> Self {
+        self.outputs.push(output);
+        self
+    }
+
+    /// Sets the memo.
+    #[must_use]
+    pub fn memo(mut self, memo: impl Into<String>) -> Self {
+        self.memo = Some(memo.into());
+        self
+    }
+
+    /// Locks the transaction until `lock_time`, in Unix seconds.
+    #[must_use]
+    pub const fn lock_time(mut self, lock_time: u64) -> Self {
+        self.lock_time = lock_time;
+        self
+    }
+
+    /// Sorts inputs by `(prev_tx, output_index)` and outputs by
+    /// `(amount, address)` when building, so the result does not depend on
+    /// the order they were added in.
+    #[must_use]
+    pub const fn sorted(mut self) -> Self {
+        self.sorted = true;
+        self
+    }
+
+    /// Assembles the transaction, sorting it first if
+    /// [`sorted`](Self::sorted) was requested.
+    #[must_use]
+    pub fn build(mut self) -> Transaction {
+        if self.sorted {
+            self.inputs
+                .sort_by_key(|input| (input.prev_tx, input.output_index));
+            // Ties on amount and address are broken by the spending policy,
+            // so multisig outputs also land in a fixed order.
+            self.outputs.sort_by_cached_key(|output| {
+                (
+                    output.amount,
+                    output.address.clone(),
+                    horizcoin_codec::encode(&output.kind).unwrap_or_default(),
+                )
+            });
+        }
+        Transaction::new(self.inputs, self.outputs, self.memo).with_lock_time(self.lock_time)
+    }
+
+    /// Assembles the transaction and signs every single-key input with the
+    /// matching key from `keys`.
+    ///
+    /// Sorting happens before signing, so the signatures commit to the
+    /// final order. Multisig inputs are left for their signers. Fails if a
+    /// single-key input has no matching key.
+    pub fn build_signed(self, keys: &[PrivateKey]) -> Result<Transaction, HorizError> {
+        let mut tx = self.build();
+        for index in 0..tx.inputs.len() {
+            let Some(public_key) = tx.inputs[index].public_key else {
+                continue;
+            };
+            let key = keys
+                .iter()
+                .find(|key| key.public_key() == public_key)
+                .ok_or_else(|| invalid(format!("no key for input {index}")))?;
+            tx.sign_input(index, key)?;
+        }
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_primitives::{Amount, TxId};
+
+    use super::*;
+
+    #[test]
+    fn sorted_builds_are_order_independent() {
+        let alice = PrivateKey::generate();
+        let bob = PrivateKey::generate();
+        let inputs = [
+            TxInput::new(TxId::new([2; 32]), 0, alice.public_key()),
+            TxInput::new(TxId::new([1; 32]), 5, bob.public_key()),
+            TxInput::new(TxId::new([1; 32]), 1, alice.public_key()),
+        ];
+        let outputs = [
+            TxOutput::new(Amount::new(500), "hz1b"),
+            TxOutput::new(Amount::new(500), "hz1a"),
+            TxOutput::new(Amount::new(20), "hz1z"),
+        ];
+        let keys = [alice, bob];
+
+        let forward = inputs
+            .iter()
+            .cloned()
+            .fold(TransactionBuilder::new(), TransactionBuilder::input);
+        let forward = outputs
+            .iter()
+            .cloned()
+            .fold(forward, TransactionBuilder::output);
+        let reverse = inputs
+            .iter()
+            .rev()
+            .cloned()
+            .fold(TransactionBuilder::new(), TransactionBuilder::input);
+        let reverse = outputs
+            .iter()
+            .rev()
+            .cloned()
+            .fold(reverse, TransactionBuilder::output);
+
+        assert_ne!(
+            forward.clone().build().txid(),
+            reverse.clone().build().txid()
+        );
+
+        let a = forward.sorted().build_signed(&keys).unwrap();
+        let b = reverse.sorted().build_signed(&keys).unwrap();
+        assert_eq!(a.txid().unwrap(), b.txid().unwrap());
+        assert_eq!(a.verify_signatures(), Ok(()));
+        assert_eq!(a.inputs[0].output_index, 1);
+        assert_eq!(
+            a.outputs
+                .iter()
+                .map(|output| output.address.as_str())
+                .collect::<Vec<_>>(),
+            ["hz1z", "hz1a", "hz1b"]
+        );
+    }
+
+    #[test]
+    fn build_signed_needs_every_key() {
+        let alice = PrivateKey::generate();
+        let builder = TransactionBuilder::new()
+            .input(TxInput::new(TxId::new([1; 32]), 0, alice.public_key()))
+            .output(TxOutput::new(Amount::new(10), "hz1a"))
+            .memo("rent");
+        assert!(builder.clone().build_signed(&[]).is_err());
+        let tx = builder.build_signed(&[alice]).unwrap();
+        assert_eq!(tx.memo.as_deref(), Some("rent"));
+    }
+}
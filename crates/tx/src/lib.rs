@@ -3,10 +3,910 @@
 //! This crate defines transaction structure, verification logic, and memo handling
 //! with a 128-byte `UTF-8` limit for the `HorizCoin` blockchain.
 
+mod builder;
+mod multisig;
+mod policy;
+mod psbt;
+mod selection;
+mod sighash;
+
+use std::collections::HashSet;
+
+use horizcoin_crypto::{
+    double_sha256, hash160, parse_address, PrivateKey, PublicKey, SIGNATURE_LEN,
+};
+use horizcoin_primitives::{Amount, HorizError, TxId, ValidationContext};
+use serde::{Deserialize, Serialize};
+
+pub use builder::TransactionBuilder;
+pub use multisig::MAX_MULTISIG_KEYS;
+pub use policy::DUST_THRESHOLD;
+pub use psbt::PartiallySignedTransaction;
+pub use selection::{coin_select, CoinSelection, ESTIMATED_BASE_SIZE, ESTIMATED_INPUT_SIZE};
+pub use sighash::{SigHashMode, SigHashType};
+
+/// Output index used by the single input of a coinbase transaction.
+///
+/// Together with an all-zero previous transaction id it forms the sentinel
+/// outpoint that marks newly minted coins.
+pub const COINBASE_OUTPUT_INDEX: u32 = u32::MAX;
+
+/// Sequence number of an input that opts out of replacement.
+pub const SEQUENCE_FINAL: u32 = u32::MAX;
+
+/// Sequence number given to new inputs: the largest value that still
+/// signals replace-by-fee.
+pub const DEFAULT_SEQUENCE: u32 = u32::MAX - 2;
+
+/// A reference to a previous output being spent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxInput {
+    /// Transaction that created the output.
+    pub prev_tx: TxId,
+    /// Index of the output within `prev_tx`.
+    pub output_index: u32,
+    /// Replacement signal; any input at or below [`DEFAULT_SEQUENCE`]
+    /// marks the transaction replaceable.
+    pub sequence: u32,
+    /// Signature authorizing the spend, followed by its
+    /// [`SigHashType`] byte; empty until signed. A multisig spend holds
+    /// several such signatures back to back.
+    pub signature: Vec<u8>,
+    /// Key the signature is checked against; `None` for the input of a
+    /// coinbase, which spends nothing, and for multisig spends, whose keys
+    /// are listed in the spent output.
+    pub public_key: Option<PublicKey>,
+}
+
+impl TxInput {
+    /// Creates an unsigned input spending `prev_tx:output_index`.
+    #[must_use]
+    pub const fn new(prev_tx: TxId, output_index: u32, public_key: PublicKey) -> Self {
+        Self {
+            prev_tx,
+            output_index,
+            sequence: DEFAULT_SEQUENCE,
+            signature: Vec::new(),
+            public_key: Some(public_key),
+        }
+    }
+
+    /// Creates an unsigned input spending the
+    /// [`MultiSig`](OutputKind::MultiSig) output `prev_tx:output_index`.
+    #[must_use]
+    pub const fn multisig(prev_tx: TxId, output_index: u32) -> Self {
+        Self {
+            prev_tx,
+            output_index,
+            sequence: DEFAULT_SEQUENCE,
+            signature: Vec::new(),
+            public_key: None,
+        }
+    }
+
+    /// Creates the keyless input of a coinbase, spending the sentinel
+    /// outpoint.
+    #[must_use]
+    pub const fn coinbase() -> Self {
+        Self {
+            prev_tx: TxId::ZERO,
+            output_index: COINBASE_OUTPUT_INDEX,
+            sequence: SEQUENCE_FINAL,
+            signature: Vec::new(),
+            public_key: None,
+        }
+    }
+
+    /// Returns `true` if this input references the coinbase sentinel outpoint.
+    #[must_use]
+    pub fn is_coinbase_sentinel(&self) -> bool {
+        self.prev_tx.is_zero() && self.output_index == COINBASE_OUTPUT_INDEX
+    }
+}
+
+/// The condition for spending an output.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputKind {
+    /// Spendable by the key whose hash the output's address encodes.
+    #[default]
+    SingleKey,
+    /// Spendable with signatures from at least `m` distinct keys of
+    /// `pubkeys`.
+    MultiSig {
+        /// Number of signatures required.
+        m: u8,
+        /// Keys allowed to sign, at most [`MAX_MULTISIG_KEYS`].
+        pubkeys: Vec<PublicKey>,
+    },
+}
+
+/// A payment to an address or to a set of keys.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxOutput {
+    /// Amount paid.
+    pub amount: Amount,
+    /// Recipient address; empty for multisig outputs.
+    pub address: String,
+    /// Who may spend the output.
+    pub kind: OutputKind,
+}
+
+impl TxOutput {
+    /// Creates an output paying `amount` to `address`.
+    #[must_use]
+    pub fn new(amount: Amount, address: impl Into<String>) -> Self {
+        Self {
+            amount,
+            address: address.into(),
+            kind: OutputKind::SingleKey,
+        }
+    }
+
+    /// Creates an output paying `amount` that any `m` of `pubkeys` may
+    /// spend together.
+    #[must_use]
+    pub const fn multisig(amount: Amount, m: u8, pubkeys: Vec<PublicKey>) -> Self {
+        Self {
+            amount,
+            address: String::new(),
+            kind: OutputKind::MultiSig { m, pubkeys },
+        }
+    }
+
+    /// Checks the output against the limits in `ctx`.
+    pub fn validate(&self, ctx: &ValidationContext) -> Result<(), HorizError> {
+        if self.amount < ctx.dust_threshold {
+            return Err(invalid(format!(
+                "output of {} is below the dust threshold of {}",
+                self.amount, ctx.dust_threshold
+            )));
+        }
+        self.kind.validate()
+    }
+}
+
+/// A transfer of coins from previous outputs to new outputs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transaction {
+    /// Outputs being spent.
+    pub inputs: Vec<TxInput>,
+    /// Outputs being created.
+    pub outputs: Vec<TxOutput>,
+    /// Optional free-form note, at most
+    /// [`MEMO_MAX_LENGTH`](horizcoin_primitives::constants::MEMO_MAX_LENGTH) bytes.
+    pub memo: Option<String>,
+    /// Unix time in seconds before which the transaction may not be mined;
+    /// `0` means no lock.
+    pub lock_time: u64,
+}
+
+impl Transaction {
+    /// Creates an unlocked transaction from its parts.
+    #[must_use]
+    pub const fn new(inputs: Vec<TxInput>, outputs: Vec<TxOutput>, memo: Option<String>) -> Self {
+        Self {
+            inputs,
+            outputs,
+            memo,
+            lock_time: 0,
+        }
+    }
+
+    /// Returns this transaction locked until `lock_time`, in Unix seconds.
+    #[must_use]
+    pub const fn with_lock_time(mut self, lock_time: u64) -> Self {
+        self.lock_time = lock_time;
+        self
+    }
+
+    /// Returns `true` if the transaction may be mined at `current_time`:
+    /// it has no lock, or its lock time has been reached.
+    ///
+    /// [`validate`](Self::validate) does not check this; the mempool and
+    /// block assembly do.
+    #[must_use]
+    pub const fn is_final(&self, current_time: u64) -> bool {
+        self.lock_time == 0 || self.lock_time <= current_time
+    }
+
+    /// Creates a coinbase minting `outputs`.
+    ///
+    /// The coinbase input carries no key, so equal arguments always yield
+    /// the same transaction and [`txid`](Self::txid).
+    #[must_use]
+    pub fn coinbase(outputs: Vec<TxOutput>, memo: Option<String>) -> Self {
+        Self::new(vec![TxInput::coinbase()], outputs, memo)
+    }
+
+    /// Returns `true` if this is a coinbase: a single keyless input spending
+    /// the sentinel outpoint.
+    #[must_use]
+    pub fn is_coinbase(&self) -> bool {
+        matches!(
+            self.inputs.as_slice(),
+            [input] if input.is_coinbase_sentinel() && input.public_key.is_none()
+        )
+    }
+
+    /// Returns `true` if any input signals replace-by-fee.
+    #[must_use]
+    pub fn signals_rbf(&self) -> bool {
+        self.inputs
+            .iter()
+            .any(|input| input.sequence <= DEFAULT_SEQUENCE)
+    }
+
+    /// Returns `true` if `self` may replace `other`: they spend at least one
+    /// common output, `self` signals replace-by-fee, and `self` pays a
+    /// strictly higher fee.
+    ///
+    /// Returns `false` if either fee cannot be computed.
+    pub fn can_replace(
+        &self,
+        other: &Self,
+        utxo_lookup: impl Fn(&TxId, u32) -> Option<TxOutput>,
+    ) -> bool {
+        let outpoints: HashSet<_> = other
+            .inputs
+            .iter()
+            .map(|input| (input.prev_tx, input.output_index))
+            .collect();
+        let conflicts = self
+            .inputs
+            .iter()
+            .any(|input| outpoints.contains(&(input.prev_tx, input.output_index)));
+        if !conflicts || !self.signals_rbf() {
+            return false;
+        }
+        match (self.fee(&utxo_lookup), other.fee(&utxo_lookup)) {
+            (Ok(new_fee), Ok(old_fee)) => new_fee > old_fee,
+            _ => false,
+        }
+    }
+
+    /// Returns the identifier of this transaction: the double `SHA-256` of
+    /// its encoding, signatures included.
+    pub fn txid(&self) -> Result<TxId, HorizError> {
+        horizcoin_codec::encode(self).map(|bytes| double_sha256(&bytes).into())
+    }
+
+    /// Returns the length of this transaction's canonical encoding, the
+    /// size fees are charged on.
+    pub fn serialized_size(&self) -> Result<usize, HorizError> {
+        horizcoin_codec::encoded_len(self)
+    }
+
+    /// Returns the sum of the output amounts.
+    ///
+    /// Fails with [`HorizError::AmountOverflow`] if the sum exceeds
+    /// [`MAX_MONEY`](horizcoin_primitives::constants::MAX_MONEY).
+    pub fn total_output_amount(&self) -> Result<Amount, HorizError> {
+        self.outputs.iter().try_fold(Amount::ZERO, |total, output| {
+            total.checked_add(output.amount)
+        })
+    }
+
+    /// Returns the fee: what the spent outputs hold minus what the
+    /// transaction pays out. A coinbase pays no fee.
+    ///
+    /// `utxo_lookup` resolves an outpoint to the output it names. Fails if
+    /// an input's output cannot be found, either total overflows, or the
+    /// outputs exceed the inputs.
+    pub fn fee(
+        &self,
+        utxo_lookup: impl Fn(&TxId, u32) -> Option<TxOutput>,
+    ) -> Result<Amount, HorizError> {
+        if self.is_coinbase() {
+            return Ok(Amount::ZERO);
+        }
+        let mut spent = Amount::ZERO;
+        for input in &self.inputs {
+            let output = utxo_lookup(&input.prev_tx, input.output_index).ok_or_else(|| {
+                invalid(format!(
+                    "unknown output {}:{}",
+                    input.prev_tx, input.output_index
+                ))
+            })?;
+            spent = spent.checked_add(output.amount)?;
+        }
+        let paid = self.total_output_amount()?;
+        spent
+            .checked_sub(paid)
+            .map_err(|_| invalid(format!("outputs of {paid} exceed inputs of {spent}")))
+    }
+
+    /// Returns the fee per byte of [`serialized_size`](Self::serialized_size),
+    /// rounded down.
+    pub fn fee_rate(
+        &self,
+        utxo_lookup: impl Fn(&TxId, u32) -> Option<TxOutput>,
+    ) -> Result<Amount, HorizError> {
+        let size =
+            u64::try_from(self.serialized_size()?).map_err(|_| invalid("transaction too large"))?;
+        Ok(Amount::new(self.fee(utxo_lookup)?.as_u64() / size))
+    }
+
+    /// Signs input `index` with `key` under [`SigHashType::ALL`].
+    pub fn sign_input(&mut self, index: usize, key: &PrivateKey) -> Result<(), HorizError> {
+        self.sign_input_with(index, key, SigHashType::ALL)
+    }
+
+    /// Signs input `index` with `key`, which must match the input's public
+    /// key, committing to the parts selected by `sighash_type`.
+    pub fn sign_input_with(
+        &mut self,
+        index: usize,
+        key: &PrivateKey,
+        sighash_type: SigHashType,
+    ) -> Result<(), HorizError> {
+        let input = self
+            .inputs
+            .get(index)
+            .ok_or_else(|| invalid(format!("no input {index} to sign")))?;
+        if input.public_key != Some(key.public_key()) {
+            return Err(invalid(format!("key does not match input {index}")));
+        }
+        let digest = self.signature_hash_for(index, sighash_type)?;
+        let mut signature = key.sign_deterministic(digest.as_bytes()).to_vec();
+        signature.push(sighash_type.to_byte());
+        self.inputs[index].signature = signature;
+        Ok(())
+    }
+
+    /// Checks that every input carries a valid signature over the digest
+    /// its [`SigHashType`] byte selects.
+    ///
+    /// A coinbase spends no existing output, so it has nothing to check.
+    /// Multisig spends name their keys only in the spent output, so they
+    /// fail here; use [`verify_against_utxos`](Self::verify_against_utxos).
+    pub fn verify_signatures(&self) -> Result<(), HorizError> {
+        if self.is_coinbase() {
+            return Ok(());
+        }
+        (0..self.inputs.len()).try_for_each(|index| self.verify_single_key(index))
+    }
+
+    /// Checks that each input is authorized to spend the output it names
+    /// and that its signatures are valid.
+    ///
+    /// A single-key output is owned by the key whose hash its address
+    /// encodes, on any network. A multisig output needs valid signatures
+    /// from at least `m` of its distinct keys. A coinbase spends nothing
+    /// and passes trivially.
+    pub fn verify_against_utxos(
+        &self,
+        utxo_lookup: impl Fn(&TxId, u32) -> Option<TxOutput>,
+    ) -> Result<(), HorizError> {
+        if self.is_coinbase() {
+            return Ok(());
+        }
+        for (index, input) in self.inputs.iter().enumerate() {
+            let spent = utxo_lookup(&input.prev_tx, input.output_index).ok_or_else(|| {
+                invalid(format!(
+                    "unknown output {}:{}",
+                    input.prev_tx, input.output_index
+                ))
+            })?;
+            match &spent.kind {
+                OutputKind::SingleKey => {
+                    let key = input
+                        .public_key
+                        .ok_or_else(|| invalid(format!("input {index} has no public key")))?;
+                    let (_, owner) = parse_address(&spent.address)?;
+                    if owner != hash160(&key.to_bytes()) {
+                        return Err(invalid(format!(
+                            "input {index} key does not own {}",
+                            spent.address
+                        )));
+                    }
+                    self.verify_single_key(index)?;
+                }
+                OutputKind::MultiSig { m, pubkeys } => {
+                    self.verify_multisig_input(index, *m, pubkeys)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_single_key(&self, index: usize) -> Result<(), HorizError> {
+        let input = &self.inputs[index];
+        let key = input
+            .public_key
+            .ok_or_else(|| invalid(format!("input {index} has no public key")))?;
+        let (signature, sighash_type) = split_signature(&input.signature)
+            .ok_or_else(|| invalid(format!("input {index} is not signed")))?;
+        let digest = self.signature_hash_for(index, SigHashType::from_byte(sighash_type)?)?;
+        if !key.verify_strict(digest.as_bytes(), signature) {
+            return Err(invalid(format!("input {index} has an invalid signature")));
+        }
+        Ok(())
+    }
+
+    /// Checks structural rules against the limits in `ctx`.
+    ///
+    /// Signatures and the existence of spent outputs are not checked here.
+    pub fn validate(&self, ctx: &ValidationContext) -> Result<(), HorizError> {
+        if self.inputs.is_empty() {
+            return Err(invalid("transaction has no inputs"));
+        }
+        if self.outputs.is_empty() {
+            return Err(invalid("transaction has no outputs"));
+        }
+        if self.inputs.len() > ctx.max_inputs {
+            return Err(invalid(format!(
+                "{} inputs exceed the maximum of {}",
+                self.inputs.len(),
+                ctx.max_inputs
+            )));
+        }
+        if self.outputs.len() > ctx.max_outputs {
+            return Err(invalid(format!(
+                "{} outputs exceed the maximum of {}",
+                self.outputs.len(),
+                ctx.max_outputs
+            )));
+        }
+        if let Some(memo) = &self.memo {
+            if memo.len() > ctx.memo_max_length {
+                return Err(invalid(format!(
+                    "memo is {} bytes, maximum is {}",
+                    memo.len(),
+                    ctx.memo_max_length
+                )));
+            }
+        }
+
+        // Only the first input of a coinbase may reference the sentinel.
+        if self.inputs[1..].iter().any(TxInput::is_coinbase_sentinel) {
+            return Err(invalid("input references the coinbase sentinel outpoint"));
+        }
+        // A coinbase mints coins, so it must not also spend real outputs.
+        if self.inputs[0].is_coinbase_sentinel() && !self.is_coinbase() {
+            return Err(invalid("malformed coinbase"));
+        }
+
+        let mut seen = HashSet::with_capacity(self.inputs.len());
+        for input in &self.inputs {
+            if !seen.insert((input.prev_tx, input.output_index)) {
+                return Err(invalid(format!(
+                    "duplicate input {}:{}",
+                    input.prev_tx, input.output_index
+                )));
+            }
+        }
+
+        self.outputs
+            .iter()
+            .try_for_each(|output| output.validate(ctx))
+    }
+}
+
+/// Splits an input signature into the `ECDSA` signature and its sighash
+/// type byte.
+fn split_signature(signature: &[u8]) -> Option<(&[u8; SIGNATURE_LEN], u8)> {
+    let (&sighash_type, signature) = signature.split_last()?;
+    Some((signature.try_into().ok()?, sighash_type))
+}
+
+fn invalid(reason: impl Into<String>) -> HorizError {
+    HorizError::InvalidTransaction(reason.into())
+}
+
 #[cfg(test)]
 mod tests {
+    use horizcoin_crypto::PrivateKey;
+    use horizcoin_primitives::constants::{MAX_MONEY, MEMO_MAX_LENGTH};
+
+    use super::*;
+
+    fn key() -> PublicKey {
+        PrivateKey::generate().public_key()
+    }
+
+    fn sentinel_input() -> TxInput {
+        TxInput::coinbase()
+    }
+
+    fn real_input(byte: u8) -> TxInput {
+        TxInput::new(TxId::new([byte; 32]), 0, key())
+    }
+
+    fn payment() -> Vec<TxOutput> {
+        vec![TxOutput::new(Amount::new(50), "hz1recipient")]
+    }
+
+    #[test]
+    fn well_formed_coinbase_passes() {
+        let tx = Transaction::new(vec![sentinel_input()], payment(), Some("block 1".into()));
+        assert!(tx.is_coinbase());
+        assert_eq!(tx.validate(&ValidationContext::default()), Ok(()));
+    }
+
+    #[test]
+    fn coinbase_with_extra_input_is_rejected() {
+        let tx = Transaction::new(vec![sentinel_input(), real_input(1)], payment(), None);
+        assert!(!tx.is_coinbase());
+        assert_eq!(
+            tx.validate(&ValidationContext::default()),
+            Err(invalid("malformed coinbase"))
+        );
+    }
+
+    #[test]
+    fn sentinel_in_regular_transaction_is_rejected() {
+        let tx = Transaction::new(vec![real_input(1), sentinel_input()], payment(), None);
+        assert!(!tx.is_coinbase());
+        assert_eq!(
+            tx.validate(&ValidationContext::default()),
+            Err(invalid("input references the coinbase sentinel outpoint"))
+        );
+    }
+
+    #[test]
+    fn structural_rules() {
+        let ok = Transaction::new(vec![real_input(1)], payment(), None);
+        assert_eq!(ok.validate(&ValidationContext::default()), Ok(()));
+
+        let no_inputs = Transaction::new(vec![], payment(), None);
+        assert!(no_inputs.validate(&ValidationContext::default()).is_err());
+
+        let no_outputs = Transaction::new(vec![real_input(1)], vec![], None);
+        assert!(no_outputs.validate(&ValidationContext::default()).is_err());
+
+        let duplicate = Transaction::new(vec![real_input(1), real_input(1)], payment(), None);
+        assert!(duplicate.validate(&ValidationContext::default()).is_err());
+
+        let zero = Transaction::new(
+            vec![real_input(1)],
+            vec![TxOutput::new(Amount::new(0), "hz1x")],
+            None,
+        );
+        assert!(zero.validate(&ValidationContext::default()).is_err());
+
+        let memo = "x".repeat(MEMO_MAX_LENGTH + 1);
+        let long_memo = Transaction::new(vec![real_input(1)], payment(), Some(memo));
+        assert!(long_memo.validate(&ValidationContext::default()).is_err());
+    }
+
+    #[test]
+    fn stricter_context_rejects_what_default_accepts() {
+        let tx = Transaction::new(
+            vec![real_input(1), real_input(2)],
+            vec![
+                TxOutput::new(Amount::new(50), "hz1a"),
+                TxOutput::new(Amount::new(500), "hz1b"),
+            ],
+            Some("rent".into()),
+        );
+        let default = ValidationContext::default();
+        assert_eq!(tx.validate(&default), Ok(()));
+
+        let one_input = ValidationContext {
+            max_inputs: 1,
+            ..default
+        };
+        assert!(tx.validate(&one_input).is_err());
+
+        let one_output = ValidationContext {
+            max_outputs: 1,
+            ..default
+        };
+        assert!(tx.validate(&one_output).is_err());
+
+        let short_memo = ValidationContext {
+            memo_max_length: 3,
+            ..default
+        };
+        assert!(tx.validate(&short_memo).is_err());
+
+        let high_dust = ValidationContext {
+            dust_threshold: Amount::new(100),
+            ..default
+        };
+        assert_eq!(
+            tx.validate(&high_dust),
+            Err(invalid("output of 50 is below the dust threshold of 100"))
+        );
+        assert_eq!(tx.outputs[1].validate(&high_dust), Ok(()));
+    }
+
+    #[test]
+    fn coinbase_is_deterministic() {
+        let a = Transaction::coinbase(payment(), Some("block 1".into()));
+        let b = Transaction::coinbase(payment(), Some("block 1".into()));
+        assert!(a.is_coinbase());
+        assert_eq!(a.txid().unwrap(), b.txid().unwrap());
+        assert_eq!(a.verify_signatures(), Ok(()));
+
+        let other = Transaction::coinbase(payment(), Some("block 2".into()));
+        assert_ne!(a.txid().unwrap(), other.txid().unwrap());
+
+        // The sentinel with a key attached is not a coinbase.
+        let keyed = TxInput::new(TxId::ZERO, COINBASE_OUTPUT_INDEX, key());
+        let fake = Transaction::new(vec![keyed], payment(), None);
+        assert!(!fake.is_coinbase());
+        assert_eq!(
+            fake.validate(&ValidationContext::default()),
+            Err(invalid("malformed coinbase"))
+        );
+    }
+
+    #[test]
+    fn signatures_cover_the_whole_transaction() {
+        let alice = PrivateKey::generate();
+        let bob = PrivateKey::generate();
+        let mut tx = Transaction::new(
+            vec![
+                TxInput::new(TxId::new([1; 32]), 0, alice.public_key()),
+                TxInput::new(TxId::new([2; 32]), 1, bob.public_key()),
+            ],
+            payment(),
+            None,
+        );
+        assert!(tx.verify_signatures().is_err());
+        assert!(tx.sign_input(0, &bob).is_err());
+        assert!(tx.sign_input(2, &alice).is_err());
+
+        tx.sign_input(0, &alice).unwrap();
+        assert!(tx.verify_signatures().is_err());
+        tx.sign_input(1, &bob).unwrap();
+        assert_eq!(tx.verify_signatures(), Ok(()));
+
+        let mut tampered = tx.clone();
+        tampered.outputs[0].amount = Amount::new(tampered.outputs[0].amount.as_u64() + 1);
+        assert_eq!(
+            tampered.verify_signatures(),
+            Err(invalid("input 0 has an invalid signature"))
+        );
+        assert_ne!(tx.txid().unwrap(), tampered.txid().unwrap());
+    }
+
+    #[test]
+    fn serialized_size_matches_encoding() {
+        let signer = PrivateKey::generate();
+        for (inputs, outputs, memo) in [(1u8, 1u8, 0), (3, 2, 10), (0, 5, MEMO_MAX_LENGTH)] {
+            let mut tx = Transaction::new(
+                (0..inputs)
+                    .map(|i| TxInput::new(TxId::new([i; 32]), u32::from(i), signer.public_key()))
+                    .collect(),
+                (0..outputs)
+                    .map(|i| TxOutput::new(Amount::new(u64::from(i) * 7), format!("hz1out{i}")))
+                    .collect(),
+                (memo > 0).then(|| "m".repeat(memo)),
+            );
+            for index in 0..tx.inputs.len() {
+                tx.sign_input(index, &signer).unwrap();
+            }
+            assert_eq!(
+                tx.serialized_size().unwrap(),
+                horizcoin_codec::encode(&tx).unwrap().len()
+            );
+        }
+    }
+
+    #[test]
+    fn fee_rate_divides_fee_by_size() {
+        let tx = Transaction::new(
+            vec![real_input(1), real_input(2)],
+            vec![
+                TxOutput::new(Amount::new(600), "hz1a"),
+                TxOutput::new(Amount::new(300), "hz1b"),
+            ],
+            None,
+        );
+        let lookup = |txid: &TxId, _: u32| {
+            (txid.as_bytes()[0] <= 2).then(|| TxOutput::new(Amount::new(5_000), "hz1owner"))
+        };
+        assert_eq!(tx.fee(lookup), Ok(Amount::new(9_100)));
+        let size = u64::try_from(tx.serialized_size().unwrap()).unwrap();
+        assert_eq!(tx.fee_rate(lookup), Ok(Amount::new(9_100 / size)));
+
+        assert_eq!(
+            Transaction::coinbase(payment(), None).fee(lookup),
+            Ok(Amount::ZERO)
+        );
+        let unknown = Transaction::new(vec![real_input(3)], payment(), None);
+        assert!(unknown.fee(lookup).is_err());
+        let overspend = Transaction::new(
+            vec![real_input(1)],
+            vec![TxOutput::new(Amount::new(5_001), "hz1a")],
+            None,
+        );
+        assert!(overspend.fee_rate(lookup).is_err());
+    }
+
+    #[test]
+    fn output_total_is_checked() {
+        let tx = Transaction::new(
+            vec![],
+            vec![
+                TxOutput::new(Amount::new(600), "hz1a"),
+                TxOutput::new(Amount::new(300), "hz1b"),
+            ],
+            None,
+        );
+        assert_eq!(tx.total_output_amount(), Ok(Amount::new(900)));
+
+        let near_max = Amount::new(u64::MAX - 1);
+        let wrapping = Transaction::new(
+            vec![],
+            vec![
+                TxOutput::new(near_max, "hz1a"),
+                TxOutput::new(near_max, "hz1b"),
+            ],
+            None,
+        );
+        assert_eq!(
+            wrapping.total_output_amount(),
+            Err(HorizError::AmountOverflow)
+        );
+
+        let capped = Transaction::new(
+            vec![],
+            vec![
+                TxOutput::new(MAX_MONEY, "hz1a"),
+                TxOutput::new(Amount::new(1), "hz1b"),
+            ],
+            None,
+        );
+        assert_eq!(
+            capped.total_output_amount(),
+            Err(HorizError::AmountOverflow)
+        );
+        let lookup = |_: &TxId, _: u32| Some(TxOutput::new(MAX_MONEY, "hz1owner"));
+        let spend = Transaction::new(vec![real_input(1)], capped.outputs, None);
+        assert_eq!(spend.fee(lookup), Err(HorizError::AmountOverflow));
+    }
+
     #[test]
-    fn placeholder_test() {
-        // Placeholder test to ensure the crate compiles
+    fn spends_must_come_from_the_owner() {
+        let owner = PrivateKey::generate();
+        let thief = PrivateKey::generate();
+        let funding = TxId::new([9; 32]);
+        let lookup = |txid: &TxId, index: u32| {
+            (*txid == funding && index == 0)
+                .then(|| TxOutput::new(Amount::new(1_000), owner.public_key().to_address()))
+        };
+
+        let mut spend = Transaction::new(
+            vec![TxInput::new(funding, 0, owner.public_key())],
+            payment(),
+            None,
+        );
+        spend.sign_input(0, &owner).unwrap();
+        assert_eq!(spend.verify_against_utxos(lookup), Ok(()));
+
+        let mut stolen = Transaction::new(
+            vec![TxInput::new(funding, 0, thief.public_key())],
+            payment(),
+            None,
+        );
+        stolen.sign_input(0, &thief).unwrap();
+        assert_eq!(stolen.verify_signatures(), Ok(()));
+        assert_eq!(
+            stolen.verify_against_utxos(lookup),
+            Err(invalid(format!(
+                "input 0 key does not own {}",
+                owner.public_key().to_address()
+            )))
+        );
+
+        let unsigned = Transaction::new(
+            vec![TxInput::new(funding, 0, owner.public_key())],
+            payment(),
+            None,
+        );
+        assert!(unsigned.verify_against_utxos(lookup).is_err());
+        assert_eq!(
+            Transaction::coinbase(payment(), None).verify_against_utxos(lookup),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn replacement_needs_conflict_signal_and_higher_fee() {
+        let lookup = |_: &TxId, _: u32| Some(TxOutput::new(Amount::new(1_000), "hz1owner"));
+        let original = Transaction::new(
+            vec![real_input(1)],
+            vec![TxOutput::new(Amount::new(900), "hz1a")],
+            None,
+        );
+        let bumped = Transaction::new(
+            vec![real_input(1), real_input(2)],
+            vec![TxOutput::new(Amount::new(1_850), "hz1a")],
+            None,
+        );
+        assert!(original.signals_rbf());
+        assert!(bumped.can_replace(&original, lookup));
+        assert!(!original.can_replace(&bumped, lookup));
+        assert!(!original.can_replace(&original, lookup));
+
+        let mut final_bump = bumped;
+        for input in &mut final_bump.inputs {
+            input.sequence = SEQUENCE_FINAL;
+        }
+        assert!(!final_bump.signals_rbf());
+        assert!(!final_bump.can_replace(&original, lookup));
+        assert!(!Transaction::coinbase(payment(), None).signals_rbf());
+    }
+
+    #[test]
+    fn unrelated_transaction_cannot_replace() {
+        let lookup = |_: &TxId, _: u32| Some(TxOutput::new(Amount::new(1_000), "hz1owner"));
+        let original = Transaction::new(
+            vec![real_input(1)],
+            vec![TxOutput::new(Amount::new(900), "hz1a")],
+            None,
+        );
+        let unrelated = Transaction::new(
+            vec![real_input(2)],
+            vec![TxOutput::new(Amount::new(10), "hz1a")],
+            None,
+        );
+        assert!(!unrelated.can_replace(&original, lookup));
+        assert!(!unrelated.can_replace(&original, |_: &TxId, _: u32| None));
+    }
+
+    #[test]
+    fn lock_time_gates_finality() {
+        let unlocked = Transaction::new(vec![real_input(1)], payment(), None);
+        assert!(unlocked.is_final(0));
+
+        let locked = unlocked.clone().with_lock_time(1_700_000_000);
+        assert_eq!(locked.validate(&ValidationContext::default()), Ok(()));
+        assert!(!locked.is_final(1_699_999_999));
+        assert!(locked.is_final(1_700_000_000));
+        assert!(locked.is_final(1_800_000_000));
+        assert_ne!(locked.txid().unwrap(), unlocked.txid().unwrap());
+        assert_ne!(locked.signature_hash(), unlocked.signature_hash());
+    }
+
+    /// Catches accidental changes to the consensus encoding: any field,
+    /// order or width change moves these bytes and therefore every txid.
+    #[test]
+    fn encoding_and_txid_are_pinned() {
+        let key = PrivateKey::from_bytes(&[1; 32]).unwrap();
+        let mut tx = Transaction::new(
+            vec![TxInput::new(TxId::new([2; 32]), 3, key.public_key())],
+            vec![TxOutput::new(Amount::new(1_000), "hz1pinned")],
+            Some("pin".into()),
+        )
+        .with_lock_time(1_700_000_000);
+        tx.sign_input(0, &key).unwrap();
+
+        let bytes = horizcoin_codec::encode(&tx).unwrap();
+        let expected = concat!(
+            // one input
+            "0100000000000000",
+            // prev_tx
+            "0202020202020202020202020202020202020202020202020202020202020202",
+            // output_index, sequence
+            "03000000fdffffff",
+            // 65-byte signature: ECDSA then sighash type
+            "4100000000000000",
+            "e7117290cdca6dc685655582a4285334790f4f87384b3c308b8220757ea65c96",
+            "06a7523e96a057e69be0dd8a93e3768026e34386d206cc76c60405c2ee08826a",
+            "01",
+            // Some(public_key)
+            "012100000000000000",
+            "031b84c5567b126440995d3ed5aaba0565d71e1834604819ff9c17f5e9d5dd07",
+            "8f",
+            // one output
+            "0100000000000000",
+            // amount, address, single-key kind
+            "e803000000000000",
+            "0900000000000000687a3170696e6e6564",
+            "00000000",
+            // Some(memo)
+            "01030000000000000070696e",
+            // lock_time
+            "00f1536500000000",
+        );
+        assert_eq!(hex::encode(&bytes), expected);
+        assert_eq!(
+            tx.txid().unwrap().to_hex(),
+            "8eb85c3937179c1fea3e061deb6120edaa319899246cdef3fe324103891a6a17"
+        );
     }
 }
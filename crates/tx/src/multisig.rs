@@ -0,0 +1,229 @@
+//! m-of-n multisignature outputs.
+
+use std::collections::HashSet;
+
+use horizcoin_crypto::{PrivateKey, PublicKey, SIGNATURE_LEN};
+use horizcoin_primitives::HorizError;
+
+use crate::{invalid, split_signature, OutputKind, SigHashType, Transaction};
+
+/// Largest number of keys a multisig output may list.
+pub const MAX_MULTISIG_KEYS: usize = 16;
+
+/// Length of one signature in a multisig spend: the `ECDSA` signature and
+/// its sighash type byte.
+const ENTRY_LEN: usize = SIGNATURE_LEN + 1;
+
+impl Transaction {
+    /// Adds `key`'s signature to multisig input `index`, committing to the
+    /// parts selected by `sighash_type`.
+    ///
+    /// Signatures never commit to each other, so signers may add theirs in
+    /// any order. Whether `key` is one of the output's keys is only known
+    /// once the spent output is, in
+    /// [`verify_against_utxos`](Self::verify_against_utxos).
+    pub fn sign_multisig_input(
+        &mut self,
+        index: usize,
+        key: &PrivateKey,
+        sighash_type: SigHashType,
+    ) -> Result<(), HorizError> {
+        let input = self
+            .inputs
+            .get(index)
+            .ok_or_else(|| invalid(format!("no input {index} to sign")))?;
+        if input.public_key.is_some() {
+            return Err(invalid(format!("input {index} is not a multisig spend")));
+        }
+        let digest = self.signature_hash_for(index, sighash_type)?;
+        let signature = key.sign_deterministic(digest.as_bytes());
+        let input = &mut self.inputs[index];
+        input.signature.extend_from_slice(&signature);
+        input.signature.push(sighash_type.to_byte());
+        Ok(())
+    }
+
+    /// Checks that input `index` carries valid signatures from at least `m`
+    /// distinct keys of `pubkeys`, the policy of the output it spends.
+    ///
+    /// Every signature present must verify against a key not already
+    /// used; a stray or repeated signature fails the spend rather than
+    /// being skipped.
+    pub fn verify_multisig_input(
+        &self,
+        index: usize,
+        m: u8,
+        pubkeys: &[PublicKey],
+    ) -> Result<(), HorizError> {
+        let input = self
+            .inputs
+            .get(index)
+            .ok_or_else(|| invalid(format!("no input {index}")))?;
+        if input.public_key.is_some() {
+            return Err(invalid(format!("input {index} is not a multisig spend")));
+        }
+        let entries = input.signature.chunks(ENTRY_LEN);
+        if input.signature.len() % ENTRY_LEN != 0 || entries.len() > pubkeys.len() {
+            return Err(invalid(format!("input {index} has malformed signatures")));
+        }
+        if entries.len() < usize::from(m) {
+            return Err(invalid(format!(
+                "input {index} has {} of {m} required signatures",
+                entries.len()
+            )));
+        }
+
+        let mut used = vec![false; pubkeys.len()];
+        for entry in entries {
+            let (signature, sighash_type) = split_signature(entry)
+                .ok_or_else(|| invalid(format!("input {index} has malformed signatures")))?;
+            let digest = self.signature_hash_for(index, SigHashType::from_byte(sighash_type)?)?;
+            let signer = pubkeys
+                .iter()
+                .zip(&used)
+                .position(|(key, used)| !used && key.verify_strict(digest.as_bytes(), signature))
+                .ok_or_else(|| invalid(format!("input {index} has an invalid signature")))?;
+            used[signer] = true;
+        }
+        Ok(())
+    }
+}
+
+impl OutputKind {
+    /// Checks that a multisig policy is satisfiable and unambiguous: at
+    /// most [`MAX_MULTISIG_KEYS`] distinct keys and `1 <= m <= keys`.
+    /// Single-key outputs always pass.
+    pub fn validate(&self) -> Result<(), HorizError> {
+        let Self::MultiSig { m, pubkeys } = self else {
+            return Ok(());
+        };
+        if pubkeys.len() > MAX_MULTISIG_KEYS {
+            return Err(invalid(format!(
+                "multisig lists {} keys, maximum is {MAX_MULTISIG_KEYS}",
+                pubkeys.len()
+            )));
+        }
+        if *m == 0 || usize::from(*m) > pubkeys.len() {
+            return Err(invalid(format!(
+                "multisig needs 1 to {} signatures, not {m}",
+                pubkeys.len()
+            )));
+        }
+        let distinct: HashSet<_> = pubkeys.iter().map(PublicKey::to_bytes).collect();
+        if distinct.len() != pubkeys.len() {
+            return Err(invalid("multisig lists a key twice"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_primitives::{Amount, TxId, ValidationContext};
+
+    use super::*;
+    use crate::{TxInput, TxOutput};
+
+    struct Escrow {
+        keys: [PrivateKey; 3],
+        funding: TxOutput,
+    }
+
+    impl Escrow {
+        fn new() -> Self {
+            let keys = [
+                PrivateKey::generate(),
+                PrivateKey::generate(),
+                PrivateKey::generate(),
+            ];
+            let funding = TxOutput::multisig(
+                Amount::new(1_000),
+                2,
+                keys.iter().map(PrivateKey::public_key).collect(),
+            );
+            Self { keys, funding }
+        }
+
+        fn spend() -> Transaction {
+            Transaction::new(
+                vec![TxInput::multisig(TxId::new([7; 32]), 0)],
+                vec![TxOutput::new(Amount::new(900), "hz1seller")],
+                None,
+            )
+        }
+
+        fn lookup(&self) -> impl Fn(&TxId, u32) -> Option<TxOutput> + '_ {
+            |_, _| Some(self.funding.clone())
+        }
+    }
+
+    #[test]
+    fn two_of_three_succeeds_with_two_signatures() {
+        let escrow = Escrow::new();
+        let mut tx = Escrow::spend();
+        assert_eq!(tx.validate(&ValidationContext::default()), Ok(()));
+
+        tx.sign_multisig_input(0, &escrow.keys[2], SigHashType::ALL)
+            .unwrap();
+        tx.sign_multisig_input(0, &escrow.keys[0], SigHashType::ALL)
+            .unwrap();
+        assert_eq!(tx.verify_against_utxos(escrow.lookup()), Ok(()));
+        assert!(tx.verify_signatures().is_err());
+    }
+
+    #[test]
+    fn two_of_three_fails_with_one_signature() {
+        let escrow = Escrow::new();
+        let mut tx = Escrow::spend();
+        tx.sign_multisig_input(0, &escrow.keys[1], SigHashType::ALL)
+            .unwrap();
+        assert_eq!(
+            tx.verify_against_utxos(escrow.lookup()),
+            Err(invalid("input 0 has 1 of 2 required signatures"))
+        );
+
+        // The same key signing twice does not count as two signers.
+        tx.sign_multisig_input(0, &escrow.keys[1], SigHashType::ALL)
+            .unwrap();
+        assert_eq!(
+            tx.verify_against_utxos(escrow.lookup()),
+            Err(invalid("input 0 has an invalid signature"))
+        );
+
+        let mut outsider = Escrow::spend();
+        outsider
+            .sign_multisig_input(0, &escrow.keys[0], SigHashType::ALL)
+            .unwrap();
+        outsider
+            .sign_multisig_input(0, &PrivateKey::generate(), SigHashType::ALL)
+            .unwrap();
+        assert!(outsider.verify_against_utxos(escrow.lookup()).is_err());
+    }
+
+    #[test]
+    fn policies_must_be_satisfiable() {
+        let keys: Vec<_> = (0..3)
+            .map(|_| PrivateKey::generate().public_key())
+            .collect();
+        let ctx = ValidationContext::default();
+        assert!(TxOutput::multisig(Amount::new(1), 3, keys.clone())
+            .validate(&ctx)
+            .is_ok());
+        assert!(TxOutput::multisig(Amount::new(1), 0, keys.clone())
+            .validate(&ctx)
+            .is_err());
+        assert!(TxOutput::multisig(Amount::new(1), 4, keys.clone())
+            .validate(&ctx)
+            .is_err());
+
+        let repeated = vec![keys[0], keys[0]];
+        assert_eq!(
+            TxOutput::multisig(Amount::new(1), 1, repeated).validate(&ctx),
+            Err(invalid("multisig lists a key twice"))
+        );
+        let too_many = vec![keys[0]; MAX_MULTISIG_KEYS + 1];
+        assert!(TxOutput::multisig(Amount::new(1), 1, too_many)
+            .validate(&ctx)
+            .is_err());
+    }
+}
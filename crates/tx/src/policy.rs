@@ -0,0 +1,118 @@
+//! Relay policy: checks a node applies before accepting a transaction
+//! from the network, stricter than what consensus requires.
+
+use horizcoin_primitives::{Amount, HorizError, TxId};
+
+use crate::{invalid, Transaction, TxOutput};
+
+/// Smallest amount a non-coinbase output may carry and still be relayed,
+/// in base units.
+///
+/// This is relay policy, deliberately above the consensus floor in
+/// [`ValidationContext::dust_threshold`](horizcoin_primitives::ValidationContext::dust_threshold):
+/// blocks may still carry smaller outputs.
+pub const DUST_THRESHOLD: Amount = Amount::new(546);
+
+impl Transaction {
+    /// Rejects the transaction if any output is below [`DUST_THRESHOLD`].
+    ///
+    /// Coinbase outputs are exempt, since a block's reward is fixed by
+    /// consensus rather than chosen by the sender.
+    pub fn check_dust(&self) -> Result<(), HorizError> {
+        if self.is_coinbase() {
+            return Ok(());
+        }
+        for (index, output) in self.outputs.iter().enumerate() {
+            if output.amount < DUST_THRESHOLD {
+                return Err(invalid(format!(
+                    "output {index} of {} is below the dust threshold of {DUST_THRESHOLD}",
+                    output.amount
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rejects the transaction if its
+    /// [`fee_rate`](Self::fee_rate) is below `min_fee_rate` per byte.
+    ///
+    /// `utxo_lookup` resolves spent outputs as for [`fee`](Self::fee).
+    pub fn check_min_fee(
+        &self,
+        utxo_lookup: impl Fn(&TxId, u32) -> Option<TxOutput>,
+        min_fee_rate: Amount,
+    ) -> Result<(), HorizError> {
+        let fee_rate = self.fee_rate(utxo_lookup)?;
+        if fee_rate < min_fee_rate {
+            return Err(invalid(format!(
+                "fee rate of {fee_rate} is below the minimum of {min_fee_rate}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_crypto::PrivateKey;
+
+    use super::*;
+    use crate::TxInput;
+
+    fn spend(amounts: &[u64]) -> Transaction {
+        Transaction::new(
+            vec![TxInput::new(
+                TxId::new([1; 32]),
+                0,
+                PrivateKey::generate().public_key(),
+            )],
+            amounts
+                .iter()
+                .map(|&amount| TxOutput::new(Amount::new(amount), "hz1payee"))
+                .collect(),
+            None,
+        )
+    }
+
+    #[test]
+    fn dust_outputs_are_rejected() {
+        let threshold = DUST_THRESHOLD.as_u64();
+        assert_eq!(spend(&[threshold, threshold + 1]).check_dust(), Ok(()));
+        assert_eq!(
+            spend(&[10_000, threshold - 1]).check_dust(),
+            Err(invalid(format!(
+                "output 1 of {} is below the dust threshold of {threshold}",
+                threshold - 1
+            )))
+        );
+
+        let coinbase = Transaction::coinbase(vec![TxOutput::new(Amount::new(1), "hz1miner")], None);
+        assert_eq!(coinbase.check_dust(), Ok(()));
+    }
+
+    #[test]
+    fn fee_rate_must_reach_the_minimum() {
+        let tx = spend(&[10_000]);
+        let size = u64::try_from(tx.serialized_size().unwrap()).unwrap();
+        let funded_with = |input: u64| {
+            move |_: &TxId, _: u32| Some(TxOutput::new(Amount::new(input), "hz1owner"))
+        };
+
+        // Exactly two units per byte.
+        let lookup = funded_with(10_000 + 2 * size);
+        assert_eq!(tx.check_min_fee(lookup, Amount::new(2)), Ok(()));
+        assert_eq!(
+            tx.check_min_fee(lookup, Amount::new(3)),
+            Err(invalid("fee rate of 2 is below the minimum of 3"))
+        );
+
+        // One unit short of two per byte rounds down to one.
+        let short = funded_with(10_000 + 2 * size - 1);
+        assert!(tx.check_min_fee(short, Amount::new(2)).is_err());
+        assert_eq!(tx.check_min_fee(short, Amount::new(1)), Ok(()));
+
+        assert!(tx
+            .check_min_fee(|_: &TxId, _: u32| None, Amount::ZERO)
+            .is_err());
+    }
+}
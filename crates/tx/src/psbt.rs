@@ -0,0 +1,200 @@
+//! Transactions passed between signers while signatures are collected.
+
+use horizcoin_crypto::{hash160, parse_address, PublicKey};
+use horizcoin_primitives::HorizError;
+use serde::{Deserialize, Serialize};
+
+use crate::{invalid, OutputKind, Transaction, TxOutput};
+
+/// An unsigned [`Transaction`] together with the outputs its inputs spend
+/// and the signatures gathered so far.
+///
+/// Each signer checks the spent amounts and addresses, signs the inputs it
+/// owns and passes the encoded value on. Once every input is signed,
+/// [`finalize`](Self::finalize) yields the broadcastable transaction. Only
+/// single-key inputs can be collected this way.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartiallySignedTransaction {
+    unsigned: Transaction,
+    spent: Vec<TxOutput>,
+    signatures: Vec<Option<Vec<u8>>>,
+}
+
+impl PartiallySignedTransaction {
+    /// Wraps `tx`, whose input `i` spends `spent[i]`.
+    ///
+    /// Any signatures already on `tx` are discarded. Fails if the lengths
+    /// differ or an input spends a multisig output.
+    pub fn new(mut tx: Transaction, spent: Vec<TxOutput>) -> Result<Self, HorizError> {
+        if spent.len() != tx.inputs.len() {
+            return Err(invalid(format!(
+                "{} spent outputs given for {} inputs",
+                spent.len(),
+                tx.inputs.len()
+            )));
+        }
+        if let Some(index) = spent
+            .iter()
+            .position(|output| output.kind != OutputKind::SingleKey)
+        {
+            return Err(invalid(format!("input {index} spends a multisig output")));
+        }
+        for input in &mut tx.inputs {
+            input.signature.clear();
+        }
+        let signatures = vec![None; tx.inputs.len()];
+        Ok(Self {
+            unsigned: tx,
+            spent,
+            signatures,
+        })
+    }
+
+    /// Returns the transaction being signed, without signatures.
+    #[must_use]
+    pub const fn unsigned_tx(&self) -> &Transaction {
+        &self.unsigned
+    }
+
+    /// Returns the outputs spent by each input, in input order.
+    #[must_use]
+    pub fn spent_outputs(&self) -> &[TxOutput] {
+        &self.spent
+    }
+
+    /// Records `signature` for input `input_index`.
+    ///
+    /// `public_key` must be the input's key and own the spent output, and
+    /// `signature` must be valid for it, sighash type byte included, so a
+    /// bad contribution is caught when it is added rather than at
+    /// [`finalize`](Self::finalize).
+    pub fn add_signature(
+        &mut self,
+        input_index: usize,
+        signature: Vec<u8>,
+        public_key: PublicKey,
+    ) -> Result<(), HorizError> {
+        let input = self
+            .unsigned
+            .inputs
+            .get(input_index)
+            .ok_or_else(|| invalid(format!("no input {input_index}")))?;
+        if input.public_key != Some(public_key) {
+            return Err(invalid(format!("key does not match input {input_index}")));
+        }
+        let (_, owner) = parse_address(&self.spent[input_index].address)?;
+        if owner != hash160(&public_key.to_bytes()) {
+            return Err(invalid(format!(
+                "input {input_index} key does not own {}",
+                self.spent[input_index].address
+            )));
+        }
+
+        // Signatures never commit to each other, so checking against a copy
+        // holding only this one is enough.
+        let mut candidate = self.unsigned.clone();
+        candidate.inputs[input_index]
+            .signature
+            .clone_from(&signature);
+        candidate.verify_single_key(input_index)?;
+
+        self.signatures[input_index] = Some(signature);
+        Ok(())
+    }
+
+    /// Returns `true` once every input has a signature.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.signatures.iter().all(Option::is_some)
+    }
+
+    /// Returns the signed transaction, failing if any input is unsigned.
+    pub fn finalize(self) -> Result<Transaction, HorizError> {
+        let mut tx = self.unsigned;
+        for (index, (input, signature)) in tx.inputs.iter_mut().zip(self.signatures).enumerate() {
+            input.signature =
+                signature.ok_or_else(|| invalid(format!("input {index} is not signed")))?;
+        }
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_crypto::PrivateKey;
+    use horizcoin_primitives::{Amount, TxId};
+
+    use super::*;
+    use crate::{SigHashType, TxInput};
+
+    fn sign(psbt: &PartiallySignedTransaction, index: usize, key: &PrivateKey) -> Vec<u8> {
+        let digest = psbt
+            .unsigned_tx()
+            .signature_hash_for(index, SigHashType::ALL)
+            .unwrap();
+        let mut signature = key.sign_deterministic(digest.as_bytes()).to_vec();
+        signature.push(SigHashType::ALL.to_byte());
+        signature
+    }
+
+    #[test]
+    fn two_signers_complete_a_transaction() {
+        let alice = PrivateKey::generate();
+        let bob = PrivateKey::generate();
+        let tx = Transaction::new(
+            vec![
+                TxInput::new(TxId::new([1; 32]), 0, alice.public_key()),
+                TxInput::new(TxId::new([2; 32]), 3, bob.public_key()),
+            ],
+            vec![TxOutput::new(Amount::new(150), "hz1carol")],
+            None,
+        );
+        let spent = vec![
+            TxOutput::new(Amount::new(100), alice.public_key().to_address()),
+            TxOutput::new(Amount::new(60), bob.public_key().to_address()),
+        ];
+        let mut psbt = PartiallySignedTransaction::new(tx, spent.clone()).unwrap();
+
+        // Alice signs on her device and forwards the encoding.
+        let alice_sig = sign(&psbt, 0, &alice);
+        assert!(psbt
+            .add_signature(0, alice_sig.clone(), bob.public_key())
+            .is_err());
+        psbt.add_signature(0, alice_sig, alice.public_key())
+            .unwrap();
+        assert!(!psbt.is_complete());
+        assert!(psbt.clone().finalize().is_err());
+        let bytes = horizcoin_codec::encode(&psbt).unwrap();
+
+        // Bob decodes it, rejects a forged signature and adds his own.
+        let mut psbt: PartiallySignedTransaction = horizcoin_codec::decode_exact(&bytes).unwrap();
+        let forged = sign(&psbt, 1, &alice);
+        assert!(psbt.add_signature(1, forged, bob.public_key()).is_err());
+        let bob_sig = sign(&psbt, 1, &bob);
+        psbt.add_signature(1, bob_sig, bob.public_key()).unwrap();
+        assert!(psbt.is_complete());
+
+        let tx = psbt.finalize().unwrap();
+        let lookup = |prev: &TxId, _: u32| {
+            Some(if *prev == TxId::new([1; 32]) {
+                spent[0].clone()
+            } else {
+                spent[1].clone()
+            })
+        };
+        assert_eq!(tx.verify_against_utxos(lookup), Ok(()));
+    }
+
+    #[test]
+    fn new_checks_spent_outputs() {
+        let alice = PrivateKey::generate();
+        let tx = Transaction::new(
+            vec![TxInput::new(TxId::new([1; 32]), 0, alice.public_key())],
+            vec![TxOutput::new(Amount::new(10), "hz1carol")],
+            None,
+        );
+        assert!(PartiallySignedTransaction::new(tx.clone(), vec![]).is_err());
+        let escrow = TxOutput::multisig(Amount::new(10), 1, vec![alice.public_key()]);
+        assert!(PartiallySignedTransaction::new(tx, vec![escrow]).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+//! Choosing which unspent outputs fund a transaction.
+
+use std::cmp::Reverse;
+
+use horizcoin_primitives::{Amount, HorizError, TxId};
+
+/// Estimated encoded size of a signed single-key input, in bytes.
+pub const ESTIMATED_INPUT_SIZE: usize = 155;
+
+/// Estimated encoded size of a transaction with no inputs, two outputs to
+/// standard addresses (payment and change) and no memo, in bytes.
+pub const ESTIMATED_BASE_SIZE: usize = 151;
+
+/// The outcome of [`coin_select`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoinSelection {
+    /// Chosen outputs as `(txid, output_index, amount)`, largest first.
+    pub selected: Vec<(TxId, u32, Amount)>,
+    /// Fee for a transaction spending `selected`.
+    pub fee: Amount,
+    /// Amount left over after the target and fee; `0` on an exact match.
+    pub change: Amount,
+}
+
+/// Picks outputs from `utxos` to pay `target` plus the fee, largest first.
+///
+/// The fee is `fee_rate` per byte of a transaction sized from
+/// [`ESTIMATED_BASE_SIZE`] and [`ESTIMATED_INPUT_SIZE`], so it grows with
+/// every input taken. Fails with [`HorizError::InsufficientFunds`] if all
+/// of `utxos` together cannot cover the target and the fee for spending
+/// them all.
+pub fn coin_select(
+    utxos: &[(TxId, u32, Amount)],
+    target: Amount,
+    fee_rate: Amount,
+) -> Result<CoinSelection, HorizError> {
+    let mut candidates = utxos.to_vec();
+    candidates.sort_by_key(|&(_, _, amount)| Reverse(amount));
+
+    let mut selected = Vec::new();
+    let mut total = Amount::ZERO;
+    for utxo in candidates {
+        total = total.saturating_add(utxo.2);
+        selected.push(utxo);
+        let fee = estimate_fee(selected.len(), fee_rate);
+        if let Ok(change) = total.checked_sub(target.saturating_add(fee)) {
+            return Ok(CoinSelection {
+                selected,
+                fee,
+                change,
+            });
+        }
+    }
+    Err(HorizError::InsufficientFunds {
+        required: target.saturating_add(estimate_fee(utxos.len(), fee_rate)),
+        available: total,
+    })
+}
+
+fn estimate_fee(inputs: usize, fee_rate: Amount) -> Amount {
+    let size = ESTIMATED_BASE_SIZE.saturating_add(inputs.saturating_mul(ESTIMATED_INPUT_SIZE));
+    Amount::new(
+        u64::try_from(size)
+            .unwrap_or(u64::MAX)
+            .saturating_mul(fee_rate.as_u64()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_crypto::PrivateKey;
+
+    use super::*;
+    use crate::{Transaction, TxInput, TxOutput};
+
+    fn utxo(byte: u8, amount: u64) -> (TxId, u32, Amount) {
+        (TxId::new([byte; 32]), 0, Amount::new(amount))
+    }
+
+    #[test]
+    fn size_estimates_match_encoding() {
+        let key = PrivateKey::generate();
+        let address = key.public_key().to_address();
+        let mut tx = Transaction::new(
+            vec![],
+            vec![
+                TxOutput::new(Amount::new(1), &address),
+                TxOutput::new(Amount::new(2), &address),
+            ],
+            None,
+        );
+        assert_eq!(
+            horizcoin_codec::encode(&tx).unwrap().len(),
+            ESTIMATED_BASE_SIZE
+        );
+
+        tx.inputs
+            .push(TxInput::new(TxId::new([1; 32]), 0, key.public_key()));
+        tx.sign_input(0, &key).unwrap();
+        assert_eq!(
+            horizcoin_codec::encode(&tx.inputs[0]).unwrap().len(),
+            ESTIMATED_INPUT_SIZE
+        );
+    }
+
+    #[test]
+    fn exact_match_leaves_no_change() {
+        let rate = Amount::new(1);
+        let fee = estimate_fee(2, rate);
+        let utxos = [utxo(1, 10), utxo(2, 600), utxo(3, 400)];
+        let target = Amount::new(1_000).checked_sub(fee).unwrap();
+        let selection = coin_select(&utxos, target, rate).unwrap();
+        assert_eq!(selection.selected, [utxo(2, 600), utxo(3, 400)]);
+        assert_eq!(selection.fee, fee);
+        assert_eq!(selection.change, Amount::ZERO);
+    }
+
+    #[test]
+    fn surplus_becomes_change() {
+        let utxos = [utxo(1, 300), utxo(2, 5_000), utxo(3, 700)];
+        let rate = Amount::new(2);
+        let selection = coin_select(&utxos, Amount::new(1_000), rate).unwrap();
+        assert_eq!(selection.selected, [utxo(2, 5_000)]);
+        assert_eq!(selection.fee, estimate_fee(1, rate));
+        assert_eq!(
+            selection.change.as_u64(),
+            5_000 - 1_000 - selection.fee.as_u64()
+        );
+
+        // With no fee the largest coin alone still wins.
+        let free = coin_select(&utxos, Amount::new(5_000), Amount::ZERO).unwrap();
+        assert_eq!((free.selected.len(), free.change), (1, Amount::ZERO));
+    }
+
+    #[test]
+    fn insufficient_funds_is_an_error() {
+        let utxos = [utxo(1, 300), utxo(2, 700)];
+        let target = Amount::new(1_000);
+        assert!(coin_select(&utxos, target, Amount::ZERO).is_ok());
+        let rate = Amount::new(1);
+        let err = coin_select(&utxos, target, rate).unwrap_err();
+        assert_eq!(
+            err,
+            HorizError::InsufficientFunds {
+                required: target.checked_add(estimate_fee(2, rate)).unwrap(),
+                available: target,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "insufficient funds: 1461 required, 1000 available"
+        );
+        assert_eq!(
+            coin_select(&[], Amount::new(1), Amount::ZERO),
+            Err(HorizError::InsufficientFunds {
+                required: Amount::new(1),
+                available: Amount::ZERO,
+            })
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Signature-hash modes: which parts of a transaction a signature commits
+//! to.
+
+use horizcoin_crypto::{double_sha256, PublicKey};
+use horizcoin_primitives::{Hash, HorizError, TxId};
+use serde::{Deserialize, Serialize};
+
+use crate::{invalid, Transaction, TxInput, TxOutput};
+
+/// Which outputs a signature commits to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SigHashMode {
+    /// Every output.
+    All,
+    /// No outputs; anyone may redirect the funds.
+    None,
+    /// Only the output at the same index as the signed input.
+    Single,
+}
+
+/// How much of a transaction a signature commits to.
+///
+/// Every mode commits to the memo. Signatures themselves are never
+/// committed to, so inputs can be signed in any order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SigHashType {
+    /// Which outputs are committed to.
+    pub mode: SigHashMode,
+    /// If set, only the signed input is committed to, so others may add
+    /// inputs of their own.
+    pub anyone_can_pay: bool,
+}
+
+impl SigHashType {
+    /// Commits to every input and output.
+    pub const ALL: Self = Self::new(SigHashMode::All);
+    /// Commits to every input and no outputs.
+    pub const NONE: Self = Self::new(SigHashMode::None);
+    /// Commits to every input and the output matching the signed input.
+    pub const SINGLE: Self = Self::new(SigHashMode::Single);
+
+    const ANYONE_CAN_PAY_FLAG: u8 = 0x80;
+
+    const fn new(mode: SigHashMode) -> Self {
+        Self {
+            mode,
+            anyone_can_pay: false,
+        }
+    }
+
+    /// Returns this mode with the `AnyoneCanPay` flag set.
+    #[must_use]
+    pub const fn with_anyone_can_pay(self) -> Self {
+        Self {
+            anyone_can_pay: true,
+            ..self
+        }
+    }
+
+    /// Returns the byte appended to signatures made with this type.
+    #[must_use]
+    pub const fn to_byte(self) -> u8 {
+        let mode = match self.mode {
+            SigHashMode::All => 1,
+            SigHashMode::None => 2,
+            SigHashMode::Single => 3,
+        };
+        if self.anyone_can_pay {
+            mode | Self::ANYONE_CAN_PAY_FLAG
+        } else {
+            mode
+        }
+    }
+
+    /// Parses the byte produced by [`to_byte`](Self::to_byte).
+    pub fn from_byte(byte: u8) -> Result<Self, HorizError> {
+        let mode = match byte & !Self::ANYONE_CAN_PAY_FLAG {
+            1 => SigHashMode::All,
+            2 => SigHashMode::None,
+            3 => SigHashMode::Single,
+            _ => return Err(invalid(format!("unknown sighash type {byte:#04x}"))),
+        };
+        Ok(Self {
+            mode,
+            anyone_can_pay: byte & Self::ANYONE_CAN_PAY_FLAG != 0,
+        })
+    }
+}
+
+impl Default for SigHashType {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// The fields a signature commits to, encoded and double-hashed.
+#[derive(Serialize)]
+struct Commitment<'a> {
+    sighash_type: u8,
+    inputs: Vec<(&'a TxId, u32, u32, Option<&'a PublicKey>)>,
+    outputs: &'a [TxOutput],
+    memo: Option<&'a str>,
+    lock_time: u64,
+}
+
+impl Transaction {
+    /// Returns the digest input `input_index` signs under `sighash_type`.
+    ///
+    /// `Single` fails if there is no output at `input_index`.
+    pub fn signature_hash_for(
+        &self,
+        input_index: usize,
+        sighash_type: SigHashType,
+    ) -> Result<Hash, HorizError> {
+        let input = self
+            .inputs
+            .get(input_index)
+            .ok_or_else(|| invalid(format!("no input {input_index}")))?;
+        let inputs = if sighash_type.anyone_can_pay {
+            std::slice::from_ref(input)
+        } else {
+            &self.inputs
+        };
+        let outputs = match sighash_type.mode {
+            SigHashMode::All => &self.outputs[..],
+            SigHashMode::None => &[],
+            SigHashMode::Single => self
+                .outputs
+                .get(input_index..=input_index)
+                .ok_or_else(|| invalid(format!("no output matches input {input_index}")))?,
+        };
+        commit(sighash_type, inputs, outputs, self)
+    }
+
+    /// Returns the [`SigHashType::ALL`] digest, which is the same for every
+    /// input.
+    pub fn signature_hash(&self) -> Result<Hash, HorizError> {
+        commit(SigHashType::ALL, &self.inputs, &self.outputs, self)
+    }
+}
+
+fn commit(
+    sighash_type: SigHashType,
+    inputs: &[TxInput],
+    outputs: &[TxOutput],
+    tx: &Transaction,
+) -> Result<Hash, HorizError> {
+    let commitment = Commitment {
+        sighash_type: sighash_type.to_byte(),
+        inputs: inputs
+            .iter()
+            .map(|input| {
+                (
+                    &input.prev_tx,
+                    input.output_index,
+                    input.sequence,
+                    input.public_key.as_ref(),
+                )
+            })
+            .collect(),
+        outputs,
+        memo: tx.memo.as_deref(),
+        lock_time: tx.lock_time,
+    };
+    horizcoin_codec::encode(&commitment).map(|bytes| double_sha256(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use horizcoin_crypto::PrivateKey;
+    use horizcoin_primitives::Amount;
+
+    use super::*;
+
+    fn two_by_two(alice: &PrivateKey, bob: &PrivateKey) -> Transaction {
+        Transaction::new(
+            vec![
+                TxInput::new(TxId::new([1; 32]), 0, alice.public_key()),
+                TxInput::new(TxId::new([2; 32]), 0, bob.public_key()),
+            ],
+            vec![
+                TxOutput::new(Amount::new(70), "hz1alice"),
+                TxOutput::new(Amount::new(30), "hz1bob"),
+            ],
+            None,
+        )
+    }
+
+    #[test]
+    fn type_byte_round_trips() {
+        for mode in [SigHashMode::All, SigHashMode::None, SigHashMode::Single] {
+            let plain = SigHashType::new(mode);
+            for ty in [plain, plain.with_anyone_can_pay()] {
+                assert_eq!(SigHashType::from_byte(ty.to_byte()), Ok(ty));
+            }
+        }
+        assert!(SigHashType::from_byte(0).is_err());
+        assert!(SigHashType::from_byte(0x84).is_err());
+    }
+
+    #[test]
+    fn single_commits_only_to_matching_output() {
+        let alice = PrivateKey::generate();
+        let bob = PrivateKey::generate();
+        let tx = two_by_two(&alice, &bob);
+        let digest = tx.signature_hash_for(1, SigHashType::SINGLE).unwrap();
+
+        let mut other_output = tx.clone();
+        other_output.outputs[0].amount = Amount::new(1);
+        assert_eq!(
+            other_output.signature_hash_for(1, SigHashType::SINGLE),
+            Ok(digest)
+        );
+        assert_ne!(
+            other_output.signature_hash_for(1, SigHashType::ALL),
+            tx.signature_hash_for(1, SigHashType::ALL)
+        );
+
+        let mut own_output = tx.clone();
+        own_output.outputs[1].amount = Amount::new(31);
+        assert_ne!(
+            own_output.signature_hash_for(1, SigHashType::SINGLE),
+            Ok(digest)
+        );
+
+        let mut short = tx;
+        short.outputs.truncate(1);
+        assert!(short.signature_hash_for(1, SigHashType::SINGLE).is_err());
+    }
+
+    #[test]
+    fn signature_modes_survive_the_edits_they_allow() {
+        let alice = PrivateKey::generate();
+        let bob = PrivateKey::generate();
+        let mut tx = two_by_two(&alice, &bob);
+        let single_acp = SigHashType::SINGLE.with_anyone_can_pay();
+        tx.sign_input_with(0, &alice, single_acp).unwrap();
+        tx.sign_input_with(1, &bob, SigHashType::NONE).unwrap();
+        assert_eq!(tx.verify_signatures(), Ok(()));
+
+        // Bob's NONE signature lets the second output change; Alice's
+        // SINGLE signature only covers the first.
+        tx.outputs[1].address = "hz1carol".into();
+        assert_eq!(tx.verify_signatures(), Ok(()));
+        tx.outputs[0].amount = Amount::new(69);
+        assert!(tx.verify_signatures().is_err());
+        tx.outputs[0].amount = Amount::new(70);
+
+        // Adding an input breaks Bob's signature but not Alice's.
+        let carol = PrivateKey::generate();
+        tx.inputs
+            .push(TxInput::new(TxId::new([3; 32]), 0, carol.public_key()));
+        tx.sign_input(2, &carol).unwrap();
+        assert_eq!(
+            tx.verify_signatures(),
+            Err(invalid("input 1 has an invalid signature"))
+        );
+        tx.sign_input_with(1, &bob, SigHashType::NONE).unwrap();
+        assert_eq!(tx.verify_signatures(), Ok(()));
+    }
+}
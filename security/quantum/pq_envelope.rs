@@ -3,11 +3,20 @@
 //! High-level flow:
 //! - Sender: encapsulate to recipient Kyber public key -> (kem_ciphertext, shared_secret)
 //! - Derive an AEAD key via HKDF from the shared_secret (and optional salt/info)
-//! - Encrypt payload with AES-256-GCM using derived key and a random nonce
-//! - Store/send: kem_ciphertext || nonce || ciphertext
+//! - Encrypt payload with AES-256-GCM using derived key and a random nonce, authenticating
+//!   caller-supplied associated data (AAD) alongside it
+//! - Store/send: version || level || kem_len || kem_ciphertext || nonce || ct_len || ciphertext
 //!
 //! - Recipient: decapsulate using Kyber secret key -> shared_secret
-//! - Derive AEAD key via same HKDF parameters -> decrypt ciphertext
+//! - Derive AEAD key via same HKDF parameters -> decrypt ciphertext with the same AAD
+//!
+//! Large payloads can instead be streamed with [`hybrid_encrypt_stream`] /
+//! [`hybrid_decrypt_stream`]: one encapsulation, then AES-256-GCM over fixed-size chunks
+//! whose nonces count up from zero, so neither side holds the whole payload in memory.
+//!
+//! The Kyber parameter set is chosen per call through [`KyberLevel`]; the
+//! level travels with the envelope so the recipient can check it matches
+//! their key.
 //!
 //! Notes:
 //! - This example uses the pqcrypto-kyber crate API. If your pqcrypto version has different
@@ -15,24 +24,230 @@
 //! - In production: ensure recipient public keys are validated and authenticated, protect secret keys in an HSM,
 //!   and store ciphertext metadata in your metadata store (encrypted_keys table).
 
-use anyhow::Context;
+use std::io::{ErrorKind, Read, Write};
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
 use aes_gcm::{Aes256Gcm, Key, Nonce}; // 96-bit nonce (12 bytes)
-use aes_gcm::aead::{Aead, NewAead};
+use anyhow::{anyhow, bail, Context};
 use hkdf::Hkdf;
 use sha2::Sha256;
+use thiserror::Error;
 use zeroize::Zeroize;
 
-use pqcrypto_kyber::kyber512; // adjust to kyber level you choose (e.g., kyber768/kyber1024)
-use pqcrypto_kyber::kyber512::{PublicKey, SecretKey};
+use pqcrypto_kyber::{kyber1024, kyber512, kyber768};
+use pqcrypto_traits::kem::{Ciphertext as _, SharedSecret as _};
 
 use getrandom::getrandom;
 
 /// Lengths
 const AES_KEY_LEN: usize = 32; // AES-256
 const AES_NONCE_LEN: usize = 12;
+const AES_TAG_LEN: usize = 16;
+
+/// Serialization format version written as the first byte of `to_bytes`.
+///
+/// Version 2 added the Kyber level byte; version 3 the ciphertext length prefix.
+pub const ENVELOPE_VERSION: u8 = 3;
+
+/// Size of the fixed header: version byte + level byte + 4-byte big-endian KEM ciphertext length.
+const HEADER_LEN: usize = 1 + 1 + 4;
+
+/// Size of each big-endian length prefix.
+const LEN_PREFIX_LEN: usize = 4;
+
+/// HKDF info for the one-shot envelope key.
+const ENVELOPE_KEY_INFO: &[u8] = b"pq-envelope-aes-key";
+
+/// HKDF info for the streaming key, kept distinct so a stream key never seals an envelope.
+const STREAM_KEY_INFO: &[u8] = b"pq-envelope-stream-aes-key";
+
+/// Format version written as the first byte of a stream from [`hybrid_encrypt_stream`].
+pub const STREAM_VERSION: u8 = 1;
+
+/// Plaintext bytes per chunk of a stream.
+pub const STREAM_CHUNK_LEN: usize = 64 * 1024;
+
+/// Kyber parameter sets, from fastest to most conservative.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum KyberLevel {
+    /// Kyber512, roughly AES-128 strength.
+    #[default]
+    Kyber512,
+    /// Kyber768, roughly AES-192 strength.
+    Kyber768,
+    /// Kyber1024, roughly AES-256 strength.
+    Kyber1024,
+}
+
+impl KyberLevel {
+    /// All levels, in declaration order.
+    pub const ALL: [Self; 3] = [Self::Kyber512, Self::Kyber768, Self::Kyber1024];
+
+    /// The byte identifying this level in a serialized envelope.
+    pub const fn tag(self) -> u8 {
+        match self {
+            Self::Kyber512 => 1,
+            Self::Kyber768 => 2,
+            Self::Kyber1024 => 3,
+        }
+    }
+
+    /// Length of a KEM ciphertext at this level; every envelope at this level carries
+    /// exactly this many KEM bytes.
+    pub const fn ciphertext_len(self) -> usize {
+        match self {
+            Self::Kyber512 => kyber512::ciphertext_bytes(),
+            Self::Kyber768 => kyber768::ciphertext_bytes(),
+            Self::Kyber1024 => kyber1024::ciphertext_bytes(),
+        }
+    }
+
+    /// Returns the level whose tag is `tag`.
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|level| level.tag() == tag)
+    }
+
+    /// Generates a recipient keypair at this level.
+    pub fn keypair(self) -> (KemPublicKey, KemSecretKey) {
+        match self {
+            Self::Kyber512 => {
+                let (pk, sk) = kyber512::keypair();
+                (KemPublicKey::Kyber512(pk), KemSecretKey::Kyber512(sk))
+            }
+            Self::Kyber768 => {
+                let (pk, sk) = kyber768::keypair();
+                (KemPublicKey::Kyber768(pk), KemSecretKey::Kyber768(sk))
+            }
+            Self::Kyber1024 => {
+                let (pk, sk) = kyber1024::keypair();
+                (KemPublicKey::Kyber1024(pk), KemSecretKey::Kyber1024(sk))
+            }
+        }
+    }
+}
+
+/// A recipient's Kyber public key at any [`KyberLevel`].
+// Keys are plain byte arrays, so they stay `Copy` rather than boxed.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Copy)]
+pub enum KemPublicKey {
+    Kyber512(kyber512::PublicKey),
+    Kyber768(kyber768::PublicKey),
+    Kyber1024(kyber1024::PublicKey),
+}
+
+impl KemPublicKey {
+    /// The parameter set this key belongs to.
+    pub const fn level(&self) -> KyberLevel {
+        match self {
+            Self::Kyber512(_) => KyberLevel::Kyber512,
+            Self::Kyber768(_) => KyberLevel::Kyber768,
+            Self::Kyber1024(_) => KyberLevel::Kyber1024,
+        }
+    }
+
+    /// Encapsulates to this key, returning `(shared_secret, kem_ciphertext)`.
+    fn encapsulate(&self) -> (Vec<u8>, Vec<u8>) {
+        match self {
+            Self::Kyber512(pk) => {
+                let (ss, ct) = kyber512::encapsulate(pk);
+                (ss.as_bytes().to_vec(), ct.as_bytes().to_vec())
+            }
+            Self::Kyber768(pk) => {
+                let (ss, ct) = kyber768::encapsulate(pk);
+                (ss.as_bytes().to_vec(), ct.as_bytes().to_vec())
+            }
+            Self::Kyber1024(pk) => {
+                let (ss, ct) = kyber1024::encapsulate(pk);
+                (ss.as_bytes().to_vec(), ct.as_bytes().to_vec())
+            }
+        }
+    }
+}
+
+/// A recipient's Kyber secret key at any [`KyberLevel`].
+// Keys are plain byte arrays, so they stay `Copy` rather than boxed.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Copy)]
+pub enum KemSecretKey {
+    Kyber512(kyber512::SecretKey),
+    Kyber768(kyber768::SecretKey),
+    Kyber1024(kyber1024::SecretKey),
+}
+
+impl KemSecretKey {
+    /// The parameter set this key belongs to.
+    pub const fn level(&self) -> KyberLevel {
+        match self {
+            Self::Kyber512(_) => KyberLevel::Kyber512,
+            Self::Kyber768(_) => KyberLevel::Kyber768,
+            Self::Kyber1024(_) => KyberLevel::Kyber1024,
+        }
+    }
+
+    /// Decapsulates `kem_ciphertext` with this key, returning the shared secret.
+    fn decapsulate(&self, kem_ciphertext: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let ss = match self {
+            Self::Kyber512(sk) => {
+                let ct = kyber512::Ciphertext::from_bytes(kem_ciphertext)
+                    .context("Failed to reconstruct KEM ciphertext from bytes")?;
+                kyber512::decapsulate(&ct, sk).as_bytes().to_vec()
+            }
+            Self::Kyber768(sk) => {
+                let ct = kyber768::Ciphertext::from_bytes(kem_ciphertext)
+                    .context("Failed to reconstruct KEM ciphertext from bytes")?;
+                kyber768::decapsulate(&ct, sk).as_bytes().to_vec()
+            }
+            Self::Kyber1024(sk) => {
+                let ct = kyber1024::Ciphertext::from_bytes(kem_ciphertext)
+                    .context("Failed to reconstruct KEM ciphertext from bytes")?;
+                kyber1024::decapsulate(&ct, sk).as_bytes().to_vec()
+            }
+        };
+        Ok(ss)
+    }
+}
+
+/// Errors produced while parsing a serialized [`HybridCipher`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EnvelopeError {
+    /// The buffer ended before a fixed-size field could be read.
+    #[error("envelope truncated: needed {needed} bytes, {available} available")]
+    Truncated { needed: usize, available: usize },
+    /// The version byte is not one this code understands.
+    #[error("unsupported envelope version {0}")]
+    BadVersion(u8),
+    /// The level byte names no known Kyber parameter set.
+    #[error("unknown Kyber level tag {0}")]
+    UnknownLevel(u8),
+    /// The envelope was made for a different Kyber level than the key.
+    #[error("envelope is for {envelope:?} but the key is {key:?}")]
+    LevelMismatch {
+        envelope: KyberLevel,
+        key: KyberLevel,
+    },
+    /// The declared KEM ciphertext length runs past the end of the buffer.
+    #[error("KEM ciphertext length {declared} exceeds remaining {remaining} bytes")]
+    KemLengthOverflow { declared: usize, remaining: usize },
+    /// The KEM ciphertext is not the fixed length of its Kyber level.
+    #[error("{level:?} KEM ciphertext must be {expected} bytes, got {declared}")]
+    KemLengthMismatch {
+        level: KyberLevel,
+        expected: usize,
+        declared: usize,
+    },
+    /// No room is left for the AEAD ciphertext and its authentication tag.
+    #[error("AEAD ciphertext missing")]
+    CiphertextMissing,
+    /// Bytes follow the declared end of the envelope.
+    #[error("{0} trailing bytes after envelope")]
+    TrailingBytes(usize),
+}
 
 #[derive(Debug, Clone)]
 pub struct HybridCipher {
+    /// Kyber parameter set the KEM ciphertext was produced with
+    pub level: KyberLevel,
     /// KEM encapsulated ciphertext (Kyber ciphertext)
     pub kem_ciphertext: Vec<u8>,
     /// AES-GCM nonce (12 bytes)
@@ -42,91 +257,656 @@ pub struct HybridCipher {
 }
 
 impl HybridCipher {
+    /// Serialize as `version || level || kem_len (u32 BE) || kem_ciphertext || nonce ||
+    /// ct_len (u32 BE) || ciphertext`.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut out = Vec::new();
-        // lengths are implicit here; for transport formats you may prefix lengths or use a structured format
+        let kem_len =
+            u32::try_from(self.kem_ciphertext.len()).expect("KEM ciphertext length fits in u32");
+        let ct_len = u32::try_from(self.ciphertext.len()).expect("ciphertext length fits in u32");
+        let mut out = Vec::with_capacity(
+            HEADER_LEN
+                + self.kem_ciphertext.len()
+                + AES_NONCE_LEN
+                + LEN_PREFIX_LEN
+                + self.ciphertext.len(),
+        );
+        out.push(ENVELOPE_VERSION);
+        out.push(self.level.tag());
+        out.extend_from_slice(&kem_len.to_be_bytes());
         out.extend_from_slice(&self.kem_ciphertext);
         out.extend_from_slice(&self.nonce);
+        out.extend_from_slice(&ct_len.to_be_bytes());
         out.extend_from_slice(&self.ciphertext);
         out
     }
+
+    /// Parse the format produced by [`HybridCipher::to_bytes`].
+    ///
+    /// Every length is checked before slicing, so malformed input from a peer or
+    /// from storage yields an [`EnvelopeError`] instead of a panic. The KEM
+    /// ciphertext must have its level's fixed length, and the buffer must end
+    /// exactly where the declared ciphertext does, so truncation anywhere is caught
+    /// here rather than surfacing as an AEAD failure.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EnvelopeError> {
+        let header = bytes.get(..HEADER_LEN).ok_or(EnvelopeError::Truncated {
+            needed: HEADER_LEN,
+            available: bytes.len(),
+        })?;
+        if header[0] != ENVELOPE_VERSION {
+            return Err(EnvelopeError::BadVersion(header[0]));
+        }
+        let level =
+            KyberLevel::from_tag(header[1]).ok_or(EnvelopeError::UnknownLevel(header[1]))?;
+        let declared = u32::from_be_bytes([header[2], header[3], header[4], header[5]]) as usize;
+        let rest = &bytes[HEADER_LEN..];
+
+        let kem_ciphertext = rest
+            .get(..declared)
+            .ok_or(EnvelopeError::KemLengthOverflow {
+                declared,
+                remaining: rest.len(),
+            })?;
+        if declared != level.ciphertext_len() {
+            return Err(EnvelopeError::KemLengthMismatch {
+                level,
+                expected: level.ciphertext_len(),
+                declared,
+            });
+        }
+        let rest = &rest[declared..];
+
+        let fixed = AES_NONCE_LEN + LEN_PREFIX_LEN;
+        let fixed_bytes = rest.get(..fixed).ok_or(EnvelopeError::Truncated {
+            needed: fixed,
+            available: rest.len(),
+        })?;
+        let mut nonce = [0u8; AES_NONCE_LEN];
+        nonce.copy_from_slice(&fixed_bytes[..AES_NONCE_LEN]);
+        let ct_len = &fixed_bytes[AES_NONCE_LEN..];
+        let ct_len = u32::from_be_bytes([ct_len[0], ct_len[1], ct_len[2], ct_len[3]]) as usize;
+        let rest = &rest[fixed..];
+
+        // Even an empty plaintext carries a full GCM tag.
+        if ct_len < AES_TAG_LEN {
+            return Err(EnvelopeError::CiphertextMissing);
+        }
+        let ciphertext = rest.get(..ct_len).ok_or(EnvelopeError::Truncated {
+            needed: ct_len,
+            available: rest.len(),
+        })?;
+        if rest.len() > ct_len {
+            return Err(EnvelopeError::TrailingBytes(rest.len() - ct_len));
+        }
+
+        Ok(HybridCipher {
+            level,
+            kem_ciphertext: kem_ciphertext.to_vec(),
+            nonce,
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
 }
 
-/// Perform hybrid encryption to a recipient's Kyber public key.
+/// Perform hybrid encryption to a recipient's Kyber public key, at the key's level.
 /// Returns kem ciphertext + AES-GCM nonce + ciphertext.
-pub fn hybrid_encrypt(recipient_pk: &PublicKey, plaintext: &[u8]) -> anyhow::Result<HybridCipher> {
-    // 1) KEM encapsulate: produces ciphertext and shared secret
-    // API: kyber512::encapsulate(&recipient_pk) -> (ciphertext, shared_secret)
-    let (kem_ciphertext, shared_secret) = kyber512::encapsulate(recipient_pk);
-
-    // shared_secret is a byte container — convert to slice
-    let ss_bytes = shared_secret.as_bytes();
+///
+/// `aad` is authenticated but not encrypted or stored: context such as a recipient id
+/// or record version that decryption must be given again, unchanged.
+pub fn hybrid_encrypt(
+    recipient_pk: &KemPublicKey,
+    plaintext: &[u8],
+    aad: &[u8],
+) -> anyhow::Result<HybridCipher> {
+    // 1) KEM encapsulate: produces shared secret and ciphertext
+    let (mut shared_secret, kem_ciphertext) = recipient_pk.encapsulate();
 
     // 2) Derive an AES-256 key via HKDF-SHA256 using the shared secret.
-    let hk = Hkdf::<Sha256>::new(None, ss_bytes);
-    let mut okm = [0u8; AES_KEY_LEN];
-    hk.expand(b"pq-envelope-aes-key", &mut okm)
-        .context("HKDF expand failure")?;
+    let aead = derive_aead(&mut shared_secret, ENVELOPE_KEY_INFO)?;
 
     // 3) Encrypt payload with AES-256-GCM
     // generate nonce
     let mut nonce = [0u8; AES_NONCE_LEN];
-    getrandom(&mut nonce).context("getrandom nonce failed")?;
-    let aead = Aes256Gcm::new(Key::from_slice(&okm));
-    let ct = aead.encrypt(Nonce::from_slice(&nonce), plaintext)
-        .context("AEAD encryption failed")?;
-
-    // zeroize derived key material
-    okm.zeroize();
+    getrandom(&mut nonce).map_err(|e| anyhow!("getrandom nonce failed: {e}"))?;
+    let ct = aead
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| anyhow!("AEAD encryption failed"))?;
 
     Ok(HybridCipher {
-        kem_ciphertext: kem_ciphertext.as_bytes().to_vec(),
+        level: recipient_pk.level(),
+        kem_ciphertext,
         nonce,
         ciphertext: ct,
     })
 }
 
 /// Perform hybrid decryption with recipient Kyber secret key.
-pub fn hybrid_decrypt(recipient_sk: &SecretKey, hc: &HybridCipher) -> anyhow::Result<Vec<u8>> {
-    // Reconstruct Kem ciphertext type accepted by pqcrypto:
-    // The pqcrypto API expects its own ciphertext type. We attempt to create using from_bytes if available.
-    // Many pqcrypto types provide `from_bytes` or `from` conversions. If your version differs you'll need to adapt this conversion.
-    let kem_ct = pqcrypto_kyber::kyber512::Ciphertext::from_bytes(&hc.kem_ciphertext)
-        .context("Failed to reconstruct KEM ciphertext from bytes; adapt to your pqcrypto API")?;
+///
+/// `aad` must equal the associated data given to [`hybrid_encrypt`], or
+/// authentication fails. Fails with [`EnvelopeError::LevelMismatch`] if the
+/// envelope was made for another Kyber level than `recipient_sk`.
+pub fn hybrid_decrypt(
+    recipient_sk: &KemSecretKey,
+    hc: &HybridCipher,
+    aad: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    if hc.level != recipient_sk.level() {
+        return Err(EnvelopeError::LevelMismatch {
+            envelope: hc.level,
+            key: recipient_sk.level(),
+        }
+        .into());
+    }
 
     // 1) Decapsulate -> shared secret
-    let shared_secret = kyber512::decapsulate(&kem_ct, recipient_sk);
-    let ss_bytes = shared_secret.as_bytes();
+    let mut shared_secret = recipient_sk.decapsulate(&hc.kem_ciphertext)?;
 
     // 2) Derive AES-256 key via HKDF-SHA256
-    let hk = Hkdf::<Sha256>::new(None, ss_bytes);
-    let mut okm = [0u8; AES_KEY_LEN];
-    hk.expand(b"pq-envelope-aes-key", &mut okm)
-        .context("HKDF expand failure")?;
+    let aead = derive_aead(&mut shared_secret, ENVELOPE_KEY_INFO)?;
 
     // 3) Decrypt AES-GCM
-    let aead = Aes256Gcm::new(Key::from_slice(&okm));
-    let pt = aead.decrypt(Nonce::from_slice(&hc.nonce), hc.ciphertext.as_ref())
-        .context("AEAD decryption failed")?;
+    aead.decrypt(
+        Nonce::from_slice(&hc.nonce),
+        Payload {
+            msg: &hc.ciphertext,
+            aad,
+        },
+    )
+    .map_err(|_| anyhow!("AEAD decryption failed"))
+}
+
+/// Parse a serialized envelope and decrypt it in one step.
+///
+/// Parsing failures surface as an [`EnvelopeError`] inside the returned error.
+pub fn hybrid_decrypt_bytes(
+    recipient_sk: &KemSecretKey,
+    bytes: &[u8],
+    aad: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let hc = HybridCipher::from_bytes(bytes)?;
+    hybrid_decrypt(recipient_sk, &hc, aad)
+}
+
+/// Encrypt everything `reader` yields to `recipient_pk`, writing the stream to `writer`.
+///
+/// Stream layout: `STREAM_VERSION || level || kem_len (u32 BE) || kem_ciphertext`, then one
+/// record per chunk: `final (u8) || ct_len (u32 BE) || ciphertext`. Every chunk but the last
+/// holds exactly [`STREAM_CHUNK_LEN`] plaintext bytes; the last, possibly empty, is flagged
+/// final. Chunk `i` is sealed under the nonce `i (u64 BE) || 0 0 0 || final`, so chunks cannot
+/// be reordered, dropped or marked final without failing authentication. `aad` is
+/// authenticated with every chunk.
+///
+/// Returns the number of plaintext bytes encrypted.
+pub fn hybrid_encrypt_stream<R: Read, W: Write>(
+    recipient_pk: &KemPublicKey,
+    aad: &[u8],
+    mut reader: R,
+    mut writer: W,
+) -> anyhow::Result<u64> {
+    let (mut shared_secret, kem_ciphertext) = recipient_pk.encapsulate();
+    let aead = derive_aead(&mut shared_secret, STREAM_KEY_INFO)?;
 
+    let kem_len = u32::try_from(kem_ciphertext.len()).expect("KEM ciphertext length fits in u32");
+    writer.write_all(&[STREAM_VERSION, recipient_pk.level().tag()])?;
+    writer.write_all(&kem_len.to_be_bytes())?;
+    writer.write_all(&kem_ciphertext)?;
+
+    let mut total = 0u64;
+    let mut counter = 0u64;
+    let mut chunk = read_chunk(&mut reader)?;
+    loop {
+        // A full chunk is only final if nothing follows it.
+        let next = if chunk.len() == STREAM_CHUNK_LEN {
+            read_chunk(&mut reader)?
+        } else {
+            Vec::new()
+        };
+        let last = chunk.len() < STREAM_CHUNK_LEN || next.is_empty();
+
+        let ct = aead
+            .encrypt(
+                Nonce::from_slice(&chunk_nonce(counter, last)),
+                Payload { msg: &chunk, aad },
+            )
+            .map_err(|_| anyhow!("AEAD encryption failed"))?;
+        let ct_len = u32::try_from(ct.len()).expect("chunk ciphertext length fits in u32");
+        writer.write_all(&[u8::from(last)])?;
+        writer.write_all(&ct_len.to_be_bytes())?;
+        writer.write_all(&ct)?;
+
+        total += chunk.len() as u64;
+        chunk.zeroize();
+        if last {
+            break;
+        }
+        counter += 1;
+        chunk = next;
+    }
+    writer.flush()?;
+    Ok(total)
+}
+
+/// Decrypt a stream produced by [`hybrid_encrypt_stream`] from `reader`, writing the
+/// plaintext to `writer` chunk by chunk.
+///
+/// Each chunk is authenticated before it is written, but truncation of the stream is only
+/// detected when it ends without a final chunk, so on error `writer` may already hold a
+/// prefix of the plaintext and must be discarded. Returns the number of plaintext bytes
+/// written.
+pub fn hybrid_decrypt_stream<R: Read, W: Write>(
+    recipient_sk: &KemSecretKey,
+    aad: &[u8],
+    mut reader: R,
+    mut writer: W,
+) -> anyhow::Result<u64> {
+    let mut header = [0u8; HEADER_LEN];
+    reader
+        .read_exact(&mut header)
+        .context("stream header truncated")?;
+    if header[0] != STREAM_VERSION {
+        return Err(EnvelopeError::BadVersion(header[0]).into());
+    }
+    let level = KyberLevel::from_tag(header[1]).ok_or(EnvelopeError::UnknownLevel(header[1]))?;
+    if level != recipient_sk.level() {
+        return Err(EnvelopeError::LevelMismatch {
+            envelope: level,
+            key: recipient_sk.level(),
+        }
+        .into());
+    }
+    let declared = u32::from_be_bytes([header[2], header[3], header[4], header[5]]) as usize;
+    if declared != level.ciphertext_len() {
+        return Err(EnvelopeError::KemLengthMismatch {
+            level,
+            expected: level.ciphertext_len(),
+            declared,
+        }
+        .into());
+    }
+    let mut kem_ciphertext = vec![0u8; declared];
+    reader
+        .read_exact(&mut kem_ciphertext)
+        .context("KEM ciphertext truncated")?;
+    let mut shared_secret = recipient_sk.decapsulate(&kem_ciphertext)?;
+    let aead = derive_aead(&mut shared_secret, STREAM_KEY_INFO)?;
+
+    let mut total = 0u64;
+    for counter in 0u64.. {
+        let mut record = [0u8; 1 + LEN_PREFIX_LEN];
+        reader
+            .read_exact(&mut record)
+            .context("stream ended before its final chunk")?;
+        let last = match record[0] {
+            0 => false,
+            1 => true,
+            flag => bail!("invalid chunk flag {flag}"),
+        };
+        let ct_len = u32::from_be_bytes([record[1], record[2], record[3], record[4]]) as usize;
+        if !(AES_TAG_LEN..=STREAM_CHUNK_LEN + AES_TAG_LEN).contains(&ct_len) {
+            bail!("chunk ciphertext length {ct_len} out of range");
+        }
+        let mut ct = vec![0u8; ct_len];
+        reader.read_exact(&mut ct).context("chunk truncated")?;
+
+        let mut chunk = aead
+            .decrypt(
+                Nonce::from_slice(&chunk_nonce(counter, last)),
+                Payload { msg: &ct, aad },
+            )
+            .map_err(|_| anyhow!("AEAD decryption of chunk {counter} failed"))?;
+        writer.write_all(&chunk)?;
+        total += chunk.len() as u64;
+        chunk.zeroize();
+        if last {
+            break;
+        }
+    }
+
+    let mut extra = [0u8; 1];
+    if reader.read(&mut extra)? != 0 {
+        bail!("data follows the final chunk");
+    }
+    writer.flush()?;
+    Ok(total)
+}
+
+/// Derive the AES-256-GCM cipher for `info` from `shared_secret`, zeroizing the secret and
+/// the intermediate key bytes.
+fn derive_aead(shared_secret: &mut [u8], info: &[u8]) -> anyhow::Result<Aes256Gcm> {
+    let hk = Hkdf::<Sha256>::new(None, shared_secret);
+    shared_secret.zeroize();
+    let mut okm = [0u8; AES_KEY_LEN];
+    hk.expand(info, &mut okm)
+        .map_err(|_| anyhow!("HKDF expand failure"))?;
+    let aead = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&okm));
     okm.zeroize();
-    Ok(pt)
+    Ok(aead)
+}
+
+/// Nonce for chunk `counter`: the counter, three zero bytes, then the final flag.
+fn chunk_nonce(counter: u64, last: bool) -> [u8; AES_NONCE_LEN] {
+    let mut nonce = [0u8; AES_NONCE_LEN];
+    nonce[..8].copy_from_slice(&counter.to_be_bytes());
+    nonce[AES_NONCE_LEN - 1] = u8::from(last);
+    nonce
+}
+
+/// Read up to [`STREAM_CHUNK_LEN`] bytes, stopping early only at end of input.
+fn read_chunk(reader: &mut impl Read) -> std::io::Result<Vec<u8>> {
+    let mut chunk = vec![0u8; STREAM_CHUNK_LEN];
+    let mut filled = 0;
+    while filled < STREAM_CHUNK_LEN {
+        match reader.read(&mut chunk[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    chunk.truncate(filled);
+    Ok(chunk)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use pqcrypto_kyber::kyber512;
 
     #[test]
     fn encrypt_decrypt_roundtrip() {
         // generate recipient keypair
-        let (pk, sk) = kyber512::keypair();
+        let (pk, sk) = KyberLevel::Kyber512.keypair();
 
         let plaintext = b"Hello post-quantum world!";
-        let hc = hybrid_encrypt(&pk, plaintext).expect("encrypt failed");
-        let decrypted = hybrid_decrypt(&sk, &hc).expect("decrypt failed");
+        let hc = hybrid_encrypt(&pk, plaintext, b"").expect("encrypt failed");
+        let decrypted = hybrid_decrypt(&sk, &hc, b"").expect("decrypt failed");
 
         assert_eq!(plaintext.as_ref(), decrypted.as_slice());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn associated_data_is_authenticated() {
+        let (pk, sk) = KyberLevel::Kyber768.keypair();
+        let hc = hybrid_encrypt(&pk, b"wrapped key", b"recipient=42;v=1").expect("encrypt failed");
+
+        let decrypted = hybrid_decrypt(&sk, &hc, b"recipient=42;v=1").expect("decrypt failed");
+        assert_eq!(decrypted, b"wrapped key");
+
+        assert!(hybrid_decrypt(&sk, &hc, b"recipient=43;v=1").is_err());
+        assert!(hybrid_decrypt(&sk, &hc, b"").is_err());
+        assert!(hybrid_decrypt_bytes(&sk, &hc.to_bytes(), b"recipient=42;v=2").is_err());
+    }
+
+    #[test]
+    fn roundtrip_at_every_level() {
+        for level in KyberLevel::ALL {
+            let (pk, sk) = level.keypair();
+            assert_eq!(pk.level(), level);
+            assert_eq!(sk.level(), level);
+
+            let hc = hybrid_encrypt(&pk, b"level payload", b"").expect("encrypt failed");
+            assert_eq!(hc.level, level);
+            let parsed = HybridCipher::from_bytes(&hc.to_bytes()).expect("parse failed");
+            assert_eq!(parsed.level, level);
+            let decrypted = hybrid_decrypt(&sk, &parsed, b"").expect("decrypt failed");
+            assert_eq!(decrypted, b"level payload");
+        }
+    }
+
+    #[test]
+    fn level_mismatch_fails_cleanly() {
+        let (pk, _) = KyberLevel::Kyber768.keypair();
+        let (_, sk) = KyberLevel::Kyber1024.keypair();
+        let hc = hybrid_encrypt(&pk, b"x", b"").expect("encrypt failed");
+
+        let err = hybrid_decrypt(&sk, &hc, b"").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<EnvelopeError>(),
+            Some(&EnvelopeError::LevelMismatch {
+                envelope: KyberLevel::Kyber768,
+                key: KyberLevel::Kyber1024,
+            })
+        );
+
+        // Relabeling the envelope gets past the check but not the KEM.
+        let mut relabeled = hc;
+        relabeled.level = KyberLevel::Kyber1024;
+        assert!(hybrid_decrypt(&sk, &relabeled, b"").is_err());
+
+        let mut bytes = hybrid_encrypt(&pk, b"x", b"")
+            .expect("encrypt failed")
+            .to_bytes();
+        bytes[1] = 9;
+        assert_eq!(
+            HybridCipher::from_bytes(&bytes).unwrap_err(),
+            EnvelopeError::UnknownLevel(9)
+        );
+    }
+
+    #[test]
+    fn from_bytes_accepts_well_formed_buffer() {
+        let (pk, sk) = KyberLevel::Kyber512.keypair();
+        let hc = hybrid_encrypt(&pk, b"payload", b"").expect("encrypt failed");
+
+        let parsed = HybridCipher::from_bytes(&hc.to_bytes()).expect("parse failed");
+        assert_eq!(parsed.kem_ciphertext, hc.kem_ciphertext);
+        assert_eq!(parsed.nonce, hc.nonce);
+        assert_eq!(parsed.ciphertext, hc.ciphertext);
+
+        let decrypted = hybrid_decrypt_bytes(&sk, &hc.to_bytes(), b"").expect("decrypt failed");
+        assert_eq!(decrypted, b"payload");
+    }
+
+    #[test]
+    fn from_bytes_rejects_truncated_buffer() {
+        let err = HybridCipher::from_bytes(&[ENVELOPE_VERSION, 1, 0]).unwrap_err();
+        assert_eq!(
+            err,
+            EnvelopeError::Truncated {
+                needed: HEADER_LEN,
+                available: 3
+            }
+        );
+    }
+
+    #[test]
+    fn from_bytes_rejects_kem_length_overflow() {
+        let mut bytes = vec![ENVELOPE_VERSION, KyberLevel::Kyber512.tag()];
+        bytes.extend_from_slice(&1000u32.to_be_bytes());
+        bytes.extend_from_slice(&[0u8; 40]);
+        let err = HybridCipher::from_bytes(&bytes).unwrap_err();
+        assert_eq!(
+            err,
+            EnvelopeError::KemLengthOverflow {
+                declared: 1000,
+                remaining: 40
+            }
+        );
+    }
+
+    #[test]
+    fn from_bytes_rejects_bad_version_and_missing_ciphertext() {
+        let (pk, _) = KyberLevel::Kyber512.keypair();
+        let mut bytes = hybrid_encrypt(&pk, b"x", b"")
+            .expect("encrypt failed")
+            .to_bytes();
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[0] = ENVELOPE_VERSION + 1;
+        assert_eq!(
+            HybridCipher::from_bytes(&wrong_version).unwrap_err(),
+            EnvelopeError::BadVersion(ENVELOPE_VERSION + 1)
+        );
+
+        // Declare a ciphertext too short to hold even the GCM tag.
+        bytes.truncate(HEADER_LEN + KyberLevel::Kyber512.ciphertext_len() + AES_NONCE_LEN);
+        bytes.extend_from_slice(&(AES_TAG_LEN as u32 - 1).to_be_bytes());
+        bytes.extend_from_slice(&[0u8; AES_TAG_LEN - 1]);
+        assert_eq!(
+            HybridCipher::from_bytes(&bytes).unwrap_err(),
+            EnvelopeError::CiphertextMissing
+        );
+    }
+
+    /// A reader returning at most `step` bytes per call, to exercise short reads.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.data.len().min(self.step).min(buf.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    fn encrypt_stream(pk: &KemPublicKey, plaintext: &[u8]) -> Vec<u8> {
+        let mut stream = Vec::new();
+        let written = hybrid_encrypt_stream(pk, b"archive", plaintext, &mut stream)
+            .expect("stream encrypt failed");
+        assert_eq!(written, plaintext.len() as u64);
+        stream
+    }
+
+    #[test]
+    fn stream_roundtrip_of_large_payload() {
+        let (pk, sk) = KyberLevel::Kyber768.keypair();
+        // Three full chunks and a partial one, with a pattern that exposes misplaced bytes.
+        let plaintext: Vec<u8> = (0..3 * STREAM_CHUNK_LEN + 12_345)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let big: Vec<u8> = plaintext.iter().copied().cycle().take(4 << 20).collect();
+
+        for payload in [&plaintext, &big] {
+            let stream = encrypt_stream(&pk, payload);
+            let mut decrypted = Vec::new();
+            let read = hybrid_decrypt_stream(
+                &sk,
+                b"archive",
+                Trickle {
+                    data: &stream,
+                    step: 1000,
+                },
+                &mut decrypted,
+            )
+            .expect("stream decrypt failed");
+            assert_eq!(read, payload.len() as u64);
+            assert!(
+                decrypted == *payload,
+                "plaintext corrupted across chunk boundaries"
+            );
+        }
+    }
+
+    #[test]
+    fn stream_handles_chunk_aligned_and_empty_payloads() {
+        let (pk, sk) = KyberLevel::Kyber512.keypair();
+        for len in [0, 1, STREAM_CHUNK_LEN, 2 * STREAM_CHUNK_LEN] {
+            let plaintext = vec![0xA5u8; len];
+            let stream = encrypt_stream(&pk, &plaintext);
+            let mut decrypted = Vec::new();
+            hybrid_decrypt_stream(&sk, b"archive", stream.as_slice(), &mut decrypted)
+                .expect("stream decrypt failed");
+            assert_eq!(decrypted, plaintext);
+        }
+    }
+
+    #[test]
+    fn stream_rejects_tampering() {
+        let (pk, sk) = KyberLevel::Kyber512.keypair();
+        let plaintext = vec![7u8; 2 * STREAM_CHUNK_LEN + 10];
+        let stream = encrypt_stream(&pk, &plaintext);
+        let decrypt =
+            |bytes: &[u8], aad: &[u8]| hybrid_decrypt_stream(&sk, aad, bytes, &mut Vec::new());
+        assert!(decrypt(&stream, b"archive").is_ok());
+        assert!(decrypt(&stream, b"other").is_err());
+
+        // Dropping the final chunk, or anything after the header, is detected.
+        let record_len = 1 + LEN_PREFIX_LEN + STREAM_CHUNK_LEN + AES_TAG_LEN;
+        let header_len = HEADER_LEN + KyberLevel::Kyber512.ciphertext_len();
+        assert!(decrypt(&stream[..header_len + 2 * record_len], b"archive").is_err());
+        assert!(decrypt(&stream[..stream.len() - 1], b"archive").is_err());
+
+        // Marking a middle chunk final changes its nonce.
+        let mut early_end = stream[..header_len + record_len].to_vec();
+        early_end[header_len] = 1;
+        assert!(decrypt(&early_end, b"archive").is_err());
+
+        // Swapping two full chunks breaks the counter.
+        let mut swapped = stream.clone();
+        let (first, second) = (header_len, header_len + record_len);
+        swapped[first..second].copy_from_slice(&stream[second..second + record_len]);
+        swapped[second..second + record_len].copy_from_slice(&stream[first..second]);
+        assert!(decrypt(&swapped, b"archive").is_err());
+
+        let mut trailing = stream;
+        trailing.push(0);
+        assert!(decrypt(&trailing, b"archive").is_err());
+    }
+
+    #[test]
+    fn framed_roundtrip_decrypts() {
+        let (pk, sk) = KyberLevel::Kyber1024.keypair();
+        let hc = hybrid_encrypt(&pk, b"framed payload", b"aad").expect("encrypt failed");
+        let bytes = hc.to_bytes();
+        assert_eq!(
+            bytes.len(),
+            HEADER_LEN
+                + KyberLevel::Kyber1024.ciphertext_len()
+                + AES_NONCE_LEN
+                + LEN_PREFIX_LEN
+                + hc.ciphertext.len()
+        );
+
+        let parsed = HybridCipher::from_bytes(&bytes).expect("parse failed");
+        let decrypted = hybrid_decrypt(&sk, &parsed, b"aad").expect("decrypt failed");
+        assert_eq!(decrypted, b"framed payload");
+    }
+
+    #[test]
+    fn from_bytes_rejects_every_truncation_and_trailing_bytes() {
+        let (pk, _) = KyberLevel::Kyber768.keypair();
+        let bytes = hybrid_encrypt(&pk, b"payload", b"")
+            .expect("encrypt failed")
+            .to_bytes();
+
+        for len in 0..bytes.len() {
+            assert!(
+                HybridCipher::from_bytes(&bytes[..len]).is_err(),
+                "accepted {len} bytes"
+            );
+        }
+        let err = HybridCipher::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert!(matches!(err, EnvelopeError::Truncated { .. }));
+
+        let mut extended = bytes;
+        extended.push(0);
+        assert_eq!(
+            HybridCipher::from_bytes(&extended).unwrap_err(),
+            EnvelopeError::TrailingBytes(1)
+        );
+    }
+
+    #[test]
+    fn from_bytes_rejects_kem_length_for_wrong_level() {
+        let mut bytes = vec![ENVELOPE_VERSION, KyberLevel::Kyber768.tag()];
+        let kem_len = KyberLevel::Kyber512.ciphertext_len();
+        bytes.extend_from_slice(&(kem_len as u32).to_be_bytes());
+        bytes.extend_from_slice(&vec![0u8; kem_len + 64]);
+        assert_eq!(
+            HybridCipher::from_bytes(&bytes).unwrap_err(),
+            EnvelopeError::KemLengthMismatch {
+                level: KyberLevel::Kyber768,
+                expected: KyberLevel::Kyber768.ciphertext_len(),
+                declared: kem_len,
+            }
+        );
+    }
+}